@@ -0,0 +1,558 @@
+// Generated by elea from a Space, do not edit.
+
+/// A state of any machine
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum SpaceState {
+    Broken(broken::State),
+    Default(default::State),
+    Pattern(pattern::State),
+}
+
+impl SpaceState {
+    /// Fully qualified state id
+    pub const fn id(&self) -> &'static str {
+        match self {
+            SpaceState::Broken(state) => state.id(),
+            SpaceState::Default(state) => state.id(),
+            SpaceState::Pattern(state) => state.id(),
+        }
+    }
+}
+
+/// Machine `broken`
+#[allow(non_camel_case_types, clippy::all)]
+pub mod broken {
+
+    #[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+    pub enum State {
+        Fix,
+        FixLight,
+        FixPower,
+        FixPowerBattery,
+        FixPowerMiscellaneous,
+        FixStructure,
+    }
+
+    impl State {
+        /// Fully qualified state id
+        pub const fn id(&self) -> &'static str {
+            match *self {
+                State::Fix => "broken/fix",
+                State::FixLight => "broken/fix/light",
+                State::FixPower => "broken/fix/power",
+                State::FixPowerBattery => "broken/fix/power/battery",
+                State::FixPowerMiscellaneous => "broken/fix/power/miscellaneous",
+                State::FixStructure => "broken/fix/structure",
+            }
+        }
+    }
+
+    impl ::core::convert::From<State> for super::SpaceState {
+        fn from(state: State) -> Self {
+            super::SpaceState::Broken(state)
+        }
+    }
+
+    /// State `broken/fix`
+    #[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+    pub struct Fix;
+
+    /// Arrows of state `broken/fix`
+    #[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+    pub enum FixArrow {
+        Light,
+        Power,
+        Structure,
+    }
+
+    impl FixArrow {
+        /// Arrow id
+        pub const fn id(&self) -> &'static str {
+            match *self {
+                FixArrow::Light => "light",
+                FixArrow::Power => "power",
+                FixArrow::Structure => "structure",
+            }
+        }
+    }
+
+    impl Fix {
+        pub const STATE: State = State::Fix;
+
+        /// The state an arrow leads to
+        pub const fn transition(self, arrow: FixArrow) -> super::SpaceState {
+            match arrow {
+                FixArrow::Light => super::SpaceState::Broken(super::broken::State::FixLight),
+                FixArrow::Power => super::SpaceState::Broken(super::broken::State::FixPower),
+                FixArrow::Structure => super::SpaceState::Broken(super::broken::State::FixStructure),
+            }
+        }
+    }
+
+    impl ::core::convert::From<Fix> for State {
+        fn from(_: Fix) -> Self {
+            State::Fix
+        }
+    }
+
+    /// State `broken/fix/light`
+    #[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+    pub struct FixLight;
+
+    /// Arrows of state `broken/fix/light`
+    #[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+    pub enum FixLightArrow {
+        ChangeBulb,
+    }
+
+    impl FixLightArrow {
+        /// Arrow id
+        pub const fn id(&self) -> &'static str {
+            match *self {
+                FixLightArrow::ChangeBulb => "change_bulb",
+            }
+        }
+    }
+
+    impl FixLight {
+        pub const STATE: State = State::FixLight;
+
+        /// The state an arrow leads to
+        pub const fn transition(self, arrow: FixLightArrow) -> super::SpaceState {
+            match arrow {
+                FixLightArrow::ChangeBulb => super::SpaceState::Broken(super::broken::State::Fix),
+            }
+        }
+    }
+
+    impl ::core::convert::From<FixLight> for State {
+        fn from(_: FixLight) -> Self {
+            State::FixLight
+        }
+    }
+
+    /// State `broken/fix/power`
+    #[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+    pub struct FixPower;
+
+    /// Arrows of state `broken/fix/power`
+    #[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+    pub enum FixPowerArrow {
+        Battery,
+        Miscellaneous,
+    }
+
+    impl FixPowerArrow {
+        /// Arrow id
+        pub const fn id(&self) -> &'static str {
+            match *self {
+                FixPowerArrow::Battery => "battery",
+                FixPowerArrow::Miscellaneous => "miscellaneous",
+            }
+        }
+    }
+
+    impl FixPower {
+        pub const STATE: State = State::FixPower;
+
+        /// The state an arrow leads to
+        pub const fn transition(self, arrow: FixPowerArrow) -> super::SpaceState {
+            match arrow {
+                FixPowerArrow::Battery => super::SpaceState::Broken(super::broken::State::FixPowerBattery),
+                FixPowerArrow::Miscellaneous => super::SpaceState::Broken(super::broken::State::FixPowerMiscellaneous),
+            }
+        }
+    }
+
+    impl ::core::convert::From<FixPower> for State {
+        fn from(_: FixPower) -> Self {
+            State::FixPower
+        }
+    }
+
+    /// State `broken/fix/power/battery`
+    #[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+    pub struct FixPowerBattery;
+
+    /// Arrows of state `broken/fix/power/battery`
+    #[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+    pub enum FixPowerBatteryArrow {
+        Charge,
+        PlugIn,
+        Replace,
+    }
+
+    impl FixPowerBatteryArrow {
+        /// Arrow id
+        pub const fn id(&self) -> &'static str {
+            match *self {
+                FixPowerBatteryArrow::Charge => "charge",
+                FixPowerBatteryArrow::PlugIn => "plug_in",
+                FixPowerBatteryArrow::Replace => "replace",
+            }
+        }
+    }
+
+    impl FixPowerBattery {
+        pub const STATE: State = State::FixPowerBattery;
+
+        /// The state an arrow leads to
+        pub const fn transition(self, arrow: FixPowerBatteryArrow) -> super::SpaceState {
+            match arrow {
+                FixPowerBatteryArrow::Charge => super::SpaceState::Broken(super::broken::State::Fix),
+                FixPowerBatteryArrow::PlugIn => super::SpaceState::Broken(super::broken::State::Fix),
+                FixPowerBatteryArrow::Replace => super::SpaceState::Broken(super::broken::State::Fix),
+            }
+        }
+    }
+
+    impl ::core::convert::From<FixPowerBattery> for State {
+        fn from(_: FixPowerBattery) -> Self {
+            State::FixPowerBattery
+        }
+    }
+
+    /// State `broken/fix/power/miscellaneous`
+    #[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+    pub struct FixPowerMiscellaneous;
+
+    /// Arrows of state `broken/fix/power/miscellaneous`
+    #[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+    pub enum FixPowerMiscellaneousArrow {
+        CheckCord,
+        CheckOutletPower,
+    }
+
+    impl FixPowerMiscellaneousArrow {
+        /// Arrow id
+        pub const fn id(&self) -> &'static str {
+            match *self {
+                FixPowerMiscellaneousArrow::CheckCord => "check_cord",
+                FixPowerMiscellaneousArrow::CheckOutletPower => "check_outlet_power",
+            }
+        }
+    }
+
+    impl FixPowerMiscellaneous {
+        pub const STATE: State = State::FixPowerMiscellaneous;
+
+        /// The state an arrow leads to
+        pub const fn transition(self, arrow: FixPowerMiscellaneousArrow) -> super::SpaceState {
+            match arrow {
+                FixPowerMiscellaneousArrow::CheckCord => super::SpaceState::Broken(super::broken::State::Fix),
+                FixPowerMiscellaneousArrow::CheckOutletPower => super::SpaceState::Broken(super::broken::State::Fix),
+            }
+        }
+    }
+
+    impl ::core::convert::From<FixPowerMiscellaneous> for State {
+        fn from(_: FixPowerMiscellaneous) -> Self {
+            State::FixPowerMiscellaneous
+        }
+    }
+
+    /// State `broken/fix/structure`
+    #[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+    pub struct FixStructure;
+
+    /// Arrows of state `broken/fix/structure`
+    #[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+    pub enum FixStructureArrow {
+        Casing,
+        Cord,
+    }
+
+    impl FixStructureArrow {
+        /// Arrow id
+        pub const fn id(&self) -> &'static str {
+            match *self {
+                FixStructureArrow::Casing => "casing",
+                FixStructureArrow::Cord => "cord",
+            }
+        }
+    }
+
+    impl FixStructure {
+        pub const STATE: State = State::FixStructure;
+
+        /// The state an arrow leads to
+        pub const fn transition(self, arrow: FixStructureArrow) -> super::SpaceState {
+            match arrow {
+                FixStructureArrow::Casing => super::SpaceState::Broken(super::broken::State::Fix),
+                FixStructureArrow::Cord => super::SpaceState::Broken(super::broken::State::Fix),
+            }
+        }
+    }
+
+    impl ::core::convert::From<FixStructure> for State {
+        fn from(_: FixStructure) -> Self {
+            State::FixStructure
+        }
+    }
+}
+
+/// Machine `default`
+#[allow(non_camel_case_types, clippy::all)]
+pub mod default {
+
+    #[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+    pub enum State {
+        Off,
+        On,
+        Pattern,
+    }
+
+    impl State {
+        /// Fully qualified state id
+        pub const fn id(&self) -> &'static str {
+            match *self {
+                State::Off => "default/off",
+                State::On => "default/on",
+                State::Pattern => "default/pattern",
+            }
+        }
+    }
+
+    impl ::core::convert::From<State> for super::SpaceState {
+        fn from(state: State) -> Self {
+            super::SpaceState::Default(state)
+        }
+    }
+
+    /// State `default/off`
+    #[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+    pub struct Off;
+
+    /// Arrows of state `default/off`
+    #[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+    pub enum OffArrow {
+        On,
+        Self_,
+    }
+
+    impl OffArrow {
+        /// Arrow id
+        pub const fn id(&self) -> &'static str {
+            match *self {
+                OffArrow::On => "on",
+                OffArrow::Self_ => "self",
+            }
+        }
+    }
+
+    impl Off {
+        pub const STATE: State = State::Off;
+
+        /// The state an arrow leads to
+        pub const fn transition(self, arrow: OffArrow) -> super::SpaceState {
+            match arrow {
+                OffArrow::On => super::SpaceState::Default(super::default::State::On),
+                OffArrow::Self_ => super::SpaceState::Default(super::default::State::Off),
+            }
+        }
+    }
+
+    impl ::core::convert::From<Off> for State {
+        fn from(_: Off) -> Self {
+            State::Off
+        }
+    }
+
+    /// State `default/on`
+    #[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+    pub struct On;
+
+    /// Arrows of state `default/on`
+    #[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+    pub enum OnArrow {
+        Off,
+        Pattern,
+        Self_,
+    }
+
+    impl OnArrow {
+        /// Arrow id
+        pub const fn id(&self) -> &'static str {
+            match *self {
+                OnArrow::Off => "off",
+                OnArrow::Pattern => "pattern",
+                OnArrow::Self_ => "self",
+            }
+        }
+    }
+
+    impl On {
+        pub const STATE: State = State::On;
+
+        /// The state an arrow leads to
+        pub const fn transition(self, arrow: OnArrow) -> super::SpaceState {
+            match arrow {
+                OnArrow::Off => super::SpaceState::Default(super::default::State::Off),
+                OnArrow::Pattern => super::SpaceState::Default(super::default::State::Pattern),
+                OnArrow::Self_ => super::SpaceState::Default(super::default::State::On),
+            }
+        }
+    }
+
+    impl ::core::convert::From<On> for State {
+        fn from(_: On) -> Self {
+            State::On
+        }
+    }
+
+    /// State `default/pattern`
+    #[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+    pub struct Pattern;
+
+    /// Arrows of state `default/pattern`
+    #[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+    pub enum PatternArrow {
+        Fade,
+        Strobe,
+    }
+
+    impl PatternArrow {
+        /// Arrow id
+        pub const fn id(&self) -> &'static str {
+            match *self {
+                PatternArrow::Fade => "fade",
+                PatternArrow::Strobe => "strobe",
+            }
+        }
+    }
+
+    impl Pattern {
+        pub const STATE: State = State::Pattern;
+
+        /// The state an arrow leads to
+        pub const fn transition(self, arrow: PatternArrow) -> super::SpaceState {
+            match arrow {
+                PatternArrow::Fade => super::SpaceState::Pattern(super::pattern::State::FadeLevels),
+                PatternArrow::Strobe => super::SpaceState::Pattern(super::pattern::State::StrobeSpeeds),
+            }
+        }
+    }
+
+    impl ::core::convert::From<Pattern> for State {
+        fn from(_: Pattern) -> Self {
+            State::Pattern
+        }
+    }
+}
+
+/// Machine `pattern`
+#[allow(non_camel_case_types, clippy::all)]
+pub mod pattern {
+
+    #[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+    pub enum State {
+        FadeLevels,
+        StrobeSpeeds,
+    }
+
+    impl State {
+        /// Fully qualified state id
+        pub const fn id(&self) -> &'static str {
+            match *self {
+                State::FadeLevels => "pattern/fade/levels",
+                State::StrobeSpeeds => "pattern/strobe/speeds",
+            }
+        }
+    }
+
+    impl ::core::convert::From<State> for super::SpaceState {
+        fn from(state: State) -> Self {
+            super::SpaceState::Pattern(state)
+        }
+    }
+
+    /// State `pattern/fade/levels`
+    #[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+    pub struct FadeLevels;
+
+    /// Arrows of state `pattern/fade/levels`
+    #[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+    pub enum FadeLevelsArrow {
+        _100Percent,
+        _20Percent,
+        _40Percent,
+        _60Percent,
+        _80Percent,
+    }
+
+    impl FadeLevelsArrow {
+        /// Arrow id
+        pub const fn id(&self) -> &'static str {
+            match *self {
+                FadeLevelsArrow::_100Percent => "100_percent",
+                FadeLevelsArrow::_20Percent => "20_percent",
+                FadeLevelsArrow::_40Percent => "40_percent",
+                FadeLevelsArrow::_60Percent => "60_percent",
+                FadeLevelsArrow::_80Percent => "80_percent",
+            }
+        }
+    }
+
+    impl FadeLevels {
+        pub const STATE: State = State::FadeLevels;
+
+        /// The state an arrow leads to
+        pub const fn transition(self, arrow: FadeLevelsArrow) -> super::SpaceState {
+            match arrow {
+                FadeLevelsArrow::_100Percent => super::SpaceState::Default(super::default::State::On),
+                FadeLevelsArrow::_20Percent => super::SpaceState::Default(super::default::State::On),
+                FadeLevelsArrow::_40Percent => super::SpaceState::Default(super::default::State::On),
+                FadeLevelsArrow::_60Percent => super::SpaceState::Default(super::default::State::On),
+                FadeLevelsArrow::_80Percent => super::SpaceState::Default(super::default::State::On),
+            }
+        }
+    }
+
+    impl ::core::convert::From<FadeLevels> for State {
+        fn from(_: FadeLevels) -> Self {
+            State::FadeLevels
+        }
+    }
+
+    /// State `pattern/strobe/speeds`
+    #[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+    pub struct StrobeSpeeds;
+
+    /// Arrows of state `pattern/strobe/speeds`
+    #[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+    pub enum StrobeSpeedsArrow {
+        Fast,
+        Medium,
+        Slow,
+    }
+
+    impl StrobeSpeedsArrow {
+        /// Arrow id
+        pub const fn id(&self) -> &'static str {
+            match *self {
+                StrobeSpeedsArrow::Fast => "fast",
+                StrobeSpeedsArrow::Medium => "medium",
+                StrobeSpeedsArrow::Slow => "slow",
+            }
+        }
+    }
+
+    impl StrobeSpeeds {
+        pub const STATE: State = State::StrobeSpeeds;
+
+        /// The state an arrow leads to
+        pub const fn transition(self, arrow: StrobeSpeedsArrow) -> super::SpaceState {
+            match arrow {
+                StrobeSpeedsArrow::Fast => super::SpaceState::Default(super::default::State::On),
+                StrobeSpeedsArrow::Medium => super::SpaceState::Default(super::default::State::On),
+                StrobeSpeedsArrow::Slow => super::SpaceState::Default(super::default::State::On),
+            }
+        }
+    }
+
+    impl ::core::convert::From<StrobeSpeeds> for State {
+        fn from(_: StrobeSpeeds) -> Self {
+            State::StrobeSpeeds
+        }
+    }
+}
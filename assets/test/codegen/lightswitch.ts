@@ -0,0 +1,120 @@
+// Generated by elea from a Space, do not edit.
+
+/** Machine ids */
+export type MachineId =
+  | "broken"
+  | "default"
+  | "pattern";
+
+/** Fully qualified state ids */
+export type StateId =
+  | "broken/fix"
+  | "broken/fix/light"
+  | "broken/fix/power"
+  | "broken/fix/power/battery"
+  | "broken/fix/power/miscellaneous"
+  | "broken/fix/structure"
+  | "default/off"
+  | "default/on"
+  | "default/pattern"
+  | "pattern/fade/levels"
+  | "pattern/strobe/speeds";
+
+/** State ids of each machine */
+export type StateIdByMachine = {
+  "broken": "broken/fix" | "broken/fix/light" | "broken/fix/power" | "broken/fix/power/battery" | "broken/fix/power/miscellaneous" | "broken/fix/structure";
+  "default": "default/off" | "default/on" | "default/pattern";
+  "pattern": "pattern/fade/levels" | "pattern/strobe/speeds";
+};
+
+/** Arrow ids of each state */
+export type ArrowIdByState = {
+  "broken/fix": "light" | "power" | "structure";
+  "broken/fix/light": "change_bulb";
+  "broken/fix/power": "battery" | "miscellaneous";
+  "broken/fix/power/battery": "charge" | "plug_in" | "replace";
+  "broken/fix/power/miscellaneous": "check_cord" | "check_outlet_power";
+  "broken/fix/structure": "casing" | "cord";
+  "default/off": "on" | "self";
+  "default/on": "off" | "pattern" | "self";
+  "default/pattern": "fade" | "strobe";
+  "pattern/fade/levels": "100_percent" | "20_percent" | "40_percent" | "60_percent" | "80_percent";
+  "pattern/strobe/speeds": "fast" | "medium" | "slow";
+};
+
+export type ArrowId<S extends StateId = StateId> = ArrowIdByState[S];
+
+export const machineIds: readonly MachineId[] = ["broken", "default", "pattern"];
+
+export const stateIds: readonly StateId[] = [
+  "broken/fix",
+  "broken/fix/light",
+  "broken/fix/power",
+  "broken/fix/power/battery",
+  "broken/fix/power/miscellaneous",
+  "broken/fix/structure",
+  "default/off",
+  "default/on",
+  "default/pattern",
+  "pattern/fade/levels",
+  "pattern/strobe/speeds",
+];
+
+/** The state each arrow of each state leads to */
+export const transitions = {
+  "broken/fix": {
+    "light": "broken/fix/light",
+    "power": "broken/fix/power",
+    "structure": "broken/fix/structure",
+  },
+  "broken/fix/light": {
+    "change_bulb": "broken/fix",
+  },
+  "broken/fix/power": {
+    "battery": "broken/fix/power/battery",
+    "miscellaneous": "broken/fix/power/miscellaneous",
+  },
+  "broken/fix/power/battery": {
+    "charge": "broken/fix",
+    "plug_in": "broken/fix",
+    "replace": "broken/fix",
+  },
+  "broken/fix/power/miscellaneous": {
+    "check_cord": "broken/fix",
+    "check_outlet_power": "broken/fix",
+  },
+  "broken/fix/structure": {
+    "casing": "broken/fix",
+    "cord": "broken/fix",
+  },
+  "default/off": {
+    "on": "default/on",
+    "self": "default/off",
+  },
+  "default/on": {
+    "off": "default/off",
+    "pattern": "default/pattern",
+    "self": "default/on",
+  },
+  "default/pattern": {
+    "fade": "pattern/fade/levels",
+    "strobe": "pattern/strobe/speeds",
+  },
+  "pattern/fade/levels": {
+    "100_percent": "default/on",
+    "20_percent": "default/on",
+    "40_percent": "default/on",
+    "60_percent": "default/on",
+    "80_percent": "default/on",
+  },
+  "pattern/strobe/speeds": {
+    "fast": "default/on",
+    "medium": "default/on",
+    "slow": "default/on",
+  },
+} as const satisfies { [S in StateId]: { [A in ArrowId<S>]: StateId } };
+
+/** The state an arrow of `state` leads to */
+export function transition<S extends StateId>(state: S, arrow: ArrowId<S>): StateId {
+  return (transitions[state] as Record<string, StateId>)[arrow];
+}
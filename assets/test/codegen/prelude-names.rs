@@ -0,0 +1,115 @@
+// Generated by elea from a Space, do not edit.
+
+/// A state of any machine
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum SpaceState {
+    Convert(convert::State),
+}
+
+impl SpaceState {
+    /// Fully qualified state id
+    pub const fn id(&self) -> &'static str {
+        match self {
+            SpaceState::Convert(state) => state.id(),
+        }
+    }
+}
+
+/// Machine `convert`
+#[allow(non_camel_case_types, clippy::all)]
+pub mod convert {
+
+    #[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+    pub enum State {
+        From,
+        Into,
+    }
+
+    impl State {
+        /// Fully qualified state id
+        pub const fn id(&self) -> &'static str {
+            match *self {
+                State::From => "convert/from",
+                State::Into => "convert/into",
+            }
+        }
+    }
+
+    impl ::core::convert::From<State> for super::SpaceState {
+        fn from(state: State) -> Self {
+            super::SpaceState::Convert(state)
+        }
+    }
+
+    /// State `convert/from`
+    #[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+    pub struct From;
+
+    /// Arrows of state `convert/from`
+    #[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+    pub enum FromArrow {
+        Into,
+    }
+
+    impl FromArrow {
+        /// Arrow id
+        pub const fn id(&self) -> &'static str {
+            match *self {
+                FromArrow::Into => "into",
+            }
+        }
+    }
+
+    impl From {
+        pub const STATE: State = State::From;
+
+        /// The state an arrow leads to
+        pub const fn transition(self, arrow: FromArrow) -> super::SpaceState {
+            match arrow {
+                FromArrow::Into => super::SpaceState::Convert(super::convert::State::Into),
+            }
+        }
+    }
+
+    impl ::core::convert::From<From> for State {
+        fn from(_: From) -> Self {
+            State::From
+        }
+    }
+
+    /// State `convert/into`
+    #[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+    pub struct Into;
+
+    /// Arrows of state `convert/into`
+    #[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+    pub enum IntoArrow {
+        From,
+    }
+
+    impl IntoArrow {
+        /// Arrow id
+        pub const fn id(&self) -> &'static str {
+            match *self {
+                IntoArrow::From => "from",
+            }
+        }
+    }
+
+    impl Into {
+        pub const STATE: State = State::Into;
+
+        /// The state an arrow leads to
+        pub const fn transition(self, arrow: IntoArrow) -> super::SpaceState {
+            match arrow {
+                IntoArrow::From => super::SpaceState::Convert(super::convert::State::From),
+            }
+        }
+    }
+
+    impl ::core::convert::From<Into> for State {
+        fn from(_: Into) -> Self {
+            State::Into
+        }
+    }
+}
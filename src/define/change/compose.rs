@@ -0,0 +1,349 @@
+//!
+//! ! Compose
+//! Product of machines, to model independent aspects of a thing, such as the
+//! power and the pattern of a lightswitch, as separate machines and combine
+//! them. A state of the product is a state of each component machine.
+//!
+//! Arrows interleave: an arrow whose id belongs to one component only moves
+//! that component, the others stay. Arrows whose id several components use
+//! synchronize: the product takes them when each of these components can,
+//! and all of them move together.
+//!
+//! The product declares the variables of every component, which must agree
+//! where they share a name, and synchronized
+//! arrows apply the effects of each component arrow, in component order, and
+//! wait for the longest of their durations.
+//!
+//! The local id of a product state has one segment per component, in the
+//! order of the components, with the `/` of component state ids written `.`:
+//! `on/fade.levels` pairs `power/on` with `pattern/fade/levels`.
+//!
+//! ----------------------------------------------------------------------------
+//! type: computer for Elea
+//! ----------------------------------------------------------------------------
+
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+use std::fmt;
+
+use crate::define::experience::language::duration;
+use crate::define::experience::representation::qualified_id::{
+    QualifiedStateId, QualifiedStateIdError
+};
+use crate::define::experience::representation::rust_minimal::{
+    ArrowId, MachineId, StateId
+};
+use crate::define::experience::representation::rust_pragmatic::{
+    Arrow, Machine, Space, State
+};
+
+//------------------------------------------------------------------------------
+// COMPOSE
+
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct ComposeOptions {
+    /// Fully qualified state of each component to start from. When given,
+    /// the product only has the states reachable from there, instead of
+    /// every combination of component states.
+    pub start: Option<Vec<String>>,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum ComposeError {
+    NoMachines,
+    UnknownMachine(MachineId),
+    DuplicateMachine(MachineId),
+    InvalidId(QualifiedStateIdError),
+    /// Component arrows must stay within their machine
+    ExternalArrow { state: StateId, arrow: ArrowId, next: StateId },
+    /// Two arrows of a component state share an id, so which one a
+    /// synchronized arrow takes would be ambiguous
+    DuplicateArrow { state: StateId, arrow: ArrowId },
+    /// Two components declare a variable of the same name differently
+    ConflictingVariable { name: String, first: MachineId, second: MachineId },
+    UnknownState(StateId),
+    /// Two states of a component share a product segment, like `a.b` and
+    /// `a/b`
+    AmbiguousState { first: StateId, second: StateId },
+    /// `start` needs one state per component
+    StartLength { expected: usize, found: usize },
+}
+
+impl fmt::Display for ComposeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ComposeError::NoMachines => write!(f, "no machines to compose"),
+            ComposeError::UnknownMachine(machine) => write!(f, "unknown machine [{}]", machine.0),
+            ComposeError::DuplicateMachine(machine) => write!(f, "machine [{}] is composed more than once", machine.0),
+            ComposeError::InvalidId(err) => write!(f, "invalid id: {}", err),
+            ComposeError::ExternalArrow { state, arrow, next } => {
+                write!(f, "arrow [{}] of [{}] leaves its machine for [{}]", arrow.0, state.0, next.0)
+            },
+            ComposeError::DuplicateArrow { state, arrow } => {
+                write!(f, "state [{}] has more than one arrow [{}]", state.0, arrow.0)
+            },
+            ComposeError::ConflictingVariable { name, first, second } => {
+                write!(f, "variable [{}] is declared differently by [{}] and [{}]", name, first.0, second.0)
+            },
+            ComposeError::UnknownState(state) => write!(f, "unknown state [{}]", state.0),
+            ComposeError::AmbiguousState { first, second } => {
+                write!(f, "states [{}] and [{}] have the same product id", first.0, second.0)
+            },
+            ComposeError::StartLength { expected, found } => {
+                write!(f, "{} start states for {} machines", found, expected)
+            },
+        }
+    }
+}
+
+/// Compose the machines of a Space, by id, into a machine named `id`
+pub fn compose_space(space: &Space, id: &str, machine_ids: &[&str], options: &ComposeOptions) -> Result<Machine, ComposeError> {
+    let machines = machine_ids.iter()
+        .map(|machine_id| {
+            let machine_id = MachineId(machine_id.to_string());
+            return space.machine_by_id.get(&machine_id).ok_or(ComposeError::UnknownMachine(machine_id));
+        })
+        .collect::<Result<Vec<&Machine>, ComposeError>>()?;
+    return compose(id, &machines, options);
+}
+
+/// Compose machines into a machine named `id`
+pub fn compose(id: &str, machines: &[&Machine], options: &ComposeOptions) -> Result<Machine, ComposeError> {
+    QualifiedStateId::machine_root(id).map_err(ComposeError::InvalidId)?;
+    if machines.is_empty() {
+        return Err(ComposeError::NoMachines);
+    }
+    let mut seen = HashSet::new();
+    let components = machines.iter()
+        .map(|machine| {
+            if !seen.insert(&machine.id) {
+                return Err(ComposeError::DuplicateMachine(machine.id.clone()));
+            }
+            return Component::new(machine);
+        })
+        .collect::<Result<Vec<Component>, ComposeError>>()?;
+
+    // components using each arrow id
+    let mut owners: BTreeMap<&ArrowId, Vec<usize>> = BTreeMap::new();
+    for (index, component) in components.iter().enumerate() {
+        for arrow_id in component.alphabet() {
+            owners.entry(arrow_id).or_default().push(index);
+        }
+    }
+
+    let product = Product { id, components: &components, owners };
+    let tuples = match &options.start {
+        Some(start) => product.reachable(product.start(start)?),
+        None => product.every_tuple(),
+    };
+    let states = tuples.iter().map(|tuple| product.state(tuple)).collect();
+    let mut machine = Machine::new(id, states);
+    let mut declared_by: HashMap<&String, &MachineId> = HashMap::new();
+    for component in &components {
+        for (name, variable) in &component.machine.variables {
+            if let Some(first) = declared_by.insert(name, &component.machine.id) {
+                if machine.variables.get(name) != Some(variable) {
+                    return Err(ComposeError::ConflictingVariable {
+                        name: name.clone(),
+                        first: first.clone(),
+                        second: component.machine.id.clone(),
+                    });
+                }
+            }
+            machine.variables.insert(name.clone(), variable.clone());
+        }
+    }
+    return Ok(machine);
+}
+
+/// Local id of the product state pairing local component state ids
+pub fn product_state_id(component_states: &[&StateId]) -> StateId {
+    let segments: Vec<String> = component_states.iter().map(|state| segment(state)).collect();
+    return StateId(segments.join("/"));
+}
+
+//------------------------------------------------------------------------------
+// COMPOSE / Product
+
+/// A component machine with its states indexed, and its arrows pointing at
+/// state indexes
+struct Component<'a> {
+    machine: &'a Machine,
+    index_of: HashMap<&'a StateId, usize>,
+    /// Arrows of each state, with the index of their next state
+    arrows: Vec<Vec<(&'a Arrow, usize)>>,
+}
+
+impl<'a> Component<'a> {
+
+    fn new(machine: &'a Machine) -> Result<Self, ComposeError> {
+        let mut index_of = HashMap::new();
+        let mut by_segment: HashMap<String, &StateId> = HashMap::new();
+        for (index, state) in machine.states.iter().enumerate() {
+            let qualified_id = QualifiedStateId::from_local(&machine.id, &state.id).map_err(ComposeError::InvalidId)?;
+            if let Some(first) = by_segment.insert(segment(&state.id), &state.id) {
+                return Err(ComposeError::AmbiguousState {
+                    first: QualifiedStateId::from_local(&machine.id, first).map_err(ComposeError::InvalidId)?.to_state_id(),
+                    second: qualified_id.to_state_id(),
+                });
+            }
+            index_of.insert(&state.id, index);
+        }
+
+        let mut arrows = Vec::new();
+        for state in &machine.states {
+            let mut state_arrows = Vec::new();
+            let mut seen = HashSet::new();
+            for arrow in &state.arrows {
+                if !seen.insert(&arrow.id) {
+                    return Err(ComposeError::DuplicateArrow {
+                        state: QualifiedStateId::from_local(&machine.id, &state.id).map_err(ComposeError::InvalidId)?.to_state_id(),
+                        arrow: arrow.id.clone(),
+                    });
+                }
+                let next = QualifiedStateId::parse(&arrow.next.0).map_err(ComposeError::InvalidId)?;
+                if next.machine() != &machine.id {
+                    return Err(ComposeError::ExternalArrow {
+                        state: QualifiedStateId::from_local(&machine.id, &state.id).map_err(ComposeError::InvalidId)?.to_state_id(),
+                        arrow: arrow.id.clone(),
+                        next: arrow.next.clone(),
+                    });
+                }
+                let next_index = machine.states.iter()
+                    .position(|state| state.id == next.local_id())
+                    .ok_or_else(|| ComposeError::UnknownState(arrow.next.clone()))?;
+                state_arrows.push((arrow, next_index));
+            }
+            arrows.push(state_arrows);
+        }
+        return Ok(Self { machine, index_of, arrows });
+    }
+
+    fn alphabet(&self) -> Vec<&'a ArrowId> {
+        let mut alphabet: Vec<&ArrowId> = self.arrows.iter().flatten().map(|(arrow, _)| &arrow.id).collect();
+        alphabet.sort();
+        alphabet.dedup();
+        return alphabet;
+    }
+
+    fn arrow(&self, state: usize, id: &ArrowId) -> Option<(&'a Arrow, usize)> {
+        return self.arrows[state].iter().find(|(arrow, _)| &arrow.id == id).copied();
+    }
+}
+
+struct Product<'a> {
+    id: &'a str,
+    components: &'a [Component<'a>],
+    owners: BTreeMap<&'a ArrowId, Vec<usize>>,
+}
+
+impl<'a> Product<'a> {
+
+    fn start(&self, start: &[String]) -> Result<Vec<usize>, ComposeError> {
+        if start.len() != self.components.len() {
+            return Err(ComposeError::StartLength { expected: self.components.len(), found: start.len() });
+        }
+        return start.iter()
+            .zip(self.components)
+            .map(|(state, component)| {
+                let unknown = || ComposeError::UnknownState(StateId(state.clone()));
+                let id = QualifiedStateId::parse(state).map_err(ComposeError::InvalidId)?;
+                if id.machine() != &component.machine.id {
+                    return Err(unknown());
+                }
+                return component.index_of.get(&id.local_id()).copied().ok_or_else(unknown);
+            })
+            .collect();
+    }
+
+    /// Every combination of component states, the last component varying
+    /// fastest
+    fn every_tuple(&self) -> Vec<Vec<usize>> {
+        let mut tuples = vec![Vec::new()];
+        for component in self.components {
+            tuples = tuples.into_iter()
+                .flat_map(|tuple| {
+                    (0..component.machine.states.len()).map(move |state| {
+                        let mut tuple = tuple.clone();
+                        tuple.push(state);
+                        tuple
+                    })
+                })
+                .collect();
+        }
+        return tuples;
+    }
+
+    fn reachable(&self, start: Vec<usize>) -> Vec<Vec<usize>> {
+        let mut seen = HashSet::from([start.clone()]);
+        let mut tuples = vec![start.clone()];
+        let mut queue = VecDeque::from([start]);
+        while let Some(tuple) = queue.pop_front() {
+            for (_, next) in self.transitions(&tuple) {
+                if seen.insert(next.clone()) {
+                    tuples.push(next.clone());
+                    queue.push_back(next);
+                }
+            }
+        }
+        return tuples;
+    }
+
+    /// Arrows of a product state, with the tuple they lead to. Arrows of
+    /// one component keep their weight, guard, effects and duration,
+    /// synchronized arrows take the product of their weights, all of their
+    /// guards and effects, and the longest of their durations.
+    fn transitions(&self, tuple: &[usize]) -> Vec<(Arrow, Vec<usize>)> {
+        let mut transitions = Vec::new();
+        for (arrow_id, owners) in &self.owners {
+            let mut next = tuple.to_vec();
+            let mut taken = Vec::new();
+            for owner in owners {
+                match self.components[*owner].arrow(tuple[*owner], arrow_id) {
+                    Some((arrow, next_state)) => {
+                        next[*owner] = next_state;
+                        taken.push(arrow);
+                    },
+                    None => break,
+                }
+            }
+            if taken.len() != owners.len() {
+                continue;
+            }
+            let mut arrow = Arrow::new(&arrow_id.0, &format!("{}/{}", self.id, self.local_id(&next).0));
+            arrow.description = taken.iter().find_map(|arrow| arrow.description.clone());
+            if taken.iter().any(|arrow| arrow.weight.is_some()) {
+                arrow.weight = Some(taken.iter().map(|arrow| arrow.weight.unwrap_or(1.0)).product());
+            }
+            let guards: Vec<&str> = taken.iter().filter_map(|arrow| arrow.guard.as_deref()).collect();
+            arrow.guard = match guards.len() {
+                0 => None,
+                1 => Some(guards[0].to_string()),
+                _ => Some(guards.iter().map(|guard| format!("({})", guard)).collect::<Vec<_>>().join(" && ")),
+            };
+            arrow.effects = taken.iter().flat_map(|arrow| arrow.effects.clone()).collect();
+            arrow.after = taken.iter()
+                .filter_map(|arrow| arrow.after.clone())
+                .max_by_key(|after| duration::parse_duration(after).ok());
+            transitions.push((arrow, next));
+        }
+        return transitions;
+    }
+
+    fn local_id(&self, tuple: &[usize]) -> StateId {
+        let states: Vec<&StateId> = tuple.iter()
+            .zip(self.components)
+            .map(|(state, component)| &component.machine.states[*state].id)
+            .collect();
+        return product_state_id(&states);
+    }
+
+    fn state(&self, tuple: &[usize]) -> State {
+        let arrows = self.transitions(tuple).into_iter().map(|(arrow, _)| arrow).collect();
+        return State::new(&self.local_id(tuple).0, arrows);
+    }
+}
+
+/// A component state id as one segment of a product state id
+fn segment(state: &StateId) -> String {
+    return state.0.replace('/', ".");
+}
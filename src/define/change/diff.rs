@@ -0,0 +1,406 @@
+//!
+//! ! Diff
+//! Semantic difference between two Spaces. Machines and states are matched by
+//! id first. A removed machine or state whose content reappears under a new
+//! id is reported as renamed, and arrows that only follow such a rename are
+//! not reported as retargeted. Arrows kept under the same id are compared by
+//! weight, guard, effects and duration as well.
+//!
+//! ----------------------------------------------------------------------------
+//! type: computer for Elea
+//! ----------------------------------------------------------------------------
+
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+
+use crate::define::experience::representation::rust_minimal::{
+    ArrowId, MachineId, StateId
+};
+use crate::define::experience::representation::rust_pragmatic::{
+    Arrow, Machine, Space, State
+};
+
+//------------------------------------------------------------------------------
+// DIFF
+
+/// Space Diff
+///
+/// State ids are fully qualified. Arrow changes of a renamed state are
+/// reported under its new id.
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+pub struct SpaceDiff {
+    pub added_machines: Vec<MachineId>,
+    pub removed_machines: Vec<MachineId>,
+    pub renamed_machines: Vec<Rename<MachineId>>,
+    pub added_states: Vec<StateId>,
+    pub removed_states: Vec<StateId>,
+    pub renamed_states: Vec<Rename<StateId>>,
+    pub added_arrows: Vec<ArrowChange>,
+    pub removed_arrows: Vec<ArrowChange>,
+    pub retargeted_arrows: Vec<Retarget>,
+    pub changed_arrows: Vec<AttributeChange>,
+    pub changed_descriptions: Vec<DescriptionChange>,
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct Rename<T> {
+    pub from: T,
+    pub to: T,
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct ArrowChange {
+    pub state: StateId,
+    pub arrow: ArrowId,
+    pub next: StateId,
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct Retarget {
+    pub state: StateId,
+    pub arrow: ArrowId,
+    pub from: StateId,
+    pub to: StateId,
+}
+
+/// What an arrow does besides leading to its next state
+#[derive(Clone, Copy, Debug, Deserialize, Eq, Ord, PartialEq, PartialOrd, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Attribute {
+    Weight,
+    Guard,
+    Effects,
+    After,
+}
+
+/// An attribute of an arrow, written as in a State file. Effects are
+/// separated by `; `.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct AttributeChange {
+    pub state: StateId,
+    pub arrow: ArrowId,
+    pub attribute: Attribute,
+    pub from: Option<String>,
+    pub to: Option<String>,
+}
+
+/// Description of a state, or of one of its arrows
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct DescriptionChange {
+    pub state: StateId,
+    pub arrow: Option<ArrowId>,
+    pub from: Option<String>,
+    pub to: Option<String>,
+}
+
+impl SpaceDiff {
+
+    pub fn is_empty(&self) -> bool {
+        return self == &SpaceDiff::default();
+    }
+}
+
+/// Compute what changed from `old` to `new`
+pub fn diff(old: &Space, new: &Space) -> SpaceDiff {
+    let mut space_diff = SpaceDiff::default();
+
+    // machines
+    let old_machines: BTreeMap<&MachineId, &Machine> = old.machines.iter().map(|m| (&m.id, m)).collect();
+    let new_machines: BTreeMap<&MachineId, &Machine> = new.machines.iter().map(|m| (&m.id, m)).collect();
+    let removed_machines: Vec<&Machine> = old.machines.iter().filter(|m| !new_machines.contains_key(&m.id)).collect();
+    let added_machines: Vec<&Machine> = new.machines.iter().filter(|m| !old_machines.contains_key(&m.id)).collect();
+
+    let machine_renames = match_renames(
+        &removed_machines,
+        &added_machines,
+        |machine| machine_signature(machine),
+    );
+    let mut machine_map: BTreeMap<MachineId, MachineId> = BTreeMap::new();
+    for machine in &old.machines {
+        if new_machines.contains_key(&machine.id) {
+            machine_map.insert(machine.id.clone(), machine.id.clone());
+        }
+    }
+    for (from, to) in &machine_renames {
+        machine_map.insert(from.id.clone(), to.id.clone());
+        space_diff.renamed_machines.push(Rename { from: from.id.clone(), to: to.id.clone() });
+    }
+    let renamed_to: BTreeSet<&MachineId> = machine_renames.iter().map(|(_, to)| &to.id).collect();
+    for machine in &removed_machines {
+        if !machine_map.contains_key(&machine.id) {
+            space_diff.removed_machines.push(machine.id.clone());
+        }
+    }
+    for machine in &added_machines {
+        if !renamed_to.contains(&machine.id) {
+            space_diff.added_machines.push(machine.id.clone());
+        }
+    }
+
+    // states, keyed by qualified id with old ids moved into renamed machines
+    let old_states: BTreeMap<String, &State> = qualified_states(old);
+    let new_states: BTreeMap<String, &State> = qualified_states(new);
+    let mut state_map: BTreeMap<String, String> = BTreeMap::new();
+    for id in old_states.keys() {
+        let moved_id = move_to_machine(id, &machine_map);
+        if new_states.contains_key(&moved_id) {
+            state_map.insert(id.clone(), moved_id);
+        }
+    }
+
+    let removed_states: Vec<(&String, &State)> = old_states.iter()
+        .filter(|(id, _)| !state_map.contains_key(*id))
+        .map(|(id, state)| (id, *state))
+        .collect();
+    let mapped_new: BTreeSet<&String> = state_map.values().collect();
+    let added_states: Vec<(&String, &State)> = new_states.iter()
+        .filter(|(id, _)| !mapped_new.contains(id))
+        .map(|(id, state)| (id, *state))
+        .collect();
+
+    let state_renames = match_renames(
+        &removed_states,
+        &added_states,
+        |(id, state)| state_signature(id, state, &machine_map),
+    );
+    for ((from, _), (to, _)) in &state_renames {
+        state_map.insert(from.to_string(), to.to_string());
+        space_diff.renamed_states.push(Rename { from: StateId(from.to_string()), to: StateId(to.to_string()) });
+    }
+    let renamed_state_to: BTreeSet<&String> = state_renames.iter().map(|(_, (to, _))| *to).collect();
+    for (id, _) in &removed_states {
+        if !state_map.contains_key(*id) {
+            space_diff.removed_states.push(StateId(id.to_string()));
+        }
+    }
+    for (id, _) in &added_states {
+        if !renamed_state_to.contains(id) {
+            space_diff.added_states.push(StateId(id.to_string()));
+        }
+    }
+
+    // arrows and descriptions of states present on both sides
+    for (old_id, new_id) in &state_map {
+        let old_state = old_states[old_id];
+        let new_state = new_states[new_id];
+        diff_state(new_id, old_state, new_state, &state_map, &machine_map, &mut space_diff);
+    }
+
+    return space_diff;
+}
+
+fn diff_state(
+    state_id: &str,
+    old_state: &State,
+    new_state: &State,
+    state_map: &BTreeMap<String, String>,
+    machine_map: &BTreeMap<MachineId, MachineId>,
+    space_diff: &mut SpaceDiff,
+) {
+    let state = StateId(state_id.to_string());
+    if old_state.description != new_state.description {
+        space_diff.changed_descriptions.push(DescriptionChange {
+            state: state.clone(),
+            arrow: None,
+            from: old_state.description.clone(),
+            to: new_state.description.clone(),
+        });
+    }
+
+    for old_arrow in &old_state.arrows {
+        match new_state.arrows.iter().find(|a| a.id == old_arrow.id) {
+            None => space_diff.removed_arrows.push(ArrowChange {
+                state: state.clone(),
+                arrow: old_arrow.id.clone(),
+                next: old_arrow.next.clone(),
+            }),
+            Some(new_arrow) => {
+                let followed_next = state_map.get(&old_arrow.next.0)
+                    .cloned()
+                    .unwrap_or_else(|| move_to_machine(&old_arrow.next.0, machine_map));
+                if followed_next != new_arrow.next.0 {
+                    space_diff.retargeted_arrows.push(Retarget {
+                        state: state.clone(),
+                        arrow: old_arrow.id.clone(),
+                        from: old_arrow.next.clone(),
+                        to: new_arrow.next.clone(),
+                    });
+                }
+                for attribute in [Attribute::Weight, Attribute::Guard, Attribute::Effects, Attribute::After] {
+                    let (from, to) = (attribute.of(old_arrow), attribute.of(new_arrow));
+                    if from != to {
+                        space_diff.changed_arrows.push(AttributeChange {
+                            state: state.clone(),
+                            arrow: old_arrow.id.clone(),
+                            attribute,
+                            from,
+                            to,
+                        });
+                    }
+                }
+                if old_arrow.description != new_arrow.description {
+                    space_diff.changed_descriptions.push(DescriptionChange {
+                        state: state.clone(),
+                        arrow: Some(old_arrow.id.clone()),
+                        from: old_arrow.description.clone(),
+                        to: new_arrow.description.clone(),
+                    });
+                }
+            },
+        }
+    }
+    for new_arrow in &new_state.arrows {
+        if !old_state.arrows.iter().any(|a| a.id == new_arrow.id) {
+            space_diff.added_arrows.push(ArrowChange {
+                state: state.clone(),
+                arrow: new_arrow.id.clone(),
+                next: new_arrow.next.clone(),
+            });
+        }
+    }
+}
+
+//------------------------------------------------------------------------------
+// DIFF / Output
+
+impl fmt::Display for SpaceDiff {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for id in &self.added_machines {
+            writeln!(f, "machine added      {}", id.0)?;
+        }
+        for id in &self.removed_machines {
+            writeln!(f, "machine removed    {}", id.0)?;
+        }
+        for rename in &self.renamed_machines {
+            writeln!(f, "machine renamed    {} -> {}", rename.from.0, rename.to.0)?;
+        }
+        for id in &self.added_states {
+            writeln!(f, "state added        {}", id.0)?;
+        }
+        for id in &self.removed_states {
+            writeln!(f, "state removed      {}", id.0)?;
+        }
+        for rename in &self.renamed_states {
+            writeln!(f, "state renamed      {} -> {}", rename.from.0, rename.to.0)?;
+        }
+        for change in &self.added_arrows {
+            writeln!(f, "arrow added        {} --{}--> {}", change.state.0, change.arrow.0, change.next.0)?;
+        }
+        for change in &self.removed_arrows {
+            writeln!(f, "arrow removed      {} --{}--> {}", change.state.0, change.arrow.0, change.next.0)?;
+        }
+        for retarget in &self.retargeted_arrows {
+            writeln!(f, "arrow retargeted   {} --{}--> {} (was {})", retarget.state.0, retarget.arrow.0, retarget.to.0, retarget.from.0)?;
+        }
+        for change in &self.changed_arrows {
+            let (from, to) = (change.from.as_deref().unwrap_or("(none)"), change.to.as_deref().unwrap_or("(none)"));
+            writeln!(f, "arrow changed      {} --{}--> {}: {} -> {}", change.state.0, change.arrow.0, change.attribute, from, to)?;
+        }
+        for change in &self.changed_descriptions {
+            let subject = match &change.arrow {
+                Some(arrow) => format!("{} --{}-->", change.state.0, arrow.0),
+                None => change.state.0.clone(),
+            };
+            writeln!(f, "description        {}: {:?} -> {:?}", subject, change.from, change.to)?;
+        }
+        return Ok(());
+    }
+}
+
+impl fmt::Display for Attribute {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Attribute::Weight => write!(f, "weight"),
+            Attribute::Guard => write!(f, "guard"),
+            Attribute::Effects => write!(f, "effects"),
+            Attribute::After => write!(f, "after"),
+        }
+    }
+}
+
+//------------------------------------------------------------------------------
+// DIFF / Helpers
+
+impl Attribute {
+
+    fn of(&self, arrow: &Arrow) -> Option<String> {
+        return match self {
+            Attribute::Weight => arrow.weight.map(|weight| weight.to_string()),
+            Attribute::Guard => arrow.guard.clone(),
+            Attribute::Effects if arrow.effects.is_empty() => None,
+            Attribute::Effects => Some(arrow.effects.join("; ")),
+            Attribute::After => arrow.after.clone(),
+        };
+    }
+}
+
+/// Id or machine, description and (arrow id, relative next) pairs
+type Signature = (String, Option<String>, Vec<(String, String)>);
+
+/// Pair removed and added items with the same signature, each at most once
+fn match_renames<'a, T, S, F>(removed: &'a [T], added: &'a [T], signature: F) -> Vec<(&'a T, &'a T)>
+where
+    S: Eq,
+    F: Fn(&T) -> S,
+{
+    let added_signatures: Vec<S> = added.iter().map(&signature).collect();
+    let mut used = vec![false; added.len()];
+    let mut renames = Vec::new();
+    for item in removed {
+        let item_signature = signature(item);
+        let found = added_signatures.iter().enumerate()
+            .find(|(i, s)| !used[*i] && **s == item_signature);
+        if let Some((i, _)) = found {
+            used[i] = true;
+            renames.push((item, &added[i]));
+        }
+    }
+    return renames;
+}
+
+/// A machine's content with references to itself made relative, so that it
+/// equals the signature of the same machine under another id
+fn machine_signature(machine: &Machine) -> Vec<Signature> {
+    let prefix = machine.id.0.clone() + "/";
+    return machine.states.iter().map(|state| {
+        let arrows = state.arrows.iter().map(|arrow| {
+            let next = match arrow.next.0.strip_prefix(&prefix) {
+                Some(local) => "./".to_string() + local,
+                None => arrow.next.0.clone(),
+            };
+            (arrow.id.0.clone(), next)
+        }).collect();
+        (state.id.0.clone(), state.description.clone(), arrows)
+    }).collect();
+}
+
+/// A state's content with references to itself made relative
+fn state_signature(
+    id: &str,
+    state: &State,
+    machine_map: &BTreeMap<MachineId, MachineId>,
+) -> Signature {
+    let machine = id.split('/').next().unwrap_or_default();
+    let machine = machine_map.get(&MachineId(machine.to_string()))
+        .map(|m| m.0.clone())
+        .unwrap_or(machine.to_string());
+    let arrows = state.arrows.iter().map(|arrow| {
+        let next = if arrow.next.0 == id { ".".to_string() } else { move_to_machine(&arrow.next.0, machine_map) };
+        (arrow.id.0.clone(), next)
+    }).collect();
+    return (machine, state.description.clone(), arrows);
+}
+
+fn qualified_states(space: &Space) -> BTreeMap<String, &State> {
+    return space.states().into_iter().map(|(id, state)| (id.to_string(), state)).collect();
+}
+
+/// The id of a state once its machine is renamed
+fn move_to_machine(id: &str, machine_map: &BTreeMap<MachineId, MachineId>) -> String {
+    let (machine, path) = id.split_once('/').unwrap_or((id, ""));
+    return match machine_map.get(&MachineId(machine.to_string())) {
+        Some(new_machine) if !path.is_empty() => new_machine.0.clone() + "/" + path,
+        _ => id.to_string(),
+    };
+}
@@ -0,0 +1,233 @@
+//!
+//! ! Minimize
+//! Merge equivalent states of a Space. Two states are equivalent, or
+//! bisimilar, when they have the same arrows, by id, weight, guard, effects
+//! and duration, and each arrow leads to equivalent states. Descriptions are
+//! not compared.
+//!
+//! Equivalence classes are found by partition refinement: states start out
+//! grouped by their arrow ids, and groups are split until the arrows of all
+//! their states lead to the same groups. The first state of each class, in
+//! Space order, represents it in the minimized Space, and arrows to the other
+//! states of the class point at it instead.
+//!
+//! ----------------------------------------------------------------------------
+//! type: computer for Elea
+//! ----------------------------------------------------------------------------
+
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+
+use crate::define::experience::representation::rust_minimal::{ArrowId, StateId};
+use crate::define::experience::representation::rust_pragmatic::{
+    Arrow, Machine, Space, SpaceError, State
+};
+
+//------------------------------------------------------------------------------
+// MINIMIZE
+
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct MinimizeOptions {
+    /// Also merge states of different machines. By default, machines keep
+    /// their states apart.
+    pub across_machines: bool,
+}
+
+pub struct Minimization {
+    pub space: Space,
+    /// The fully qualified id of each state of the original Space in the
+    /// minimized Space
+    pub mapping: BTreeMap<StateId, StateId>,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum MinimizeError {
+    Space(Vec<SpaceError>),
+}
+
+impl fmt::Display for MinimizeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            MinimizeError::Space(errors) => {
+                let errors: Vec<String> = errors.iter().map(|err| err.to_string()).collect();
+                write!(f, "invalid space: {}", errors.join(", "))
+            },
+        }
+    }
+}
+
+impl Minimization {
+
+    /// Classes of more than one state, each with its representative first
+    pub fn merged(&self) -> Vec<Vec<StateId>> {
+        let mut classes: BTreeMap<&StateId, Vec<StateId>> = BTreeMap::new();
+        for (state, representative) in &self.mapping {
+            classes.entry(representative).or_default().push(state.clone());
+        }
+        return classes.into_iter()
+            .filter(|(_, states)| states.len() > 1)
+            .map(|(representative, mut states)| {
+                states.retain(|state| state != representative);
+                states.insert(0, representative.clone());
+                states
+            })
+            .collect();
+    }
+
+    pub fn removed_count(&self) -> usize {
+        return self.mapping.iter().filter(|(state, representative)| state != representative).count();
+    }
+}
+
+impl fmt::Display for Minimization {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let merged = self.merged();
+        if merged.is_empty() {
+            return writeln!(f, "no equivalent states");
+        }
+        for class in merged {
+            let others: Vec<&str> = class[1..].iter().map(|state| state.0.as_str()).collect();
+            writeln!(f, "{} <- {}", class[0].0, others.join(", "))?;
+        }
+        return writeln!(f, "{} states removed", self.removed_count());
+    }
+}
+
+/// Merge the bisimilar states of a Space
+pub fn minimize(space: &Space, options: MinimizeOptions) -> Result<Minimization, MinimizeError> {
+    let errors = space.validate();
+    if !errors.is_empty() {
+        return Err(MinimizeError::Space(errors));
+    }
+
+    let states = space.states();
+    let index_of: HashMap<StateId, usize> = states.iter()
+        .enumerate()
+        .map(|(index, (id, _))| (id.to_state_id(), index))
+        .collect();
+    // arrows of each state as labels and next state indexes
+    let arrows: Vec<Vec<(Label, usize)>> = states.iter()
+        .map(|(_, state)| {
+            state.arrows.iter()
+                .map(|arrow| {
+                    let label = Label::new(
+                        Some(&arrow.id), arrow.weight, arrow.guard.as_ref(), &arrow.effects, arrow.after.as_ref(),
+                    );
+                    return (label, index_of[&arrow.next]);
+                })
+                .collect()
+        })
+        .collect();
+
+    let machines = classify(states.iter().map(|(id, _)| {
+        return if options.across_machines { None } else { Some(id.machine().clone()) };
+    }));
+    let classes = refine(&arrows, &machines);
+
+    // the first state of each class represents it
+    let mut representatives: HashMap<usize, usize> = HashMap::new();
+    for (index, class) in classes.iter().enumerate() {
+        representatives.entry(*class).or_insert(index);
+    }
+    let representative_of = |index: usize| representatives[&classes[index]];
+    let mapping: BTreeMap<StateId, StateId> = states.iter()
+        .enumerate()
+        .map(|(index, (id, _))| (id.to_state_id(), states[representative_of(index)].0.to_state_id()))
+        .collect();
+
+    let machines = space.machines.iter()
+        .map(|machine| {
+            let kept = machine.states.iter()
+                .filter(|state| {
+                    let id = StateId(format!("{}/{}", machine.id.0, state.id.0));
+                    return mapping.get(&id) == Some(&id);
+                })
+                .map(|state| {
+                    let arrows = state.arrows.iter()
+                        .map(|arrow| Arrow { next: mapping[&arrow.next].clone(), ..arrow.clone() })
+                        .collect();
+                    let mut kept = State::new(&state.id.0, arrows);
+                    kept.description = state.description.clone();
+                    kept
+                })
+                .collect();
+            let mut minimized = Machine::new(&machine.id.0, kept);
+            minimized.variables = machine.variables.clone();
+            return minimized;
+        })
+        .collect();
+    return Ok(Minimization { space: Space::new(machines), mapping });
+}
+
+//------------------------------------------------------------------------------
+// MINIMIZE / Helpers
+
+/// What tells arrows apart besides their next state: the weight, guard,
+/// effects and duration, and the id unless it is left out, for arrows that
+/// may be renamed. Arrows without a weight weigh 1.
+#[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub(crate) struct Label {
+    id: Option<ArrowId>,
+    weight: u64,
+    guard: Option<String>,
+    effects: Vec<String>,
+    after: Option<String>,
+}
+
+impl Label {
+
+    pub(crate) fn new(
+        id: Option<&ArrowId>,
+        weight: Option<f64>,
+        guard: Option<&String>,
+        effects: &[String],
+        after: Option<&String>,
+    ) -> Self {
+        return Self {
+            id: id.cloned(),
+            weight: weight.unwrap_or(1.0).to_bits(),
+            guard: guard.cloned(),
+            effects: effects.to_vec(),
+            after: after.cloned(),
+        };
+    }
+
+    pub(crate) fn id(&self) -> Option<&ArrowId> {
+        return self.id.as_ref();
+    }
+}
+
+/// Classes of states, the same when they are bisimilar. States start out in
+/// their class of `start`, split by the labels of their edges, and classes
+/// are split until the edges of all their states lead to the same classes.
+/// Edges are labels and next state indexes.
+pub(crate) fn refine(edges: &[Vec<(Label, usize)>], start: &[usize]) -> Vec<usize> {
+    let mut classes = classify(edges.iter().enumerate().map(|(state, state_edges)| {
+        let mut labels: Vec<&Label> = state_edges.iter().map(|(label, _)| label).collect();
+        labels.sort();
+        return (start[state], labels);
+    }));
+    loop {
+        let refined = classify(edges.iter().enumerate().map(|(state, state_edges)| {
+            let mut signature: Vec<(&Label, usize)> = state_edges.iter().map(|(label, next)| (label, classes[*next])).collect();
+            signature.sort();
+            return (classes[state], signature);
+        }));
+        let done = refined.iter().max() == classes.iter().max();
+        classes = refined;
+        if done {
+            return classes;
+        }
+    }
+}
+
+/// Number states by their key, the same key giving the same number
+pub(crate) fn classify<K: Ord>(keys: impl Iterator<Item = K>) -> Vec<usize> {
+    let keys: Vec<K> = keys.collect();
+    let mut numbers: BTreeMap<&K, usize> = BTreeMap::new();
+    for key in &keys {
+        let next = numbers.len();
+        numbers.entry(key).or_insert(next);
+    }
+    return keys.iter().map(|key| numbers[key]).collect();
+}
@@ -0,0 +1,4 @@
+pub mod compose;
+pub mod diff;
+pub mod minimize;
+pub mod rename;
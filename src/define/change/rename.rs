@@ -0,0 +1,435 @@
+//!
+//! ! Rename
+//! Rename a state of a NestedMachines directory, or move it to another
+//! machine or directory. The State file defining it is moved to where its new
+//! id places it, and every arrow pointing at it is rewritten. Only the `next`
+//! of those arrows and the `id` of the moved file change, in place: the rest
+//! of each file, comments and layout included, is kept.
+//!
+//! Every file is parsed and its rewrite verified before anything is written,
+//! so nothing is written if parsing or verification fails. Files are then
+//! written next to where they go, under a hidden name, and moved into place
+//! once all of them were written. Directories left empty by the move are
+//! removed.
+//!
+//! Only states with a State file of their own, loaded from the machines
+//! directory itself, can be renamed: states of Tree files and states included
+//! from elsewhere take their id from where they are loaded. States below the
+//! renamed state keep their ids.
+//!
+//! ----------------------------------------------------------------------------
+//! type: computer for Elea
+//! ----------------------------------------------------------------------------
+
+use serde_yaml::{Mapping, Value};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::define::experience::representation::filesystem::{
+    self, file_base_id, Format, MachinesError, SourceFile, DEFAULT_MACHINE_ID
+};
+use crate::define::experience::representation::qualified_id::{
+    QualifiedStateId, QualifiedStateIdError
+};
+use crate::define::experience::representation::rust_minimal::StateId;
+use crate::define::experience::representation::rust_pragmatic::Space;
+
+//------------------------------------------------------------------------------
+// RENAME
+
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct RenameOptions {
+    /// Report what would change without writing anything
+    pub dry_run: bool,
+}
+
+/// What a rename changed, or would change with `dry_run`. State ids are
+/// fully qualified.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct RenameReport {
+    pub from: StateId,
+    pub to: StateId,
+    /// The State file of the state, before and after the rename
+    pub file_from: PathBuf,
+    pub file_to: PathBuf,
+    /// Files with an arrow pointing at the state, at their path after the
+    /// rename
+    pub rewritten_files: Vec<PathBuf>,
+    pub dry_run: bool,
+}
+
+#[derive(Debug)]
+pub enum RenameError {
+    Machines(MachinesError),
+    InvalidId(QualifiedStateIdError),
+    UnknownState(StateId),
+    /// The new id, or the file it places the state in, is already taken
+    TargetExists(String),
+    /// The state is not defined by a State file of the machines directory
+    Unsupported { state: StateId, file_path: Option<String> },
+    File { file_path: String, error: String },
+    YAML { file_path: String, error: String },
+    /// A value to rewrite is written in a way it cannot be rewritten in
+    /// place, such as a key spanning lines
+    Rewrite { file_path: String },
+}
+
+impl fmt::Display for RenameError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            RenameError::Machines(err) => write!(f, "cannot load machines: {:?}", err),
+            RenameError::InvalidId(err) => write!(f, "invalid state id: {}", err),
+            RenameError::UnknownState(state) => write!(f, "unknown state [{}]", state.0),
+            RenameError::TargetExists(target) => write!(f, "[{}] already exists", target),
+            RenameError::Unsupported { state, file_path: Some(file_path) } => {
+                write!(f, "state [{}] is not defined by its own State file: {}", state.0, file_path)
+            },
+            RenameError::Unsupported { state, file_path: None } => {
+                write!(f, "state [{}] is not defined by a file of the machines directory", state.0)
+            },
+            RenameError::File { file_path, error } => write!(f, "{}: {}", file_path, error),
+            RenameError::YAML { file_path, error } => write!(f, "{}: invalid YAML: {}", file_path, error),
+            RenameError::Rewrite { file_path } => write!(f, "{}: cannot rewrite in place", file_path),
+        }
+    }
+}
+
+impl RenameReport {
+    /// Every file written, created or removed, in path order
+    pub fn touched_files(&self) -> Vec<PathBuf> {
+        let mut files = self.rewritten_files.clone();
+        files.push(self.file_from.clone());
+        files.push(self.file_to.clone());
+        files.sort();
+        files.dedup();
+        return files;
+    }
+}
+
+impl fmt::Display for RenameReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.dry_run {
+            writeln!(f, "dry run, nothing written")?;
+        }
+        writeln!(f, "rename {} -> {}", self.from.0, self.to.0)?;
+        if self.file_from != self.file_to {
+            writeln!(f, "moved file     {} -> {}", self.file_from.display(), self.file_to.display())?;
+        }
+        for path in &self.rewritten_files {
+            writeln!(f, "rewritten file {}", path.display())?;
+        }
+        return Ok(());
+    }
+}
+
+/// Rename the state `from` of a machines directory to `to`, both fully
+/// qualified
+pub fn rename_state(machines_path: &Path, from: &str, to: &str, options: RenameOptions) -> Result<RenameReport, RenameError> {
+    let from_id = QualifiedStateId::parse(from).map_err(RenameError::InvalidId)?;
+    let to_id = QualifiedStateId::parse(to).map_err(RenameError::InvalidId)?;
+
+    let sources = filesystem::to_sources(machines_path, Format::NestedMachines).map_err(RenameError::Machines)?;
+    let space = Space::new(filesystem::machines_from_sources(&sources));
+    if space.resolve(&from_id.to_string()).is_none() {
+        return Err(RenameError::UnknownState(from_id.to_state_id()));
+    }
+    if from_id != to_id && space.resolve(&to_id.to_string()).is_some() {
+        return Err(RenameError::TargetExists(to_id.to_string()));
+    }
+
+    let file_from = state_file(machines_path, &sources.files, &from_id)?;
+    let (file_to, local_id) = state_file_path(machines_path, &to_id);
+    if file_to != file_from && file_to.exists() {
+        return Err(RenameError::TargetExists(file_to.display().to_string()));
+    }
+
+    // new content of every file to write, by path after the rename
+    let mut contents: BTreeMap<PathBuf, String> = BTreeMap::new();
+    let mut rewritten_files = Vec::new();
+    for file in sources.files.iter().filter(|file| !file.is_include) {
+        let content = read_file(&file.path)?;
+        let mut value = parse_yaml(&file.path, &content)?;
+        if !is_state_value(&value) {
+            continue;
+        }
+        let rewritten = rewrite_arrows(&mut value, &from_id.to_string(), &to_id.to_string());
+        let is_moved = file.path == file_from;
+        if !is_moved && !rewritten {
+            continue;
+        }
+
+        let mut new_content = content.clone();
+        if rewritten {
+            new_content = replace_scalars(&new_content, "next", false, &from_id.to_string(), &to_id.to_string());
+        }
+        if is_moved {
+            if let Value::Mapping(mapping) = &mut value {
+                mapping.insert(Value::from("id"), Value::from(local_id.as_str()));
+            }
+            new_content = replace_scalars(&new_content, "id", true, &from_id.local_id().0, &local_id);
+        }
+        // the text must say what the rewritten value says
+        if parse_yaml(&file.path, &new_content).ok().as_ref() != Some(&value) {
+            return Err(RenameError::Rewrite { file_path: file.path.display().to_string() });
+        }
+
+        let path = if is_moved { file_to.clone() } else { file.path.clone() };
+        if rewritten {
+            rewritten_files.push(path.clone());
+        }
+        contents.insert(path, new_content);
+    }
+    rewritten_files.sort();
+
+    if !options.dry_run {
+        write_staged(&contents)?;
+        if file_from != file_to {
+            fs::remove_file(&file_from).map_err(|err| file_error(&file_from, err))?;
+            remove_empty_parents(&file_from, machines_path)?;
+        }
+    }
+
+    return Ok(RenameReport {
+        from: from_id.to_state_id(),
+        to: to_id.to_state_id(),
+        file_from,
+        file_to,
+        rewritten_files,
+        dry_run: options.dry_run,
+    });
+}
+
+//------------------------------------------------------------------------------
+// RENAME / Helpers
+
+/// The State file defining a state, which must be loaded from the machines
+/// directory at the place its path gives it
+fn state_file(machines_path: &Path, files: &[SourceFile], state_id: &QualifiedStateId) -> Result<PathBuf, RenameError> {
+    let unsupported = |file_path: Option<&Path>| RenameError::Unsupported {
+        state: state_id.to_state_id(),
+        file_path: file_path.map(|path| path.display().to_string()),
+    };
+    let file = files.iter()
+        .find(|file| {
+            file.base_id.machine() == state_id.machine()
+                && file.states.iter().any(|state| state.id == state_id.local_id())
+        })
+        .ok_or_else(|| unsupported(None))?;
+
+    let relative_path = file.path.strip_prefix(machines_path).map_err(|_| unsupported(None))?;
+    if file_base_id(None, relative_path).ok().as_ref() != Some(&file.base_id) {
+        return Err(unsupported(None));
+    }
+    if !is_state_value(&read_yaml(&file.path)?) {
+        return Err(unsupported(Some(&file.path)));
+    }
+    return Ok(file.path.clone());
+}
+
+/// Where a State file places a state, and the id written in it. Root files
+/// belong to the default machine, so its states all live at the root.
+fn state_file_path(machines_path: &Path, state_id: &QualifiedStateId) -> (PathBuf, String) {
+    let name = state_id.name().unwrap_or_default();
+    if state_id.machine().0 == DEFAULT_MACHINE_ID {
+        return (machines_path.join(format!("{}.yaml", name)), state_id.local_id().0);
+    }
+    let mut path = machines_path.join(&state_id.machine().0);
+    for segment in &state_id.path()[..state_id.path().len() - 1] {
+        path.push(segment);
+    }
+    path.push(format!("{}.yaml", name));
+    return (path, name.to_string());
+}
+
+fn read_yaml(path: &Path) -> Result<Value, RenameError> {
+    return parse_yaml(path, &read_file(path)?);
+}
+
+fn read_file(path: &Path) -> Result<String, RenameError> {
+    return fs::read_to_string(path).map_err(|err| file_error(path, err));
+}
+
+fn parse_yaml(path: &Path, content: &str) -> Result<Value, RenameError> {
+    return serde_yaml::from_str(content).map_err(|err| RenameError::YAML {
+        file_path: path.display().to_string(),
+        error: err.to_string(),
+    });
+}
+
+/// Whether a file is in the State format, see `yaml_state_file_format`
+fn is_state_value(value: &Value) -> bool {
+    let is_key = |mapping: &Mapping, key: &str| mapping.contains_key(Value::from(key));
+    return match value {
+        Value::Mapping(mapping) => {
+            is_key(mapping, "arrows") && !["tree", "states", "include"].iter().any(|key| is_key(mapping, key))
+        },
+        _ => false,
+    };
+}
+
+/// Point arrows at `from` to `to`. Returns whether any arrow changed.
+fn rewrite_arrows(value: &mut Value, from: &str, to: &str) -> bool {
+    let mut rewritten = false;
+    if let Some(Value::Sequence(arrows)) = value.get_mut("arrows") {
+        for arrow in arrows {
+            if let Some(next) = arrow.get_mut("next") {
+                if next.as_str() == Some(from) {
+                    *next = Value::from(to);
+                    rewritten = true;
+                }
+            }
+        }
+    }
+    return rewritten;
+}
+
+/// Replace the scalar values `from` of the mapping key `key` with `to`, in
+/// block or flow mappings, keeping their quotes. With `top_level`, only keys
+/// at the start of a line are replaced.
+fn replace_scalars(content: &str, key: &str, top_level: bool, from: &str, to: &str) -> String {
+    let pattern = format!("{}:", key);
+    let mut out = String::with_capacity(content.len());
+    for line in content.split_inclusive('\n') {
+        let mut rest = line;
+        let mut offset = 0;
+        while let Some(found) = rest.find(&pattern) {
+            let start = offset + found;
+            let before = line[..start].chars().next_back();
+            let is_key = match before {
+                None => true,
+                Some(c) => !top_level && (c.is_whitespace() || c == '{' || c == ','),
+            };
+            let value_start = start + pattern.len();
+            let after = &line[value_start..];
+            let spaces = after.len() - after.trim_start_matches([' ', '\t']).len();
+            let in_flow = line[..start].contains('{');
+            match scalar_span(&after[spaces..], in_flow) {
+                Some((length, text)) if is_key && spaces > 0 && text == from => {
+                    let quoted = &after[spaces..spaces + length];
+                    out.push_str(&line[offset..value_start + spaces]);
+                    out.push_str(&quote_like(quoted, to));
+                    offset = value_start + spaces + length;
+                },
+                _ => {
+                    out.push_str(&line[offset..value_start]);
+                    offset = value_start;
+                },
+            }
+            rest = &line[offset..];
+        }
+        out.push_str(&line[offset..]);
+    }
+    return out;
+}
+
+/// Length of the scalar at the start of `text` as written, and its value.
+/// Plain scalars end at a comment or the end of the line, or in a flow
+/// mapping at a `,` or `}`.
+fn scalar_span(text: &str, in_flow: bool) -> Option<(usize, String)> {
+    if let Some(quoted) = text.strip_prefix('"') {
+        let mut escaped = false;
+        for (index, c) in quoted.char_indices() {
+            match c {
+                '"' if !escaped => {
+                    let length = index + 2;
+                    return serde_json::from_str(&text[..length]).ok().map(|value| (length, value));
+                },
+                '\\' => escaped = !escaped,
+                _ => escaped = false,
+            }
+        }
+        return None;
+    }
+    if let Some(quoted) = text.strip_prefix('\'') {
+        let mut value = String::new();
+        let mut chars = quoted.char_indices().peekable();
+        while let Some((index, c)) = chars.next() {
+            if c == '\'' {
+                if chars.peek().is_some_and(|(_, next)| *next == '\'') {
+                    chars.next();
+                } else {
+                    return Some((index + 2, value));
+                }
+            }
+            value.push(c);
+        }
+        return None;
+    }
+    let mut end = text.trim_end_matches(['\r', '\n']).len();
+    if let Some(comment) = text.find(" #") {
+        end = end.min(comment);
+    }
+    if in_flow {
+        if let Some(flow_end) = text.find([',', '}']) {
+            end = end.min(flow_end);
+        }
+    }
+    let value = text[..end].trim_end();
+    return Some((value.len(), value.to_string()));
+}
+
+/// `value` written with the quotes of `written`
+fn quote_like(written: &str, value: &str) -> String {
+    if written.starts_with('"') {
+        return serde_json::to_string(value).unwrap_or_default();
+    }
+    if written.starts_with('\'') {
+        return format!("'{}'", value.replace('\'', "''"));
+    }
+    return value.to_string();
+}
+
+/// Write every file to a hidden file next to it, then move them all into
+/// place. Nothing is moved unless every file was written.
+fn write_staged(contents: &BTreeMap<PathBuf, String>) -> Result<(), RenameError> {
+    let mut staged: Vec<(PathBuf, &PathBuf)> = Vec::new();
+    let mut stage = || -> Result<(), RenameError> {
+        for (path, content) in contents {
+            if let Some(parent) = path.parent() {
+                fs::create_dir_all(parent).map_err(|err| file_error(parent, err))?;
+            }
+            let file_name = path.file_name().unwrap_or_default().to_string_lossy();
+            let staged_path = path.with_file_name(format!(".{}.elea-rename", file_name));
+            fs::write(&staged_path, content).map_err(|err| file_error(&staged_path, err))?;
+            staged.push((staged_path, path));
+        }
+        return Ok(());
+    };
+    if let Err(err) = stage() {
+        for (staged_path, _) in &staged {
+            let _ = fs::remove_file(staged_path);
+        }
+        return Err(err);
+    }
+    for (staged_path, path) in &staged {
+        fs::rename(staged_path, path).map_err(|err| file_error(path, err))?;
+    }
+    return Ok(());
+}
+
+/// Remove the directories above `file_path` that are left empty, up to the
+/// machines directory
+fn remove_empty_parents(file_path: &Path, machines_path: &Path) -> Result<(), RenameError> {
+    let mut dir = file_path.parent();
+    while let Some(path) = dir {
+        if path == machines_path || !path.starts_with(machines_path) {
+            break;
+        }
+        let mut entries = fs::read_dir(path).map_err(|err| file_error(path, err))?;
+        if entries.next().is_some() {
+            break;
+        }
+        fs::remove_dir(path).map_err(|err| file_error(path, err))?;
+        dir = path.parent();
+    }
+    return Ok(());
+}
+
+fn file_error(path: &Path, err: std::io::Error) -> RenameError {
+    return RenameError::File {
+        file_path: path.display().to_string(),
+        error: err.to_string(),
+    };
+}
@@ -0,0 +1,163 @@
+//!
+//! ! Context
+//! Typed key/value data a runtime holds next to its current state. Guards
+//! read it to decide whether an arrow may be taken.
+//!
+//! ----------------------------------------------------------------------------
+//! type: computer for Elea
+//! ----------------------------------------------------------------------------
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+
+//------------------------------------------------------------------------------
+// CONTEXT
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(untagged)]
+pub enum Value {
+    Bool(bool),
+    Int(i64),
+    Float(f64),
+    Text(String),
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Type {
+    Bool,
+    Int,
+    Float,
+    Text,
+}
+
+/// Context
+///
+/// Values by key, in key order
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+#[serde(transparent)]
+pub struct Context {
+    values: BTreeMap<String, Value>,
+}
+
+impl Value {
+
+    pub fn type_of(&self) -> Type {
+        return match self {
+            Value::Bool(_) => Type::Bool,
+            Value::Int(_) => Type::Int,
+            Value::Float(_) => Type::Float,
+            Value::Text(_) => Type::Text,
+        };
+    }
+
+    /// The value as a `to`, integers widening to floats
+    pub fn convert(self, to: Type) -> Option<Value> {
+        return match (self, to) {
+            (Value::Int(value), Type::Float) => Some(Value::Float(value as f64)),
+            (value, to) if value.type_of() == to => Some(value),
+            _ => None,
+        };
+    }
+}
+
+impl Type {
+
+    /// Whether values of type `found` can be stored as this type, see
+    /// `Value::convert`
+    pub fn accepts(&self, found: Type) -> bool {
+        return *self == found || (*self == Type::Float && found == Type::Int);
+    }
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Value::Bool(value) => write!(f, "{}", value),
+            Value::Int(value) => write!(f, "{}", value),
+            // floats always show a decimal point, `20.0` rather than `20`
+            Value::Float(value) => write!(f, "{:?}", value),
+            Value::Text(value) => write!(f, "{:?}", value),
+        }
+    }
+}
+
+impl fmt::Display for Type {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Type::Bool => write!(f, "bool"),
+            Type::Int => write!(f, "int"),
+            Type::Float => write!(f, "float"),
+            Type::Text => write!(f, "text"),
+        }
+    }
+}
+
+impl From<bool> for Value {
+    fn from(value: bool) -> Self {
+        return Value::Bool(value);
+    }
+}
+
+impl From<i64> for Value {
+    fn from(value: i64) -> Self {
+        return Value::Int(value);
+    }
+}
+
+impl From<f64> for Value {
+    fn from(value: f64) -> Self {
+        return Value::Float(value);
+    }
+}
+
+impl From<&str> for Value {
+    fn from(value: &str) -> Self {
+        return Value::Text(value.to_string());
+    }
+}
+
+impl From<String> for Value {
+    fn from(value: String) -> Self {
+        return Value::Text(value);
+    }
+}
+
+impl Context {
+
+    pub fn new() -> Self {
+        return Self::default();
+    }
+
+    pub fn get(&self, key: &str) -> Option<&Value> {
+        return self.values.get(key);
+    }
+
+    /// Set a value, returning the value it replaces
+    pub fn set(&mut self, key: &str, value: impl Into<Value>) -> Option<Value> {
+        return self.values.insert(key.to_string(), value.into());
+    }
+
+    pub fn remove(&mut self, key: &str) -> Option<Value> {
+        return self.values.remove(key);
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&String, &Value)> {
+        return self.values.iter();
+    }
+
+    pub fn len(&self) -> usize {
+        return self.values.len();
+    }
+
+    pub fn is_empty(&self) -> bool {
+        return self.values.is_empty();
+    }
+}
+
+impl<K: Into<String>, V: Into<Value>> FromIterator<(K, V)> for Context {
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(values: I) -> Self {
+        return Self { values: values.into_iter().map(|(key, value)| (key.into(), value.into())).collect() };
+    }
+}
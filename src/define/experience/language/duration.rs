@@ -0,0 +1,85 @@
+//!
+//! ! Duration
+//! Durations of timed arrows, the time a runtime stays in a state before the
+//! arrow can be taken, see `unite::timer`.
+//!
+//! ```yaml
+//! arrows:
+//!   - id: calm
+//!     next: default/on
+//!     after: 10s
+//! ```
+//!
+//! Durations are one or more amounts with a unit, `ms`, `s`, `m` or `h`,
+//! such as `250ms`, `10s` or `1m 30s`, and are never zero.
+//!
+//! ----------------------------------------------------------------------------
+//! type: computer for Elea
+//! ----------------------------------------------------------------------------
+
+use std::fmt;
+use std::time::Duration;
+
+//------------------------------------------------------------------------------
+// DURATION
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum DurationError {
+    /// Byte offset in the duration, and what was expected there
+    Parse { position: usize, message: String },
+    Zero,
+}
+
+impl fmt::Display for DurationError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            DurationError::Parse { position, message } => write!(f, "at {}: {}", position, message),
+            DurationError::Zero => write!(f, "duration is zero"),
+        }
+    }
+}
+
+/// Parse a duration such as `1m 30s`
+pub fn parse_duration(source: &str) -> Result<Duration, DurationError> {
+    let parse_error = |position: usize, message: &str| DurationError::Parse { position, message: message.to_string() };
+    let mut duration = Duration::ZERO;
+    let mut position = 0;
+    let mut amounts = 0;
+    while position < source.len() {
+        let rest = &source[position..];
+        let trimmed = rest.trim_start();
+        if trimmed.is_empty() {
+            break;
+        }
+        position += rest.len() - trimmed.len();
+
+        let digits = trimmed.find(|c: char| !c.is_ascii_digit()).unwrap_or(trimmed.len());
+        if digits == 0 {
+            return Err(parse_error(position, "expected an amount"));
+        }
+        let amount: u64 = trimmed[..digits].parse().map_err(|_| parse_error(position, "amount is too large"))?;
+        position += digits;
+
+        let rest = &source[position..];
+        let unit = rest.find(|c: char| !c.is_ascii_alphabetic()).unwrap_or(rest.len());
+        let millis = match &rest[..unit] {
+            "ms" => 1,
+            "s" => 1_000,
+            "m" => 60_000,
+            "h" => 3_600_000,
+            "" => return Err(parse_error(position, "expected a unit, [ms], [s], [m] or [h]")),
+            other => return Err(parse_error(position, &format!("unknown unit [{}]", other))),
+        };
+        let amount = amount.checked_mul(millis).ok_or_else(|| parse_error(position, "amount is too large"))?;
+        duration = duration.checked_add(Duration::from_millis(amount)).ok_or_else(|| parse_error(position, "amount is too large"))?;
+        position += unit;
+        amounts += 1;
+    }
+    if amounts == 0 {
+        return Err(parse_error(source.len(), "expected an amount"));
+    }
+    if duration.is_zero() {
+        return Err(DurationError::Zero);
+    }
+    return Ok(duration);
+}
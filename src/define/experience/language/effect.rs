@@ -0,0 +1,218 @@
+//!
+//! ! Effect
+//! Assignments to the variables of a machine, made when an arrow is taken.
+//! Machines declare their variables, each with a type and a default, in a
+//! Variables file at the root of the machine's directory.
+//!
+//! ```yaml
+//! variables:
+//!   brightness:
+//!     type: int
+//!     default: 100
+//!   power_saving:
+//!     type: bool
+//!     default: false
+//! ```
+//!
+//! Arrows list their effects, applied in order, each seeing the values the
+//! previous ones set. The value assigned is an expression, as in guards.
+//!
+//! ```yaml
+//! arrows:
+//!   - id: dim
+//!     next: default/on
+//!     effects:
+//!       - set brightness = brightness / 2
+//!       - set power_saving = true
+//! ```
+//!
+//! An effect may only assign a variable its machine declares, with a value
+//! of the variable's type; integers are stored in float variables as floats.
+//! `check` verifies this without running the machine.
+//!
+//! ----------------------------------------------------------------------------
+//! type: computer for Elea
+//! ----------------------------------------------------------------------------
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::str::FromStr;
+
+use crate::define::experience::language::context::{Context, Type};
+use crate::define::experience::language::guard::{self, Expr, GuardError};
+use crate::define::experience::representation::rust_minimal::{ArrowId, StateId};
+use crate::define::experience::representation::rust_pragmatic::{Machine, Variable};
+
+//------------------------------------------------------------------------------
+// EFFECT
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Effect {
+    pub source: String,
+    pub variable: String,
+    pub expr: Expr,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum EffectError {
+    /// Byte offset in the effect, and what was expected there
+    Parse { position: usize, message: String },
+    /// The machine declares no such variable
+    UndeclaredVariable(String),
+    /// The value assigned is not of the variable's type
+    TypeMismatch { variable: String, expected: Type, found: Type },
+    /// The value assigned does not evaluate
+    Expr(GuardError),
+}
+
+/// A problem of the variables or effects of a machine. State ids are local
+/// to the machine.
+#[derive(Clone, Debug, PartialEq)]
+pub enum CheckError {
+    /// The default of a variable is not of its type
+    Default { variable: String, expected: Type, found: Type },
+    Effect { state: StateId, arrow: ArrowId, effect: String, error: EffectError },
+}
+
+impl fmt::Display for EffectError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            EffectError::Parse { position, message } => write!(f, "at {}: {}", position, message),
+            EffectError::UndeclaredVariable(name) => write!(f, "undeclared variable [{}]", name),
+            EffectError::TypeMismatch { variable, expected, found } => {
+                write!(f, "variable [{}] is {}, not {}", variable, expected, found)
+            },
+            EffectError::Expr(err) => write!(f, "{}", err),
+        }
+    }
+}
+
+impl fmt::Display for CheckError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CheckError::Default { variable, expected, found } => {
+                write!(f, "default of variable [{}] is {}, not {}", variable, found, expected)
+            },
+            CheckError::Effect { state, arrow, effect, error } => {
+                write!(f, "effect [{}] of arrow [{}] of [{}]: {}", effect, arrow.0, state.0, error)
+            },
+        }
+    }
+}
+
+impl Effect {
+
+    /// Parse `set <variable> = <expression>`
+    pub fn parse(source: &str) -> Result<Self, EffectError> {
+        let parse_error = |position: usize, message: &str| EffectError::Parse { position, message: message.to_string() };
+        let skip_space = |position: usize| {
+            position + source[position..].len() - source[position..].trim_start().len()
+        };
+
+        let mut position = skip_space(0);
+        if !source[position..].starts_with("set") || !source[position + 3..].starts_with(char::is_whitespace) {
+            return Err(parse_error(position, "expected [set]"));
+        }
+        position = skip_space(position + 3);
+        let length = source[position..]
+            .find(|c: char| !(c.is_alphanumeric() || c == '_' || c == '.'))
+            .unwrap_or(source.len() - position);
+        let variable = &source[position..position + length];
+        if !variable.starts_with(|c: char| c.is_alphabetic() || c == '_') {
+            return Err(parse_error(position, "expected a variable"));
+        }
+        position = skip_space(position + length);
+        if !source[position..].starts_with('=') || source[position..].starts_with("==") {
+            return Err(parse_error(position, "expected [=]"));
+        }
+
+        let expr = guard::parse_expr(source, position + 1).map_err(|err| match err {
+            GuardError::Parse { position, message } => EffectError::Parse { position, message },
+            err => EffectError::Expr(err),
+        })?;
+        return Ok(Self { source: source.to_string(), variable: variable.to_string(), expr });
+    }
+
+    /// Assign the variable its value in `context`, as the type `variables`
+    /// declares for it
+    pub fn apply(&self, variables: &BTreeMap<String, Variable>, context: &mut Context) -> Result<(), EffectError> {
+        let declared = variables.get(&self.variable).ok_or_else(|| EffectError::UndeclaredVariable(self.variable.clone()))?;
+        let value = self.expr.evaluate(context).map_err(EffectError::Expr)?;
+        let found = value.type_of();
+        let value = value.convert(declared.type_).ok_or_else(|| EffectError::TypeMismatch {
+            variable: self.variable.clone(),
+            expected: declared.type_,
+            found,
+        })?;
+        context.set(&self.variable, value);
+        return Ok(());
+    }
+
+    /// Check the effect against the variables of its machine
+    pub fn check(&self, variables: &BTreeMap<String, Variable>) -> Result<(), EffectError> {
+        let declared = variables.get(&self.variable).ok_or_else(|| EffectError::UndeclaredVariable(self.variable.clone()))?;
+        let types = |name: &str| variables.get(name).map(|variable| variable.type_);
+        let found = self.expr.type_of(&types).map_err(|err| match err {
+            GuardError::UnknownVariable(name) => EffectError::UndeclaredVariable(name),
+            err => EffectError::Expr(err),
+        })?;
+        if !declared.type_.accepts(found) {
+            return Err(EffectError::TypeMismatch { variable: self.variable.clone(), expected: declared.type_, found });
+        }
+        return Ok(());
+    }
+}
+
+impl FromStr for Effect {
+    type Err = EffectError;
+
+    fn from_str(source: &str) -> Result<Self, Self::Err> {
+        return Effect::parse(source);
+    }
+}
+
+impl fmt::Display for Effect {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        return write!(f, "{}", self.source);
+    }
+}
+
+/// Check the defaults of a machine's variables and the effects of its
+/// arrows, in state and arrow order
+pub fn check(machine: &Machine) -> Vec<CheckError> {
+    let mut errors = Vec::new();
+    for (name, variable) in &machine.variables {
+        if !variable.type_.accepts(variable.default.type_of()) {
+            errors.push(CheckError::Default {
+                variable: name.clone(),
+                expected: variable.type_,
+                found: variable.default.type_of(),
+            });
+        }
+    }
+    for state in &machine.states {
+        for arrow in &state.arrows {
+            for effect in &arrow.effects {
+                if let Err(error) = Effect::parse(effect).and_then(|parsed| parsed.check(&machine.variables)) {
+                    errors.push(CheckError::Effect {
+                        state: state.id.clone(),
+                        arrow: arrow.id.clone(),
+                        effect: effect.clone(),
+                        error,
+                    });
+                }
+            }
+        }
+    }
+    return errors;
+}
+
+/// The defaults of a machine's variables, as their declared types
+pub fn defaults(machine: &Machine) -> Context {
+    return machine.variables.iter()
+        .map(|(name, variable)| {
+            let value = variable.default.clone().convert(variable.type_).unwrap_or_else(|| variable.default.clone());
+            (name.clone(), value)
+        })
+        .collect();
+}
@@ -0,0 +1,559 @@
+//!
+//! ! Guard
+//! Conditions on arrows, over the context of a runtime. An arrow with a guard
+//! may only be taken when its guard is true.
+//!
+//! ```yaml
+//! arrows:
+//!   - id: strobe
+//!     next: pattern/strobe
+//!     guard: battery_level >= 20 && !power_saving
+//! ```
+//!
+//! Guards are expressions of context keys, literals (`true`, `false`,
+//! numbers, and text in single or double quotes), comparisons (`==`, `!=`,
+//! `<`, `<=`, `>`, `>=`), arithmetic (`+`, `-`, `*`, `/`, `%`) and logic
+//! (`!`, `&&`, `||`, or `not`, `and`, `or`). Integers and floats mix, the
+//! result being a float; text compares to text and `+` joins it. Other mixes
+//! of types, keys missing from the context, and guards that are not booleans
+//! are errors, which a runtime reports instead of taking or denying the arrow.
+//!
+//! Analyses of a Space, in `plan`, take arrows regardless of their guards.
+//!
+//! ----------------------------------------------------------------------------
+//! type: computer for Elea
+//! ----------------------------------------------------------------------------
+
+use std::cmp::Ordering;
+use std::fmt;
+use std::str::FromStr;
+
+use crate::define::experience::language::context::{Context, Type, Value};
+
+//------------------------------------------------------------------------------
+// GUARD
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Guard {
+    pub source: String,
+    pub expr: Expr,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Expr {
+    Literal(Value),
+    /// A context key
+    Variable(String),
+    Not(Box<Expr>),
+    Neg(Box<Expr>),
+    Binary(BinaryOp, Box<Expr>, Box<Expr>),
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum BinaryOp {
+    Or,
+    And,
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Rem,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum GuardError {
+    /// Byte offset in the guard, and what was expected there
+    Parse { position: usize, message: String },
+    UnknownVariable(String),
+    /// An operator applied to values of types it does not take
+    TypeMismatch { operator: String, types: Vec<Type> },
+    /// The guard is not a boolean
+    NotBoolean(Type),
+    DivisionByZero,
+    Overflow,
+}
+
+impl fmt::Display for GuardError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            GuardError::Parse { position, message } => write!(f, "at {}: {}", position, message),
+            GuardError::UnknownVariable(name) => write!(f, "unknown variable [{}]", name),
+            GuardError::TypeMismatch { operator, types } => {
+                let types: Vec<String> = types.iter().map(|t| t.to_string()).collect();
+                write!(f, "[{}] does not take {}", operator, types.join(" and "))
+            },
+            GuardError::NotBoolean(found) => write!(f, "guard is {}, not bool", found),
+            GuardError::DivisionByZero => write!(f, "division by zero"),
+            GuardError::Overflow => write!(f, "integer overflow"),
+        }
+    }
+}
+
+impl Guard {
+
+    pub fn parse(source: &str) -> Result<Self, GuardError> {
+        return Ok(Self { source: source.to_string(), expr: parse_expr(source, 0)? });
+    }
+
+    /// Whether the guard holds in a context
+    pub fn evaluate(&self, context: &Context) -> Result<bool, GuardError> {
+        return match self.expr.evaluate(context)? {
+            Value::Bool(value) => Ok(value),
+            value => Err(GuardError::NotBoolean(value.type_of())),
+        };
+    }
+
+    /// Context keys the guard reads, in order of appearance
+    pub fn variables(&self) -> Vec<&str> {
+        let mut variables = Vec::new();
+        self.expr.visit(&mut |expr| {
+            if let Expr::Variable(name) = expr {
+                if !variables.contains(&name.as_str()) {
+                    variables.push(name.as_str());
+                }
+            }
+        });
+        return variables;
+    }
+}
+
+impl FromStr for Guard {
+    type Err = GuardError;
+
+    fn from_str(source: &str) -> Result<Self, Self::Err> {
+        return Guard::parse(source);
+    }
+}
+
+impl fmt::Display for Guard {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        return write!(f, "{}", self.source);
+    }
+}
+
+impl Expr {
+
+    pub fn evaluate(&self, context: &Context) -> Result<Value, GuardError> {
+        return match self {
+            Expr::Literal(value) => Ok(value.clone()),
+            Expr::Variable(name) => context.get(name).cloned().ok_or_else(|| GuardError::UnknownVariable(name.clone())),
+            Expr::Not(expr) => match expr.evaluate(context)? {
+                Value::Bool(value) => Ok(Value::Bool(!value)),
+                value => Err(mismatch("!", &[&value])),
+            },
+            Expr::Neg(expr) => match expr.evaluate(context)? {
+                Value::Int(value) => value.checked_neg().map(Value::Int).ok_or(GuardError::Overflow),
+                Value::Float(value) => Ok(Value::Float(-value)),
+                value => Err(mismatch("-", &[&value])),
+            },
+            Expr::Binary(op @ (BinaryOp::And | BinaryOp::Or), left, right) => {
+                // the right side is only evaluated when needed
+                let left = match left.evaluate(context)? {
+                    Value::Bool(value) => value,
+                    value => return Err(mismatch(&op.to_string(), &[&value])),
+                };
+                if left == (*op == BinaryOp::Or) {
+                    return Ok(Value::Bool(left));
+                }
+                match right.evaluate(context)? {
+                    Value::Bool(value) => Ok(Value::Bool(value)),
+                    value => Err(mismatch(&op.to_string(), &[&value])),
+                }
+            },
+            Expr::Binary(op, left, right) => op.apply(&left.evaluate(context)?, &right.evaluate(context)?),
+        };
+    }
+
+    /// The type of the expression when variables have the types `types`
+    /// gives, without evaluating it. Errors are those evaluation could meet,
+    /// but for division by zero and overflow.
+    pub fn type_of(&self, types: &dyn Fn(&str) -> Option<Type>) -> Result<Type, GuardError> {
+        return match self {
+            Expr::Literal(value) => Ok(value.type_of()),
+            Expr::Variable(name) => types(name).ok_or_else(|| GuardError::UnknownVariable(name.clone())),
+            Expr::Not(expr) => match expr.type_of(types)? {
+                Type::Bool => Ok(Type::Bool),
+                found => Err(type_mismatch("!", &[found])),
+            },
+            Expr::Neg(expr) => match expr.type_of(types)? {
+                found @ (Type::Int | Type::Float) => Ok(found),
+                found => Err(type_mismatch("-", &[found])),
+            },
+            Expr::Binary(op, left, right) => op.result_type(left.type_of(types)?, right.type_of(types)?),
+        };
+    }
+
+    fn visit<'a, V: FnMut(&'a Expr)>(&'a self, visitor: &mut V) {
+        visitor(self);
+        match self {
+            Expr::Literal(_) | Expr::Variable(_) => {},
+            Expr::Not(expr) | Expr::Neg(expr) => expr.visit(visitor),
+            Expr::Binary(_, left, right) => {
+                left.visit(visitor);
+                right.visit(visitor);
+            },
+        }
+    }
+}
+
+impl fmt::Display for Expr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Expr::Literal(value) => write!(f, "{}", value),
+            Expr::Variable(name) => write!(f, "{}", name),
+            Expr::Not(expr) => write!(f, "!{}", expr),
+            Expr::Neg(expr) => write!(f, "-{}", expr),
+            Expr::Binary(op, left, right) => write!(f, "({} {} {})", left, op, right),
+        }
+    }
+}
+
+impl fmt::Display for BinaryOp {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let op = match self {
+            BinaryOp::Or => "||",
+            BinaryOp::And => "&&",
+            BinaryOp::Eq => "==",
+            BinaryOp::Ne => "!=",
+            BinaryOp::Lt => "<",
+            BinaryOp::Le => "<=",
+            BinaryOp::Gt => ">",
+            BinaryOp::Ge => ">=",
+            BinaryOp::Add => "+",
+            BinaryOp::Sub => "-",
+            BinaryOp::Mul => "*",
+            BinaryOp::Div => "/",
+            BinaryOp::Rem => "%",
+        };
+        return write!(f, "{}", op);
+    }
+}
+
+//------------------------------------------------------------------------------
+// GUARD / Evaluation
+
+impl BinaryOp {
+
+    /// Apply a comparison or arithmetic operator
+    fn apply(&self, left: &Value, right: &Value) -> Result<Value, GuardError> {
+        let mismatch = || mismatch(&self.to_string(), &[left, right]);
+        return match self {
+            BinaryOp::Eq | BinaryOp::Ne | BinaryOp::Lt | BinaryOp::Le | BinaryOp::Gt | BinaryOp::Ge => {
+                let ordering = match (left, right) {
+                    (Value::Bool(l), Value::Bool(r)) if matches!(self, BinaryOp::Eq | BinaryOp::Ne) => Some(l.cmp(r)),
+                    (Value::Text(l), Value::Text(r)) => Some(l.cmp(r)),
+                    (Value::Int(l), Value::Int(r)) => Some(l.cmp(r)),
+                    _ => match (as_float(left), as_float(right)) {
+                        (Some(l), Some(r)) => l.partial_cmp(&r),
+                        _ => return Err(mismatch()),
+                    },
+                };
+                // comparisons with NaN are false, but for `!=`
+                let holds = match ordering {
+                    Some(ordering) => match self {
+                        BinaryOp::Eq => ordering == Ordering::Equal,
+                        BinaryOp::Ne => ordering != Ordering::Equal,
+                        BinaryOp::Lt => ordering == Ordering::Less,
+                        BinaryOp::Le => ordering != Ordering::Greater,
+                        BinaryOp::Gt => ordering == Ordering::Greater,
+                        _ => ordering != Ordering::Less,
+                    },
+                    None => *self == BinaryOp::Ne,
+                };
+                Ok(Value::Bool(holds))
+            },
+            BinaryOp::Add if matches!((left, right), (Value::Text(_), Value::Text(_))) => {
+                match (left, right) {
+                    (Value::Text(l), Value::Text(r)) => Ok(Value::Text(format!("{}{}", l, r))),
+                    _ => Err(mismatch()),
+                }
+            },
+            BinaryOp::Add | BinaryOp::Sub | BinaryOp::Mul | BinaryOp::Div | BinaryOp::Rem => {
+                if let (Value::Int(l), Value::Int(r)) = (left, right) {
+                    if matches!(self, BinaryOp::Div | BinaryOp::Rem) && *r == 0 {
+                        return Err(GuardError::DivisionByZero);
+                    }
+                    let result = match self {
+                        BinaryOp::Add => l.checked_add(*r),
+                        BinaryOp::Sub => l.checked_sub(*r),
+                        BinaryOp::Mul => l.checked_mul(*r),
+                        BinaryOp::Div => l.checked_div(*r),
+                        _ => l.checked_rem(*r),
+                    };
+                    return result.map(Value::Int).ok_or(GuardError::Overflow);
+                }
+                let (l, r) = match (as_float(left), as_float(right)) {
+                    (Some(l), Some(r)) => (l, r),
+                    _ => return Err(mismatch()),
+                };
+                if matches!(self, BinaryOp::Div | BinaryOp::Rem) && r == 0.0 {
+                    return Err(GuardError::DivisionByZero);
+                }
+                Ok(Value::Float(match self {
+                    BinaryOp::Add => l + r,
+                    BinaryOp::Sub => l - r,
+                    BinaryOp::Mul => l * r,
+                    BinaryOp::Div => l / r,
+                    _ => l % r,
+                }))
+            },
+            BinaryOp::And | BinaryOp::Or => Err(mismatch()),
+        };
+    }
+
+    /// Type of the result of the operator, see `apply`
+    fn result_type(&self, left: Type, right: Type) -> Result<Type, GuardError> {
+        let numeric = |found: Type| matches!(found, Type::Int | Type::Float);
+        return match self {
+            BinaryOp::And | BinaryOp::Or => match (left, right) {
+                (Type::Bool, Type::Bool) => Ok(Type::Bool),
+                (Type::Bool, found) | (found, _) => Err(type_mismatch(&self.to_string(), &[found])),
+            },
+            BinaryOp::Eq | BinaryOp::Ne if left == Type::Bool && right == Type::Bool => Ok(Type::Bool),
+            BinaryOp::Eq | BinaryOp::Ne | BinaryOp::Lt | BinaryOp::Le | BinaryOp::Gt | BinaryOp::Ge => {
+                match (left, right) {
+                    (Type::Text, Type::Text) => Ok(Type::Bool),
+                    _ if numeric(left) && numeric(right) => Ok(Type::Bool),
+                    _ => Err(type_mismatch(&self.to_string(), &[left, right])),
+                }
+            },
+            BinaryOp::Add if left == Type::Text && right == Type::Text => Ok(Type::Text),
+            BinaryOp::Add | BinaryOp::Sub | BinaryOp::Mul | BinaryOp::Div | BinaryOp::Rem => {
+                match (left, right) {
+                    (Type::Int, Type::Int) => Ok(Type::Int),
+                    _ if numeric(left) && numeric(right) => Ok(Type::Float),
+                    _ => Err(type_mismatch(&self.to_string(), &[left, right])),
+                }
+            },
+        };
+    }
+}
+
+fn as_float(value: &Value) -> Option<f64> {
+    return match value {
+        Value::Int(value) => Some(*value as f64),
+        Value::Float(value) => Some(*value),
+        _ => None,
+    };
+}
+
+fn mismatch(operator: &str, values: &[&Value]) -> GuardError {
+    let types: Vec<Type> = values.iter().map(|value| value.type_of()).collect();
+    return type_mismatch(operator, &types);
+}
+
+fn type_mismatch(operator: &str, types: &[Type]) -> GuardError {
+    return GuardError::TypeMismatch { operator: operator.to_string(), types: types.to_vec() };
+}
+
+//------------------------------------------------------------------------------
+// GUARD / Parser
+
+/// Parse the expression making up the end of `source`, from byte `start`.
+/// Error positions are offsets in `source`.
+pub(crate) fn parse_expr(source: &str, start: usize) -> Result<Expr, GuardError> {
+    let tokens = tokenize(&source[start..])?.into_iter()
+        .map(|(position, token)| (start + position, token))
+        .collect();
+    let mut parser = Parser { tokens, next: 0, end: source.len() };
+    let expr = parser.or()?;
+    if let Some((position, token)) = parser.tokens.get(parser.next) {
+        return Err(GuardError::Parse { position: *position, message: format!("unexpected [{}]", token) });
+    }
+    return Ok(expr);
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Token {
+    Symbol(&'static str),
+    Word(String),
+    Literal(Value),
+}
+
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Token::Symbol(symbol) => write!(f, "{}", symbol),
+            Token::Word(word) => write!(f, "{}", word),
+            Token::Literal(value) => write!(f, "{}", value),
+        }
+    }
+}
+
+/// Symbols, longest first so that `<=` is not read as `<`
+const SYMBOLS: [&str; 17] = [
+    "&&", "||", "==", "!=", "<=", ">=", "<", ">", "!", "+", "-", "*", "/", "%", "(", ")", "=",
+];
+
+fn tokenize(source: &str) -> Result<Vec<(usize, Token)>, GuardError> {
+    let mut tokens = Vec::new();
+    let mut position = 0;
+    while position < source.len() {
+        let rest = &source[position..];
+        let c = rest.chars().next().unwrap_or_default();
+        if c.is_whitespace() {
+            position += c.len_utf8();
+        } else if let Some(symbol) = SYMBOLS.iter().find(|symbol| rest.starts_with(**symbol)) {
+            if *symbol == "=" {
+                return Err(GuardError::Parse { position, message: "unexpected [=], comparisons use [==]".to_string() });
+            }
+            tokens.push((position, Token::Symbol(symbol)));
+            position += symbol.len();
+        } else if c == '"' || c == '\'' {
+            let (text, length) = read_text(rest, position)?;
+            tokens.push((position, Token::Literal(Value::Text(text))));
+            position += length;
+        } else if c.is_ascii_digit() {
+            let length = rest.find(|c: char| !c.is_ascii_digit() && c != '.').unwrap_or(rest.len());
+            let number = &rest[..length];
+            let value = match number.contains('.') {
+                true => number.parse().map(Value::Float).ok(),
+                false => number.parse().map(Value::Int).ok(),
+            };
+            let value = value.ok_or_else(|| GuardError::Parse { position, message: format!("invalid number [{}]", number) })?;
+            tokens.push((position, Token::Literal(value)));
+            position += length;
+        } else if c.is_alphabetic() || c == '_' {
+            let length = rest.find(|c: char| !(c.is_alphanumeric() || c == '_' || c == '.')).unwrap_or(rest.len());
+            let word = &rest[..length];
+            let token = match word {
+                "true" => Token::Literal(Value::Bool(true)),
+                "false" => Token::Literal(Value::Bool(false)),
+                "and" => Token::Symbol("&&"),
+                "or" => Token::Symbol("||"),
+                "not" => Token::Symbol("!"),
+                _ => Token::Word(word.to_string()),
+            };
+            tokens.push((position, token));
+            position += length;
+        } else {
+            return Err(GuardError::Parse { position, message: format!("unexpected [{}]", c) });
+        }
+    }
+    return Ok(tokens);
+}
+
+/// A quoted text at the start of `rest`, and its length with the quotes
+fn read_text(rest: &str, position: usize) -> Result<(String, usize), GuardError> {
+    let mut chars = rest.char_indices();
+    let quote = chars.next().map(|(_, c)| c).unwrap_or('"');
+    let mut text = String::new();
+    while let Some((index, c)) = chars.next() {
+        match c {
+            '\\' => match chars.next() {
+                Some((_, escaped)) => text.push(escaped),
+                None => break,
+            },
+            _ if c == quote => return Ok((text, index + c.len_utf8())),
+            _ => text.push(c),
+        }
+    }
+    return Err(GuardError::Parse { position, message: "unterminated text".to_string() });
+}
+
+struct Parser {
+    tokens: Vec<(usize, Token)>,
+    next: usize,
+    /// Offset reported for errors at the end of the guard
+    end: usize,
+}
+
+impl Parser {
+
+    fn peek_symbol(&self) -> Option<&'static str> {
+        return match self.tokens.get(self.next) {
+            Some((_, Token::Symbol(symbol))) => Some(symbol),
+            _ => None,
+        };
+    }
+
+    fn error(&self, message: &str) -> GuardError {
+        let position = self.tokens.get(self.next).map(|(position, _)| *position).unwrap_or(self.end);
+        return GuardError::Parse { position, message: message.to_string() };
+    }
+
+    /// Left associative binary operators of one precedence level
+    fn binary(&mut self, ops: &[(&str, BinaryOp)], operand: fn(&mut Self) -> Result<Expr, GuardError>) -> Result<Expr, GuardError> {
+        let mut left = operand(self)?;
+        while let Some(op) = self.peek_symbol().and_then(|symbol| ops.iter().find(|(s, _)| *s == symbol)).map(|(_, op)| *op) {
+            self.next += 1;
+            left = Expr::Binary(op, Box::new(left), Box::new(operand(self)?));
+        }
+        return Ok(left);
+    }
+
+    fn or(&mut self) -> Result<Expr, GuardError> {
+        return self.binary(&[("||", BinaryOp::Or)], Self::and);
+    }
+
+    fn and(&mut self) -> Result<Expr, GuardError> {
+        return self.binary(&[("&&", BinaryOp::And)], Self::not);
+    }
+
+    fn not(&mut self) -> Result<Expr, GuardError> {
+        if self.peek_symbol() == Some("!") {
+            self.next += 1;
+            return Ok(Expr::Not(Box::new(self.not()?)));
+        }
+        return self.comparison();
+    }
+
+    /// Comparisons do not chain: `a < b < c` is an error
+    fn comparison(&mut self) -> Result<Expr, GuardError> {
+        let ops = [
+            ("==", BinaryOp::Eq), ("!=", BinaryOp::Ne), ("<", BinaryOp::Lt),
+            ("<=", BinaryOp::Le), (">", BinaryOp::Gt), (">=", BinaryOp::Ge),
+        ];
+        let left = self.sum()?;
+        let op = match self.peek_symbol().and_then(|symbol| ops.iter().find(|(s, _)| *s == symbol)) {
+            Some((_, op)) => *op,
+            None => return Ok(left),
+        };
+        self.next += 1;
+        return Ok(Expr::Binary(op, Box::new(left), Box::new(self.sum()?)));
+    }
+
+    fn sum(&mut self) -> Result<Expr, GuardError> {
+        return self.binary(&[("+", BinaryOp::Add), ("-", BinaryOp::Sub)], Self::product);
+    }
+
+    fn product(&mut self) -> Result<Expr, GuardError> {
+        return self.binary(&[("*", BinaryOp::Mul), ("/", BinaryOp::Div), ("%", BinaryOp::Rem)], Self::unary);
+    }
+
+    fn unary(&mut self) -> Result<Expr, GuardError> {
+        if self.peek_symbol() == Some("-") {
+            self.next += 1;
+            return Ok(Expr::Neg(Box::new(self.unary()?)));
+        }
+        return self.primary();
+    }
+
+    fn primary(&mut self) -> Result<Expr, GuardError> {
+        let token = match self.tokens.get(self.next) {
+            Some((_, token)) => token.clone(),
+            None => return Err(self.error("expected a value")),
+        };
+        let expr = match token {
+            Token::Literal(value) => Expr::Literal(value),
+            Token::Word(name) => Expr::Variable(name),
+            Token::Symbol("(") => {
+                self.next += 1;
+                let expr = self.or()?;
+                if self.peek_symbol() != Some(")") {
+                    return Err(self.error("expected [)]"));
+                }
+                expr
+            },
+            Token::Symbol(symbol) => return Err(self.error(&format!("unexpected [{}]", symbol))),
+        };
+        self.next += 1;
+        return Ok(expr);
+    }
+}
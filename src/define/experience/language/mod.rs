@@ -0,0 +1,6 @@
+//! Language of values, guards, effects and durations
+
+pub mod context;
+pub mod duration;
+pub mod effect;
+pub mod guard;
@@ -0,0 +1,430 @@
+//!
+//! ! Lint
+//! Style rules for machine definitions. Each rule has a severity, `off`
+//! disabling it, and an allow-list of fully qualified state ids, id prefixes
+//! ending in `*`, or offending ids. Rules are configured in a `.elea-lint.yaml`
+//! file at the root of a machines directory; rules it leaves out keep their
+//! defaults.
+//!
+//! ```yaml
+//! rules:
+//!   id_casing:
+//!     style: snake_case
+//!     allow: [default/OFF]
+//!   fan_out:
+//!     severity: error
+//!     max: 8
+//!   missing_description:
+//!     severity: off
+//! ```
+//!
+//! ----------------------------------------------------------------------------
+//! type: computer for Elea
+//! ----------------------------------------------------------------------------
+
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::define::experience::language::duration;
+use crate::define::experience::language::effect::{self, CheckError, Effect};
+use crate::define::experience::language::guard::Guard;
+use crate::define::experience::representation::filesystem::{
+    self, Format, MachinesError
+};
+use crate::define::experience::representation::qualified_id::QualifiedStateId;
+use crate::define::experience::representation::rust_minimal::{ArrowId, StateId};
+use crate::define::experience::representation::rust_pragmatic::{Space, SpaceError};
+
+/// Name of the lint configuration file in a machines directory
+pub const CONFIG_FILE_NAME: &str = ".elea-lint.yaml";
+
+//------------------------------------------------------------------------------
+// LINT / Configuration
+
+#[derive(Clone, Copy, Debug, Deserialize, Eq, Ord, PartialEq, PartialOrd, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Severity {
+    Off,
+    Info,
+    Warning,
+    Error,
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub enum CasingStyle {
+    #[serde(rename = "snake_case")]
+    SnakeCase,
+    #[serde(rename = "kebab-case")]
+    KebabCase,
+    #[serde(rename = "camelCase")]
+    CamelCase,
+}
+
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct LintConfig {
+    pub rules: Rules,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(default)]
+pub struct Rules {
+    /// Machine ids, state path segments and arrow ids follow one style
+    pub id_casing: IdCasingRule,
+    /// States have at most `max` arrows
+    pub fan_out: FanOutRule,
+    /// States have a description
+    pub missing_description: Rule,
+    /// Arrows named `self` point at their own state
+    pub self_arrow: Rule,
+    /// Arrows point at a state that exists
+    pub unresolved_arrow: Rule,
+    /// Arrow guards parse, see `define::experience::language::guard`
+    pub invalid_guard: Rule,
+    /// Arrow effects assign declared variables values of their type,
+    /// variable defaults are of their type, and machines declaring a
+    /// variable of the same name declare it alike, see
+    /// `define::experience::language::effect`
+    pub invalid_effect: Rule,
+    /// Durations of timed arrows parse, see `define::experience::language::duration`
+    pub invalid_timer: Rule,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(default)]
+pub struct Rule {
+    pub severity: Severity,
+    pub allow: Vec<String>,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(default)]
+pub struct IdCasingRule {
+    pub severity: Severity,
+    pub allow: Vec<String>,
+    pub style: CasingStyle,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(default)]
+pub struct FanOutRule {
+    pub severity: Severity,
+    pub allow: Vec<String>,
+    pub max: usize,
+}
+
+impl Default for Rules {
+    fn default() -> Self {
+        return Self {
+            id_casing: IdCasingRule::default(),
+            fan_out: FanOutRule::default(),
+            missing_description: Rule { severity: Severity::Info, allow: Vec::new() },
+            self_arrow: Rule { severity: Severity::Error, allow: Vec::new() },
+            unresolved_arrow: Rule { severity: Severity::Error, allow: Vec::new() },
+            invalid_guard: Rule { severity: Severity::Error, allow: Vec::new() },
+            invalid_effect: Rule { severity: Severity::Error, allow: Vec::new() },
+            invalid_timer: Rule { severity: Severity::Error, allow: Vec::new() },
+        };
+    }
+}
+
+impl Default for Rule {
+    fn default() -> Self {
+        return Self { severity: Severity::Warning, allow: Vec::new() };
+    }
+}
+
+impl Default for IdCasingRule {
+    fn default() -> Self {
+        return Self { severity: Severity::Warning, allow: Vec::new(), style: CasingStyle::SnakeCase };
+    }
+}
+
+impl Default for FanOutRule {
+    fn default() -> Self {
+        return Self { severity: Severity::Warning, allow: Vec::new(), max: 10 };
+    }
+}
+
+//------------------------------------------------------------------------------
+// LINT / Findings
+
+/// Finding
+///
+/// `state` is fully qualified. `file` is set when linting a directory.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct Finding {
+    pub rule: String,
+    pub severity: Severity,
+    pub state: StateId,
+    pub arrow: Option<ArrowId>,
+    pub message: String,
+    pub file: Option<PathBuf>,
+}
+
+impl fmt::Display for Finding {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if let Some(file) = &self.file {
+            write!(f, "{}: ", file.display())?;
+        }
+        let severity = match self.severity {
+            Severity::Off => "off",
+            Severity::Info => "info",
+            Severity::Warning => "warning",
+            Severity::Error => "error",
+        };
+        return write!(f, "{} [{}] {}: {}", severity, self.rule, self.state.0, self.message);
+    }
+}
+
+#[derive(Debug)]
+pub enum LintError {
+    Config { file_path: String, error: String },
+    Machines(MachinesError),
+}
+
+//------------------------------------------------------------------------------
+// LINT / Engine
+
+/// Apply every enabled rule to a Space. Findings are ordered by state id.
+pub fn lint(space: &Space, config: &LintConfig) -> Vec<Finding> {
+    let rules = &config.rules;
+    let mut findings = Vec::new();
+    // state paths whose last segment was checked, with their machine
+    let mut introduced: HashSet<(&str, &[String])> = HashSet::new();
+
+    let states = space.states();
+    for (state_id, state) in &states {
+        let mut finding = |rule: &str, severity: Severity, arrow: Option<&ArrowId>, message: String| {
+            findings.push(Finding {
+                rule: rule.to_string(),
+                severity,
+                state: state_id.to_state_id(),
+                arrow: arrow.cloned(),
+                message,
+                file: None,
+            });
+        };
+
+        // id casing, of each path segment on the first state it is part of
+        let mut segments = Vec::new();
+        for index in 0..state_id.path().len() {
+            if introduced.insert((state_id.machine().0.as_str(), &state_id.path()[..=index])) {
+                segments.push(state_id.path()[index].as_str());
+            }
+        }
+        if enabled(rules.id_casing.severity, &rules.id_casing.allow, state_id, None) {
+            for id in segments {
+                if !is_cased(id, rules.id_casing.style) && !rules.id_casing.allow.iter().any(|a| a == id) {
+                    finding("id_casing", rules.id_casing.severity, None, format!("id [{}] is not {}", id, style_name(rules.id_casing.style)));
+                }
+            }
+            for arrow in &state.arrows {
+                if !is_cased(&arrow.id.0, rules.id_casing.style) && !rules.id_casing.allow.contains(&arrow.id.0) {
+                    finding("id_casing", rules.id_casing.severity, Some(&arrow.id), format!("arrow id [{}] is not {}", arrow.id.0, style_name(rules.id_casing.style)));
+                }
+            }
+        }
+
+        // fan out
+        if enabled(rules.fan_out.severity, &rules.fan_out.allow, state_id, None) && state.arrows.len() > rules.fan_out.max {
+            finding("fan_out", rules.fan_out.severity, None, format!("{} arrows, more than {}", state.arrows.len(), rules.fan_out.max));
+        }
+
+        // missing description
+        if enabled(rules.missing_description.severity, &rules.missing_description.allow, state_id, None)
+            && state.description.as_deref().unwrap_or("").trim().is_empty()
+        {
+            finding("missing_description", rules.missing_description.severity, None, "state has no description".to_string());
+        }
+
+        for arrow in &state.arrows {
+            // self arrows
+            if arrow.id.0 == "self"
+                && arrow.next.0 != state_id.to_string()
+                && enabled(rules.self_arrow.severity, &rules.self_arrow.allow, state_id, Some(&arrow.id))
+            {
+                finding("self_arrow", rules.self_arrow.severity, Some(&arrow.id), format!("arrow [self] points at [{}]", arrow.next.0));
+            }
+            // unresolved arrows
+            if space.resolve(&arrow.next.0).is_none()
+                && enabled(rules.unresolved_arrow.severity, &rules.unresolved_arrow.allow, state_id, Some(&arrow.id))
+            {
+                finding("unresolved_arrow", rules.unresolved_arrow.severity, Some(&arrow.id), format!("arrow [{}] points at unknown state [{}]", arrow.id.0, arrow.next.0));
+            }
+            // invalid guards
+            if let Some(Err(err)) = arrow.guard.as_deref().map(Guard::parse) {
+                if enabled(rules.invalid_guard.severity, &rules.invalid_guard.allow, state_id, Some(&arrow.id)) {
+                    finding("invalid_guard", rules.invalid_guard.severity, Some(&arrow.id), format!("guard of arrow [{}] is invalid: {}", arrow.id.0, err));
+                }
+            }
+            // invalid timers
+            if let Some(Err(err)) = arrow.after.as_deref().map(duration::parse_duration) {
+                if enabled(rules.invalid_timer.severity, &rules.invalid_timer.allow, state_id, Some(&arrow.id)) {
+                    finding("invalid_timer", rules.invalid_timer.severity, Some(&arrow.id), format!("duration of arrow [{}] is invalid: {}", arrow.id.0, err));
+                }
+            }
+            // invalid effects
+            if !arrow.effects.is_empty() && enabled(rules.invalid_effect.severity, &rules.invalid_effect.allow, state_id, Some(&arrow.id)) {
+                let variables = match space.machine_by_id.get(state_id.machine()) {
+                    Some(machine) => &machine.variables,
+                    None => {
+                        finding("invalid_effect", rules.invalid_effect.severity, Some(&arrow.id), format!("effects of arrow [{}] cannot be checked, machine [{}] is not indexed", arrow.id.0, state_id.machine().0));
+                        continue;
+                    },
+                };
+                for source in &arrow.effects {
+                    if let Err(err) = Effect::parse(source).and_then(|effect| effect.check(variables)) {
+                        finding("invalid_effect", rules.invalid_effect.severity, Some(&arrow.id), format!("effect [{}] of arrow [{}] is invalid: {}", source, arrow.id.0, err));
+                    }
+                }
+            }
+        }
+    }
+
+    // id casing of machine ids, reported on the machine
+    for machine in &space.machines {
+        let machine_id = match QualifiedStateId::machine_root(&machine.id.0) {
+            Ok(machine_id) => machine_id,
+            Err(_) => continue,
+        };
+        let id = &machine.id.0;
+        if enabled(rules.id_casing.severity, &rules.id_casing.allow, &machine_id, None)
+            && !is_cased(id, rules.id_casing.style)
+            && !rules.id_casing.allow.contains(id)
+        {
+            findings.push(Finding {
+                rule: "id_casing".to_string(),
+                severity: rules.id_casing.severity,
+                state: machine_id.to_state_id(),
+                arrow: None,
+                message: format!("id [{}] is not {}", id, style_name(rules.id_casing.style)),
+                file: None,
+            });
+        }
+    }
+
+    // invalid variable defaults and conflicting declarations, reported on
+    // the machine
+    let conflicts = space.conflicting_variables();
+    for machine in &space.machines {
+        let machine_id = match QualifiedStateId::machine_root(&machine.id.0) {
+            Ok(machine_id) => machine_id,
+            Err(_) => continue,
+        };
+        if !enabled(rules.invalid_effect.severity, &rules.invalid_effect.allow, &machine_id, None) {
+            continue;
+        }
+        for err in effect::check(machine) {
+            if let CheckError::Default { .. } = err {
+                findings.push(Finding {
+                    rule: "invalid_effect".to_string(),
+                    severity: rules.invalid_effect.severity,
+                    state: machine_id.to_state_id(),
+                    arrow: None,
+                    message: err.to_string(),
+                    file: None,
+                });
+            }
+        }
+        for conflict in &conflicts {
+            if let SpaceError::ConflictingVariable { second, .. } = conflict {
+                if *second == machine.id {
+                    findings.push(Finding {
+                        rule: "invalid_effect".to_string(),
+                        severity: rules.invalid_effect.severity,
+                        state: machine_id.to_state_id(),
+                        arrow: None,
+                        message: conflict.to_string(),
+                        file: None,
+                    });
+                }
+            }
+        }
+    }
+
+    findings.sort_by(|f1, f2| f1.state.cmp(&f2.state));
+    return findings;
+}
+
+/// Lint a NestedMachines directory with the configuration at its root,
+/// pointing each finding at the file that defines its state
+pub fn lint_dir(machines_path: &Path) -> Result<Vec<Finding>, LintError> {
+    let config = load_config(machines_path)?;
+    let sources = filesystem::to_sources(machines_path, Format::NestedMachines).map_err(LintError::Machines)?;
+    let space = Space::new(filesystem::machines_from_sources(&sources));
+
+    let mut file_by_state: HashMap<String, &PathBuf> = HashMap::new();
+    for file in &sources.files {
+        if !file.variables.is_empty() {
+            file_by_state.insert(file.base_id.machine().0.clone(), &file.path);
+        }
+        for state in &file.states {
+            if let Ok(state_id) = QualifiedStateId::from_local(file.base_id.machine(), &state.id) {
+                file_by_state.insert(state_id.to_string(), &file.path);
+            }
+        }
+    }
+
+    let mut findings = lint(&space, &config);
+    for finding in &mut findings {
+        finding.file = file_by_state.get(&finding.state.0).map(|path| path.to_path_buf());
+    }
+    return Ok(findings);
+}
+
+/// The configuration file of a machines directory, or the defaults
+pub fn load_config(machines_path: &Path) -> Result<LintConfig, LintError> {
+    let config_path = machines_path.join(CONFIG_FILE_NAME);
+    if !config_path.exists() {
+        return Ok(LintConfig::default());
+    }
+    let config_error = |error: String| LintError::Config {
+        file_path: config_path.display().to_string(),
+        error,
+    };
+    let content = fs::read_to_string(&config_path).map_err(|err| config_error(err.to_string()))?;
+    return serde_yaml::from_str(&content).map_err(|err| config_error(err.to_string()));
+}
+
+//------------------------------------------------------------------------------
+// LINT / Helpers
+
+/// Whether a rule applies to a state, or one of its arrows
+fn enabled(severity: Severity, allow: &[String], state_id: &QualifiedStateId, arrow: Option<&ArrowId>) -> bool {
+    if severity == Severity::Off {
+        return false;
+    }
+    let state = state_id.to_string();
+    return !allow.iter().any(|pattern| {
+        let by_state = match pattern.strip_suffix('*') {
+            Some(prefix) => state.starts_with(prefix),
+            None => *pattern == state,
+        };
+        by_state || arrow.is_some_and(|a| a.0 == *pattern)
+    });
+}
+
+fn is_cased(id: &str, style: CasingStyle) -> bool {
+    let is_word = |word: &str, upper: bool| {
+        !word.is_empty() && word.chars().all(|c| c.is_ascii_digit() || c.is_ascii_lowercase() || (upper && c.is_ascii_uppercase()))
+    };
+    return match style {
+        CasingStyle::SnakeCase => id.split('_').all(|w| is_word(w, false)),
+        CasingStyle::KebabCase => id.split('-').all(|w| is_word(w, false)),
+        CasingStyle::CamelCase => {
+            id.chars().next().is_some_and(|c| !c.is_ascii_uppercase()) && is_word(id, true)
+        },
+    };
+}
+
+fn style_name(style: CasingStyle) -> &'static str {
+    return match style {
+        CasingStyle::SnakeCase => "snake_case",
+        CasingStyle::KebabCase => "kebab-case",
+        CasingStyle::CamelCase => "camelCase",
+    };
+}
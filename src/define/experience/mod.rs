@@ -1 +1,3 @@
+pub mod language;
+pub mod lint;
 pub mod representation;
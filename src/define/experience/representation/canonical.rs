@@ -0,0 +1,117 @@
+//!
+//! ! Canonical Representation
+//! A canonical serialization of Spaces, Machines and States: JSON with
+//! machines, states, arrows and variables sorted by id, fields in declaration
+//! order and no indexes. Two definitions with the same canonical form have the same
+//! content hash, a SHA-256 of that form, wherever they were loaded from.
+//!
+//! ----------------------------------------------------------------------------
+//! type: computer for Elea
+//! ----------------------------------------------------------------------------
+
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+
+use crate::define::experience::representation::rust_pragmatic::{
+    Arrow, Machine, Space, State, Variable
+};
+
+//------------------------------------------------------------------------------
+// CANONICAL
+
+/// Canonical
+///
+/// Implemented by the parts of a Space that can be fingerprinted
+pub trait Canonical {
+
+    /// Canonical JSON form
+    fn canonical(&self) -> String;
+
+    /// Lowercase hex SHA-256 of the canonical form
+    fn content_hash(&self) -> String {
+        return hex(&Sha256::digest(self.canonical().as_bytes()));
+    }
+}
+
+#[derive(Serialize)]
+struct CanonicalSpace<'a> {
+    machines: Vec<CanonicalMachine<'a>>,
+}
+
+#[derive(Serialize)]
+struct CanonicalMachine<'a> {
+    id: &'a str,
+    states: Vec<CanonicalState<'a>>,
+    /// Left out when empty, so machines without variables keep their hash
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    variables: &'a BTreeMap<String, Variable>,
+}
+
+#[derive(Serialize)]
+struct CanonicalState<'a> {
+    id: &'a str,
+    description: &'a Option<String>,
+    arrows: Vec<&'a Arrow>,
+}
+
+impl Canonical for Space {
+    fn canonical(&self) -> String {
+        return to_json(&canonical_space(self));
+    }
+}
+
+impl Canonical for Machine {
+    fn canonical(&self) -> String {
+        return to_json(&canonical_machine(self));
+    }
+}
+
+impl Canonical for State {
+    fn canonical(&self) -> String {
+        return to_json(&canonical_state(self));
+    }
+}
+
+impl Space {
+
+    /// Content hash of every machine, in machine id order
+    pub fn machine_hashes(&self) -> Vec<(String, String)> {
+        let mut hashes: Vec<(String, String)> = self.machines.iter()
+            .map(|machine| (machine.id.0.clone(), machine.content_hash()))
+            .collect();
+        hashes.sort();
+        return hashes;
+    }
+}
+
+/// Lowercase hex encoding
+pub fn hex(bytes: &[u8]) -> String {
+    return bytes.iter().map(|b| format!("{:02x}", b)).collect();
+}
+
+//------------------------------------------------------------------------------
+// CANONICAL / Helpers
+
+fn canonical_space(space: &Space) -> CanonicalSpace<'_> {
+    let mut machines: Vec<CanonicalMachine> = space.machines.iter().map(canonical_machine).collect();
+    machines.sort_by(|m1, m2| m1.id.cmp(m2.id));
+    return CanonicalSpace { machines };
+}
+
+fn canonical_machine(machine: &Machine) -> CanonicalMachine<'_> {
+    let mut states: Vec<CanonicalState> = machine.states.iter().map(canonical_state).collect();
+    states.sort_by(|s1, s2| s1.id.cmp(s2.id));
+    return CanonicalMachine { id: &machine.id.0, states, variables: &machine.variables };
+}
+
+fn canonical_state(state: &State) -> CanonicalState<'_> {
+    let mut arrows: Vec<&Arrow> = state.arrows.iter().collect();
+    arrows.sort_by(|a1, a2| a1.id.cmp(&a2.id).then_with(|| a1.next.cmp(&a2.next)));
+    return CanonicalState { id: &state.id.0, description: &state.description, arrows };
+}
+
+fn to_json<T: Serialize>(value: &T) -> String {
+    // serializing plain structs, strings and options cannot fail
+    return serde_json::to_string(value).unwrap_or_default();
+}
@@ -0,0 +1,162 @@
+//!
+//! ! Code Generation
+//! Source code generated from a Space, so that programs driving a machine
+//! use types that cannot drift from its definition. Each language has its own
+//! module; the identifier helpers they share are here.
+//!
+//! ----------------------------------------------------------------------------
+//! type: computer for Elea
+//! ----------------------------------------------------------------------------
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs;
+use std::path::Path;
+
+use crate::define::experience::representation::filesystem::{
+    self, Format, MachinesError
+};
+use crate::define::experience::representation::rust_pragmatic::{
+    Space, SpaceError
+};
+
+pub mod rust;
+pub mod typescript;
+
+//------------------------------------------------------------------------------
+// CODEGEN
+
+#[derive(Debug)]
+pub enum CodegenError {
+    Machines(MachinesError),
+    /// Code is only generated for Spaces without structural problems
+    Space(Vec<SpaceError>),
+    /// Two ids of one scope map to the same identifier
+    NameCollision { scope: String, name: String, first: String, second: String },
+    File { file_path: String, error: String },
+}
+
+impl fmt::Display for CodegenError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CodegenError::Machines(err) => write!(f, "cannot load machines: {:?}", err),
+            CodegenError::Space(errors) => {
+                write!(f, "invalid space:")?;
+                for error in errors {
+                    write!(f, " {};", error)?;
+                }
+                return Ok(());
+            },
+            CodegenError::NameCollision { scope, name, first, second } => {
+                write!(f, "[{}] and [{}] are both named {} in {}", first, second, name, scope)
+            },
+            CodegenError::File { file_path, error } => write!(f, "{}: {}", file_path, error),
+        }
+    }
+}
+
+/// Load and validate the Space of a NestedMachines directory
+pub fn load_space(machines_path: &Path) -> Result<Space, CodegenError> {
+    let machines = filesystem::to_machines(machines_path, Format::NestedMachines).map_err(CodegenError::Machines)?;
+    let space = Space::new(machines);
+    let errors = space.validate();
+    if !errors.is_empty() {
+        return Err(CodegenError::Space(errors));
+    }
+    return Ok(space);
+}
+
+/// Write generated code, leaving the file untouched when it is unchanged so
+/// that builds depending on it are not redone
+pub(crate) fn write_if_changed(file_path: &Path, code: &str) -> Result<(), CodegenError> {
+    if fs::read_to_string(file_path).is_ok_and(|existing| existing == code) {
+        return Ok(());
+    }
+    return fs::write(file_path, code).map_err(|err| CodegenError::File {
+        file_path: file_path.display().to_string(),
+        error: err.to_string(),
+    });
+}
+
+//------------------------------------------------------------------------------
+// CODEGEN / Identifiers
+
+/// Words of an id, split at separators such as `_`, `-` and `/`, and where a
+/// lowercase letter is followed by an uppercase one
+pub(crate) fn words(id: &str) -> Vec<String> {
+    let mut words = Vec::new();
+    let mut word = String::new();
+    let mut previous_lowercase = false;
+    for c in id.chars() {
+        if !c.is_ascii_alphanumeric() {
+            if !word.is_empty() {
+                words.push(std::mem::take(&mut word));
+            }
+            previous_lowercase = false;
+            continue;
+        }
+        if c.is_ascii_uppercase() && previous_lowercase {
+            words.push(std::mem::take(&mut word));
+        }
+        previous_lowercase = c.is_ascii_lowercase() || c.is_ascii_digit();
+        word.push(c);
+    }
+    if !word.is_empty() {
+        words.push(word);
+    }
+    return words;
+}
+
+/// `fade/levels` as `FadeLevels`. Identifiers never start with a digit.
+pub(crate) fn pascal_case(id: &str) -> String {
+    let name: String = words(id).iter()
+        .map(|word| {
+            let mut chars = word.chars();
+            match chars.next() {
+                Some(first) => first.to_ascii_uppercase().to_string() + &chars.as_str().to_ascii_lowercase(),
+                None => String::new(),
+            }
+        })
+        .collect();
+    return leading_digit_safe(name);
+}
+
+/// `fade/levels` as `fade_levels`. Identifiers never start with a digit.
+pub(crate) fn snake_case(id: &str) -> String {
+    let words: Vec<String> = words(id).iter().map(|word| word.to_ascii_lowercase()).collect();
+    return leading_digit_safe(words.join("_"));
+}
+
+fn leading_digit_safe(name: String) -> String {
+    if name.is_empty() || name.starts_with(|c: char| c.is_ascii_digit()) {
+        return format!("_{}", name);
+    }
+    return name;
+}
+
+/// Identifiers of one scope, such as the variants of an enum, failing when
+/// two ids map to the same identifier
+pub(crate) struct Names {
+    scope: String,
+    ids_by_name: BTreeMap<String, String>,
+}
+
+impl Names {
+
+    pub(crate) fn new(scope: &str) -> Self {
+        return Self { scope: scope.to_string(), ids_by_name: BTreeMap::new() };
+    }
+
+    pub(crate) fn add(&mut self, name: String, id: &str) -> Result<String, CodegenError> {
+        if let Some(first) = self.ids_by_name.get(&name) {
+            return Err(CodegenError::NameCollision {
+                scope: self.scope.clone(),
+                name,
+                first: first.clone(),
+                second: id.to_string(),
+            });
+        }
+        self.ids_by_name.insert(name.clone(), id.to_string());
+        return Ok(name);
+    }
+}
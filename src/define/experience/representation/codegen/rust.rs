@@ -0,0 +1,262 @@
+//!
+//! ! Rust Code Generation
+//! Rust types for the machines of a Space. Each machine gets a module with a
+//! `State` enum of its states, and for each state a marker type and an enum
+//! of its arrows. A state's `transition` only takes its own arrows, so an
+//! arrow that is not valid in the current state does not compile:
+//!
+//! ```ignore
+//! let next: SpaceState = default::On.transition(default::OnArrow::Off);
+//! ```
+//!
+//! `SpaceState` holds a state of any machine, as arrows may point into other
+//! machines. Every type gives back its Elea id through `id()`. From a
+//! `build.rs`:
+//!
+//! ```ignore
+//! let space = codegen::load_space(Path::new("machines")).unwrap();
+//! let out_dir = PathBuf::from(env::var("OUT_DIR").unwrap());
+//! codegen::rust::write_file(&space, &out_dir.join("machines.rs")).unwrap();
+//! println!("cargo:rerun-if-changed=machines");
+//! ```
+//!
+//! and `include!(concat!(env!("OUT_DIR"), "/machines.rs"));` in the crate.
+//!
+//! ----------------------------------------------------------------------------
+//! type: computer for Elea
+//! ----------------------------------------------------------------------------
+
+use std::fmt::Write;
+use std::path::Path;
+
+use crate::define::experience::representation::codegen::{
+    pascal_case, snake_case, write_if_changed, CodegenError, Names
+};
+use crate::define::experience::representation::qualified_id::QualifiedStateId;
+use crate::define::experience::representation::rust_pragmatic::{
+    Machine, Space, State
+};
+
+const HEADER: &str = "// Generated by elea from a Space, do not edit.\n";
+
+const DERIVES: &str = "#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]";
+
+const KEYWORDS: [&str; 49] = [
+    "abstract", "as", "async", "await", "become", "box", "break", "const",
+    "continue", "crate", "do", "dyn", "else", "enum", "extern", "false",
+    "final", "fn", "for", "gen", "if", "impl", "in", "let", "loop", "macro",
+    "match", "mod", "move", "mut", "override", "priv", "pub", "ref", "return",
+    "self", "static", "struct", "super", "trait", "true", "try", "type",
+    "typeof", "unsafe", "unsized", "use", "virtual", "where",
+];
+
+//------------------------------------------------------------------------------
+// RUST
+
+/// Rust source for the machines of a Space. The Space should be valid, see
+/// `codegen::load_space`, and is rejected with its errors otherwise.
+pub fn generate(space: &Space) -> Result<String, CodegenError> {
+    let errors = space.validate();
+    if !errors.is_empty() {
+        return Err(CodegenError::Space(errors));
+    }
+    let mut modules = Names::new("the space");
+    let mut variants = Names::new("SpaceState");
+    let mut machines: Vec<(&Machine, String, String)> = Vec::new();
+    for machine in &space.machines {
+        let module = modules.add(module_name(&machine.id.0), &machine.id.0)?;
+        let variant = variants.add(type_name(&machine.id.0), &machine.id.0)?;
+        machines.push((machine, module, variant));
+    }
+
+    let mut out = String::from(HEADER);
+    emit_space_state(&mut out, &machines);
+    for (machine, module, variant) in &machines {
+        emit_machine(&mut out, space, machine, module, variant)?;
+    }
+    return Ok(out);
+}
+
+/// Generate into a file, for use from a `build.rs`
+pub fn write_file(space: &Space, file_path: &Path) -> Result<(), CodegenError> {
+    return write_if_changed(file_path, &generate(space)?);
+}
+
+//------------------------------------------------------------------------------
+// RUST / Emitter
+
+fn emit_space_state(out: &mut String, machines: &[(&Machine, String, String)]) {
+    let _ = writeln!(out);
+    let _ = writeln!(out, "/// A state of any machine");
+    let _ = writeln!(out, "{}", DERIVES);
+    let _ = writeln!(out, "pub enum SpaceState {{");
+    for (_, module, variant) in machines {
+        let _ = writeln!(out, "    {}({}::State),", variant, module);
+    }
+    let _ = writeln!(out, "}}");
+    let _ = writeln!(out);
+    let _ = writeln!(out, "impl SpaceState {{");
+    let _ = writeln!(out, "    /// Fully qualified state id");
+    let _ = writeln!(out, "    pub const fn id(&self) -> &'static str {{");
+    let _ = writeln!(out, "        match self {{");
+    for (_, _, variant) in machines {
+        let _ = writeln!(out, "            SpaceState::{}(state) => state.id(),", variant);
+    }
+    let _ = writeln!(out, "        }}");
+    let _ = writeln!(out, "    }}");
+    let _ = writeln!(out, "}}");
+}
+
+fn emit_machine(out: &mut String, space: &Space, machine: &Machine, module: &str, variant: &str) -> Result<(), CodegenError> {
+    let scope = format!("machine [{}]", machine.id.0);
+    let mut types = Names::new(&scope);
+    types.add("State".to_string(), "State")?;
+    let mut state_variants = Names::new(&scope);
+
+    let mut states: Vec<(&State, String, String, String)> = Vec::new();
+    for state in &machine.states {
+        let qualified_id = format!("{}/{}", machine.id.0, state.id.0);
+        let name = type_name(&state.id.0);
+        let state_type = types.add(name.clone(), &qualified_id)?;
+        let arrow_type = types.add(format!("{}Arrow", name), &qualified_id)?;
+        state_variants.add(name.clone(), &qualified_id)?;
+        states.push((state, qualified_id, state_type, arrow_type));
+    }
+
+    let _ = writeln!(out);
+    let _ = writeln!(out, "/// Machine `{}`", machine.id.0);
+    // identifiers follow the ids, such as `_20Percent` for `20_percent`
+    let _ = writeln!(out, "#[allow(non_camel_case_types, clippy::all)]");
+    let _ = writeln!(out, "pub mod {} {{", module);
+    let _ = writeln!(out);
+    let _ = writeln!(out, "    {}", DERIVES);
+    let _ = writeln!(out, "    pub enum State {{");
+    for (state, _, state_type, _) in &states {
+        emit_doc(out, "        ", state.description.as_deref());
+        let _ = writeln!(out, "        {},", state_type);
+    }
+    let _ = writeln!(out, "    }}");
+    let _ = writeln!(out);
+    let _ = writeln!(out, "    impl State {{");
+    let _ = writeln!(out, "        /// Fully qualified state id");
+    let _ = writeln!(out, "        pub const fn id(&self) -> &'static str {{");
+    let _ = writeln!(out, "            match *self {{");
+    for (_, qualified_id, state_type, _) in &states {
+        let _ = writeln!(out, "                State::{} => {:?},", state_type, qualified_id);
+    }
+    let _ = writeln!(out, "            }}");
+    let _ = writeln!(out, "        }}");
+    let _ = writeln!(out, "    }}");
+    let _ = writeln!(out);
+    let _ = writeln!(out, "    impl ::core::convert::From<State> for super::SpaceState {{");
+    let _ = writeln!(out, "        fn from(state: State) -> Self {{");
+    let _ = writeln!(out, "            super::SpaceState::{}(state)", variant);
+    let _ = writeln!(out, "        }}");
+    let _ = writeln!(out, "    }}");
+
+    for (state, qualified_id, state_type, arrow_type) in &states {
+        emit_state(out, space, state, qualified_id, state_type, arrow_type)?;
+    }
+    let _ = writeln!(out, "}}");
+    return Ok(());
+}
+
+fn emit_state(out: &mut String, space: &Space, state: &State, qualified_id: &str, state_type: &str, arrow_type: &str) -> Result<(), CodegenError> {
+    let mut arrow_variants = Names::new(&format!("state [{}]", qualified_id));
+    let mut arrows = Vec::new();
+    for arrow in &state.arrows {
+        let variant = arrow_variants.add(type_name(&arrow.id.0), &arrow.id.0)?;
+        arrows.push((arrow, variant, target(space, &arrow.next.0)?));
+    }
+
+    let _ = writeln!(out);
+    let _ = writeln!(out, "    /// State `{}`", qualified_id);
+    let _ = writeln!(out, "    {}", DERIVES);
+    let _ = writeln!(out, "    pub struct {};", state_type);
+    let _ = writeln!(out);
+    let _ = writeln!(out, "    /// Arrows of state `{}`", qualified_id);
+    let _ = writeln!(out, "    {}", DERIVES);
+    let _ = writeln!(out, "    pub enum {} {{", arrow_type);
+    for (arrow, variant, _) in &arrows {
+        emit_doc(out, "        ", arrow.description.as_deref());
+        let _ = writeln!(out, "        {},", variant);
+    }
+    let _ = writeln!(out, "    }}");
+    let _ = writeln!(out);
+    let _ = writeln!(out, "    impl {} {{", arrow_type);
+    let _ = writeln!(out, "        /// Arrow id");
+    let _ = writeln!(out, "        pub const fn id(&self) -> &'static str {{");
+    let _ = writeln!(out, "            match *self {{");
+    for (arrow, variant, _) in &arrows {
+        let _ = writeln!(out, "                {}::{} => {:?},", arrow_type, variant, arrow.id.0);
+    }
+    let _ = writeln!(out, "            }}");
+    let _ = writeln!(out, "        }}");
+    let _ = writeln!(out, "    }}");
+    let _ = writeln!(out);
+    let _ = writeln!(out, "    impl {} {{", state_type);
+    let _ = writeln!(out, "        pub const STATE: State = State::{};", state_type);
+    let _ = writeln!(out);
+    let _ = writeln!(out, "        /// The state an arrow leads to");
+    let _ = writeln!(out, "        pub const fn transition(self, arrow: {}) -> super::SpaceState {{", arrow_type);
+    let _ = writeln!(out, "            match arrow {{");
+    for (_, variant, (next_module, next_machine, next_state)) in &arrows {
+        let _ = writeln!(
+            out,
+            "                {}::{} => super::SpaceState::{}(super::{}::State::{}),",
+            arrow_type, variant, next_machine, next_module, next_state,
+        );
+    }
+    let _ = writeln!(out, "            }}");
+    let _ = writeln!(out, "        }}");
+    let _ = writeln!(out, "    }}");
+    let _ = writeln!(out);
+    // in full, as a state called `from` shadows the prelude `From`
+    let _ = writeln!(out, "    impl ::core::convert::From<{}> for State {{", state_type);
+    let _ = writeln!(out, "        fn from(_: {}) -> Self {{", state_type);
+    let _ = writeln!(out, "            State::{}", state_type);
+    let _ = writeln!(out, "        }}");
+    let _ = writeln!(out, "    }}");
+    return Ok(());
+}
+
+fn emit_doc(out: &mut String, indent: &str, description: Option<&str>) {
+    for line in description.unwrap_or("").lines() {
+        let _ = writeln!(out, "{}/// {}", indent, line.trim_end());
+    }
+}
+
+//------------------------------------------------------------------------------
+// RUST / Helpers
+
+/// Module, `SpaceState` variant and `State` variant of an arrow target
+fn target(space: &Space, next: &str) -> Result<(String, String, String), CodegenError> {
+    let unresolved = || CodegenError::Space(space.validate());
+    let next_id = QualifiedStateId::parse(next).map_err(|_| unresolved())?;
+    if space.resolve_id(&next_id).is_none() {
+        return Err(unresolved());
+    }
+    return Ok((
+        module_name(&next_id.machine().0),
+        type_name(&next_id.machine().0),
+        type_name(&next_id.local_id().0),
+    ));
+}
+
+fn module_name(id: &str) -> String {
+    let name = snake_case(id);
+    return match name.as_str() {
+        // keywords that cannot be raw identifiers
+        "crate" | "self" | "super" => format!("{}_", name),
+        _ if KEYWORDS.contains(&name.as_str()) => format!("r#{}", name),
+        _ => name,
+    };
+}
+
+fn type_name(id: &str) -> String {
+    let name = pascal_case(id);
+    if name == "Self" {
+        return "Self_".to_string();
+    }
+    return name;
+}
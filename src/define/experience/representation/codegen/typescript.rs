@@ -0,0 +1,141 @@
+//!
+//! ! TypeScript Code Generation
+//! A TypeScript module for the machines of a Space: union types of the
+//! machine, state and arrow ids, and a transition table typed so that each
+//! state only accepts its own arrows:
+//!
+//! ```ts
+//! const next: StateId = transition("default/on", "off");
+//! ```
+//!
+//! State ids are fully qualified, as in `Space::resolve`. Ids are written as
+//! string literals, so they need no renaming.
+//!
+//! ----------------------------------------------------------------------------
+//! type: computer for Elea
+//! ----------------------------------------------------------------------------
+
+use std::fmt::Write;
+use std::path::Path;
+
+use crate::define::experience::representation::codegen::{
+    write_if_changed, CodegenError
+};
+use crate::define::experience::representation::qualified_id::QualifiedStateId;
+use crate::define::experience::representation::rust_pragmatic::Space;
+
+const HEADER: &str = "// Generated by elea from a Space, do not edit.\n";
+
+const INDENT: &str = "  ";
+
+//------------------------------------------------------------------------------
+// TYPESCRIPT
+
+/// TypeScript source for the machines of a Space. The Space should be
+/// valid, see `codegen::load_space`.
+pub fn generate(space: &Space) -> Result<String, CodegenError> {
+    let errors = space.validate();
+    if !errors.is_empty() {
+        return Err(CodegenError::Space(errors));
+    }
+    let states: Vec<(QualifiedStateId, _)> = space.states();
+
+    let mut out = String::from(HEADER);
+
+    let _ = writeln!(out);
+    let _ = writeln!(out, "/** Machine ids */");
+    let machine_ids: Vec<String> = space.machines.iter().map(|m| literal(&m.id.0)).collect();
+    emit_union(&mut out, "MachineId", &machine_ids);
+
+    let _ = writeln!(out);
+    let _ = writeln!(out, "/** Fully qualified state ids */");
+    let state_ids: Vec<String> = states.iter().map(|(id, _)| literal(&id.to_string())).collect();
+    emit_union(&mut out, "StateId", &state_ids);
+
+    let _ = writeln!(out);
+    let _ = writeln!(out, "/** State ids of each machine */");
+    let _ = writeln!(out, "export type StateIdByMachine = {{");
+    for machine in &space.machines {
+        let ids: Vec<String> = states.iter()
+            .filter(|(id, _)| id.machine() == &machine.id)
+            .map(|(id, _)| literal(&id.to_string()))
+            .collect();
+        let _ = writeln!(out, "{}{}: {};", INDENT, literal(&machine.id.0), union(&ids));
+    }
+    let _ = writeln!(out, "}};");
+
+    let _ = writeln!(out);
+    let _ = writeln!(out, "/** Arrow ids of each state */");
+    let _ = writeln!(out, "export type ArrowIdByState = {{");
+    for (id, state) in &states {
+        let ids: Vec<String> = state.arrows.iter().map(|a| literal(&a.id.0)).collect();
+        let _ = writeln!(out, "{}{}: {};", INDENT, literal(&id.to_string()), union(&ids));
+    }
+    let _ = writeln!(out, "}};");
+    let _ = writeln!(out);
+    let _ = writeln!(out, "export type ArrowId<S extends StateId = StateId> = ArrowIdByState[S];");
+
+    let _ = writeln!(out);
+    let _ = writeln!(out, "export const machineIds: readonly MachineId[] = [{}];", machine_ids.join(", "));
+    let _ = writeln!(out);
+    let _ = writeln!(out, "export const stateIds: readonly StateId[] = [");
+    for id in &state_ids {
+        let _ = writeln!(out, "{}{},", INDENT, id);
+    }
+    let _ = writeln!(out, "];");
+
+    let _ = writeln!(out);
+    let _ = writeln!(out, "/** The state each arrow of each state leads to */");
+    let _ = writeln!(out, "export const transitions = {{");
+    for (id, state) in &states {
+        if state.arrows.is_empty() {
+            let _ = writeln!(out, "{}{}: {{}},", INDENT, literal(&id.to_string()));
+            continue;
+        }
+        let _ = writeln!(out, "{}{}: {{", INDENT, literal(&id.to_string()));
+        for arrow in &state.arrows {
+            let _ = writeln!(out, "{}{}: {},", INDENT.repeat(2), literal(&arrow.id.0), literal(&arrow.next.0));
+        }
+        let _ = writeln!(out, "{}}},", INDENT);
+    }
+    let _ = writeln!(out, "}} as const satisfies {{ [S in StateId]: {{ [A in ArrowId<S>]: StateId }} }};");
+
+    let _ = writeln!(out);
+    let _ = writeln!(out, "/** The state an arrow of `state` leads to */");
+    let _ = writeln!(out, "export function transition<S extends StateId>(state: S, arrow: ArrowId<S>): StateId {{");
+    let _ = writeln!(out, "{}return (transitions[state] as Record<string, StateId>)[arrow];", INDENT);
+    let _ = writeln!(out, "}}");
+    return Ok(out);
+}
+
+/// Generate into a file
+pub fn write_file(space: &Space, file_path: &Path) -> Result<(), CodegenError> {
+    return write_if_changed(file_path, &generate(space)?);
+}
+
+//------------------------------------------------------------------------------
+// TYPESCRIPT / Helpers
+
+/// A string literal, JSON strings being valid TypeScript
+fn literal(id: &str) -> String {
+    return serde_json::to_string(id).unwrap_or_default();
+}
+
+fn union(literals: &[String]) -> String {
+    if literals.is_empty() {
+        return "never".to_string();
+    }
+    return literals.join(" | ");
+}
+
+fn emit_union(out: &mut String, name: &str, literals: &[String]) {
+    if literals.is_empty() {
+        let _ = writeln!(out, "export type {} = never;", name);
+        return;
+    }
+    let _ = writeln!(out, "export type {} =", name);
+    for (i, literal) in literals.iter().enumerate() {
+        let end = if i + 1 == literals.len() { ";" } else { "" };
+        let _ = writeln!(out, "{}| {}{}", INDENT, literal, end);
+    }
+}
@@ -7,16 +7,24 @@
 //! ----------------------------------------------------------------------------
 
 use serde::{Serialize, Deserialize};
-use std::collections::HashMap;
-use std::collections::hash_map::Entry;
+use std::collections::BTreeMap;
+use std::collections::btree_map::Entry;
 use std::ffi::OsStr;
 use std::fmt;
 use std::fs;
-use std::path::Path;
+use std::path::{Path, PathBuf};
+use std::thread;
 use walkdir::WalkDir;
 
+use crate::define::experience::representation::qualified_id::{
+    QualifiedStateId, QualifiedStateIdError
+};
 use crate::define::experience::representation::rust_pragmatic as elea;
 
+pub mod cache;
+pub mod formatter;
+pub mod watch;
+
 //------------------------------------------------------------------------------
 // PARSERS
 
@@ -24,6 +32,21 @@ pub enum Format {
     NestedMachines,
 }
 
+/// Machine of the state files at the root of a NestedMachines directory
+pub const DEFAULT_MACHINE_ID: &str = "default";
+
+/// Load Options
+///
+/// State files of a directory are parsed on `threads` threads, defaulting
+/// to the available parallelism. With a `cache_dir`, the states parsed from
+/// each file are stored under a hash of the file's content and location, and
+/// files that did not change are not parsed again.
+#[derive(Clone, Debug, Default)]
+pub struct LoadOptions {
+    pub threads: Option<usize>,
+    pub cache_dir: Option<PathBuf>,
+}
+
 //------------------------------------------------------------------------------
 // PARSERS / Types
 //
@@ -34,6 +57,32 @@ struct StateTree {
     #[serde(default = "Vec::new")]
     tree: Vec<StateTree>,
     description: Option<String>,
+    /// Weight of the arrow leading to this node
+    #[serde(default, alias = "probability", deserialize_with = "elea::deserialize_weight")]
+    weight: Option<f64>,
+}
+
+/// An `include` entry of an Include file. The path is relative to the
+/// including file. The included directory or file is loaded as if it were
+/// located next to the including file, inside the optional `as` namespace.
+/// The arrows of states included under a mount must lead to states inside
+/// the mount, see `to_machines`.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Include {
+    pub path: String,
+    #[serde(rename = "as")]
+    pub namespace: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+struct IncludeFile {
+    include: Vec<Include>,
+}
+
+/// A Variables file: the variables of the machine it belongs to
+#[derive(Debug, Deserialize, Serialize)]
+struct VariablesFile {
+    variables: BTreeMap<String, elea::Variable>,
 }
 
 #[derive(Debug, Deserialize, Serialize)]
@@ -42,6 +91,44 @@ pub enum StateFileFormat {
     State,
     Tree,
     StateList,
+    Include,
+    Variables,
+}
+
+/// The files a machines directory was loaded from
+#[derive(Clone, Debug)]
+pub struct Sources {
+    pub files: Vec<SourceFile>,
+    /// Directories walked, the machines root first
+    pub dirs: Vec<SourceDir>,
+}
+
+/// A loaded file and the states it defines, with ids local to the machine
+/// of `base_id`. Include files define no states themselves, Variables files
+/// only define variables. `mount` is where an include placed the file, if
+/// one did.
+#[derive(Clone, Debug)]
+pub struct SourceFile {
+    pub path: PathBuf,
+    pub base_id: QualifiedStateId,
+    pub mount: Option<QualifiedStateId>,
+    pub states: Vec<elea::State>,
+    pub variables: BTreeMap<String, elea::Variable>,
+    pub is_include: bool,
+}
+
+/// A walked directory and where it is placed, see `file_base_id`
+#[derive(Clone, Debug)]
+pub struct SourceDir {
+    pub path: PathBuf,
+    pub mount: Option<QualifiedStateId>,
+}
+
+/// What a state file contributes once parsed
+pub(crate) enum StateFileContents {
+    States(Vec<elea::State>),
+    Includes(Vec<Include>),
+    Variables(BTreeMap<String, elea::Variable>),
 }
 
 impl fmt::Display for StateFileFormat {
@@ -52,27 +139,19 @@ impl fmt::Display for StateFileFormat {
         // operation succeeded or failed. Note that `write!` uses syntax which
         // is very similar to `println!`.
         match &self {
-            StateFileFormat::Unknown => write!(f, "{}", "Unknown"),
-            StateFileFormat::State => write!(f, "{}", "State"),
-            StateFileFormat::Tree => write!(f, "{}", "Tree"),
-            StateFileFormat::StateList => write!(f, "{}", "StateList"),
+            StateFileFormat::Unknown => write!(f, "Unknown"),
+            StateFileFormat::State => write!(f, "State"),
+            StateFileFormat::Tree => write!(f, "Tree"),
+            StateFileFormat::StateList => write!(f, "StateList"),
+            StateFileFormat::Include => write!(f, "Include"),
+            StateFileFormat::Variables => write!(f, "Variables"),
         }
     }
 }
 
 
 /// Check the top-level keys of the YAML file to determine the format
-fn yaml_state_file_format(file_path: &Path) -> Result<StateFileFormat, StateYAMLFileParseError> {
-
-    let file = state_yaml_file(file_path)?;
-    let file_value: serde_yaml::Value = serde_yaml::from_reader(file).map_err(|err| {
-        StateYAMLFileParseError::YAML(StateYAMLFileParseYAMLError {
-            file_path: file_path.display().to_string(),
-            format: StateFileFormat::Unknown,
-            error: err.to_string(),
-        })
-    })?;
-
+fn yaml_state_file_format(file_value: &serde_yaml::Value) -> StateFileFormat {
     let mut format: StateFileFormat = StateFileFormat::Unknown;
     if let serde_yaml::Value::Mapping(mapping) = file_value {
         for (key, _) in mapping {
@@ -81,13 +160,15 @@ fn yaml_state_file_format(file_path: &Path) -> Result<StateFileFormat, StateYAML
                     "arrows" => format = StateFileFormat::State,
                     "tree" => format = StateFileFormat::Tree,
                     "states" => format = StateFileFormat::StateList,
+                    "include" => format = StateFileFormat::Include,
+                    "variables" => format = StateFileFormat::Variables,
                     _        => {},
                 }
             }
         }
     }
 
-    return Ok(format);
+    return format;
 }
 
 
@@ -106,6 +187,30 @@ pub enum MachinesError {
 #[derive(Debug, Deserialize, Serialize)]
 pub enum FormatNestedMachinesError {
     StateFile(StateFileParseError),
+    InvalidPath(InvalidPathError),
+    Include(IncludeError),
+}
+
+/// A file whose directories cannot form a state id
+#[derive(Debug, Deserialize, Serialize)]
+pub struct InvalidPathError {
+    pub file_path: String,
+    pub error: QualifiedStateIdError,
+}
+
+//------------------------------------------------------------------------------
+// PARSERS / Types / Errors / Include
+
+#[derive(Debug, Deserialize, Serialize)]
+pub enum IncludeError {
+    /// The included path does not exist
+    NotFound { file_path: String, include_path: String },
+    /// Chain of canonical paths, ending with the path included again
+    Cycle(Vec<String>),
+    InvalidNamespace { file_path: String, error: QualifiedStateIdError },
+    /// An arrow of an included state leads outside the mount it is included
+    /// at, such as a target written out for where the file lives
+    TargetOutsideMount { file_path: String, state: String, arrow: String, next: String, mount: String },
 }
 
 //------------------------------------------------------------------------------
@@ -125,6 +230,7 @@ pub enum StateFileParseError {
 pub enum StateYAMLFileParseError {
     File(String),
     YAML(StateYAMLFileParseYAMLError),
+    StateId(QualifiedStateIdError),
     UnknownFormat,
 }
 
@@ -140,92 +246,353 @@ pub struct StateYAMLFileParseYAMLError {
 
 /// Create Elea Machines using the Lulo Filesystem representation.
 ///
-/// works with yaml files
+/// works with yaml files. An Include file (top-level `include` key) loads
+/// another local machine directory or state file in its place, so Tree files
+/// shared between machine directories take the id of where they are included.
+/// Arrow targets written out in State files are not rewritten: an included
+/// state whose arrow leads outside the mount it is included at fails to load,
+/// rather than wiring into the machine it was written for. A Variables file
+/// (top-level `variables` key) declares the variables of its machine.
+///
+/// Machines are returned in machine id order.
 pub fn to_machines(machines_path: &Path, format: Format) -> Result<Vec<elea::Machine>, MachinesError> {
+    return to_machines_with(machines_path, format, &LoadOptions::default());
+}
+
+pub fn to_machines_with(machines_path: &Path, format: Format, options: &LoadOptions) -> Result<Vec<elea::Machine>, MachinesError> {
+    let sources = to_sources_with(machines_path, format, options)?;
+    return Ok(machines_from_sources(&sources));
+}
+
+/// Load every file of a machines directory, keeping track of where each
+/// state came from. See `to_machines`.
+pub fn to_sources(machines_path: &Path, format: Format) -> Result<Sources, MachinesError> {
+    return to_sources_with(machines_path, format, &LoadOptions::default());
+}
+
+pub fn to_sources_with(machines_path: &Path, format: Format, options: &LoadOptions) -> Result<Sources, MachinesError> {
     match format {
         Format::NestedMachines => {
-            return nested_machines_from_filesystem(machines_path).map_err(|err| {
+            return nested_machines_sources(machines_path, options).map_err(|err| {
                 MachinesError::NestedMachines(err)
             });
         }
     }
 }
 
+/// Group the states and variables of loaded files into machines, in machine
+/// id order. The default machine is always present. A variable declared by
+/// several files takes the declaration of the last one.
+pub fn machines_from_sources(sources: &Sources) -> Vec<elea::Machine> {
+    return machines_from_files(sources.files.iter());
+}
 
-fn nested_machines_from_filesystem(machines_path: &Path) -> Result<Vec<elea::Machine>, FormatNestedMachinesError> {
-    
-    let mut states_default = Vec::new(); 
-    let mut states_by_machine_id: HashMap<String,Vec<elea::State>> = HashMap::new();
+/// Group the states and variables of some loaded files into machines, see
+/// `machines_from_sources`
+pub(crate) fn machines_from_files<'a>(files: impl Iterator<Item = &'a SourceFile>) -> Vec<elea::Machine> {
+    let mut states_by_machine_id: BTreeMap<String,Vec<elea::State>> = BTreeMap::new();
+    let mut variables_by_machine_id: BTreeMap<String,BTreeMap<String, elea::Variable>> = BTreeMap::new();
+    states_by_machine_id.insert(DEFAULT_MACHINE_ID.to_string(), Vec::new());
+    for file in files {
+        match states_by_machine_id.entry(file.base_id.machine().0.clone()) {
+            Entry::Vacant(e) => { e.insert(file.states.clone()); },
+            Entry::Occupied(mut e) => { e.get_mut().extend(file.states.clone()); }
+        }
+        if !file.variables.is_empty() {
+            variables_by_machine_id.entry(file.base_id.machine().0.clone())
+                .or_default()
+                .extend(file.variables.clone());
+        }
+    }
+
+    return states_by_machine_id.iter().map(|(m_id, m_states)| {
+        let mut machine = elea::Machine::new(m_id, m_states.clone());
+        machine.variables = variables_by_machine_id.remove(m_id).unwrap_or_default();
+        machine
+    }).collect();
+}
+
+fn nested_machines_sources(machines_path: &Path, options: &LoadOptions) -> Result<Sources, FormatNestedMachinesError> {
+    let mut sources = Sources { files: Vec::new(), dirs: Vec::new() };
+    let mut include_stack: Vec<PathBuf> = Vec::new();
+    states_from_dir(machines_path, None, options, &mut include_stack, &mut sources)?;
+    return Ok(sources);
+}
 
-    for entry in WalkDir::new(machines_path)
+/// Load every state file below `dir_path`, which is placed at `mount`. A
+/// `None` mount is the root of a machines directory: its files belong to the
+/// default machine and its directories are machines.
+fn states_from_dir(
+    dir_path: &Path,
+    mount: Option<&QualifiedStateId>,
+    options: &LoadOptions,
+    include_stack: &mut Vec<PathBuf>,
+    sources: &mut Sources,
+) -> Result<(), FormatNestedMachinesError> {
+
+    enter_include(dir_path, include_stack)?;
+    sources.dirs.push(SourceDir {
+        path: dir_path.to_path_buf(),
+        mount: mount.cloned(),
+    });
+
+    let mut dir_files: Vec<(PathBuf, QualifiedStateId, bool)> = Vec::new();
+    for entry in WalkDir::new(dir_path)
+        .sort_by_file_name()
         .into_iter()
+        .filter_entry(|e| !is_hidden(e))
         .filter_map(|e| e.ok()) 
         .filter(|e| !e.file_type().is_dir())
     {
         let path = entry.path();
-        let prefix = path.strip_prefix(machines_path).unwrap();
+        let prefix = path.strip_prefix(dir_path).unwrap();
 
-        let num_of_parts = prefix.iter().count();
-        // Default machine
-        if num_of_parts == 1 {
-            let states = states_from_file(path, "default").map_err(|err| {
-                FormatNestedMachinesError::StateFile(err)
-            })?;
-            states_default.extend(states);
-        // Machine is first directory
-        } else if num_of_parts >= 2 {
-            let base_path = prefix.parent();
-
-            let mut machine_name = String::new();
-            let mut state_parts: Vec<&str> = Vec::new();
-
-            // there should be at least 1 because of >=2
-            // the first will be machine
-            let mut count = 1;
-            for c in base_path.unwrap().into_iter() {
-                let part_str = c.to_str().unwrap();
-                if count == 1 {
-                    machine_name.push_str(part_str);
-                } else {
-                    state_parts.push(part_str);
-                }
-                count += 1;
+        let base_id = file_base_id(mount, prefix).map_err(|err| {
+            FormatNestedMachinesError::InvalidPath(InvalidPathError {
+                file_path: path.display().to_string(),
+                error: err,
+            })
+        })?;
+        // files in a directory of the root are inside a machine
+        let is_mounted = mount.is_some() || prefix.iter().count() > 1;
+        dir_files.push((path.to_path_buf(), base_id, is_mounted));
+    }
+
+    let parsed_files = parse_files_parallel(&dir_files, options);
+    for ((path, base_id, is_mounted), contents) in dir_files.iter().zip(parsed_files) {
+        let file_mount = if *is_mounted { Some(base_id) } else { None };
+        let contents = contents.map_err(FormatNestedMachinesError::StateFile)?;
+        add_file_contents(path, base_id, contents, file_mount, mount, options, include_stack, sources)?;
+    }
+
+    include_stack.pop();
+    return Ok(());
+}
+
+/// Hidden files and directories, such as tool configuration, are not state
+/// files
+pub(crate) fn is_hidden(entry: &walkdir::DirEntry) -> bool {
+    return entry.depth() > 0 && entry.file_name().to_string_lossy().starts_with('.');
+}
+
+/// Parse files on the configured number of threads, keeping their order
+fn parse_files_parallel(
+    files: &[(PathBuf, QualifiedStateId, bool)],
+    options: &LoadOptions,
+) -> Vec<Result<StateFileContents, StateFileParseError>> {
+    let threads = options.threads
+        .unwrap_or_else(|| thread::available_parallelism().map(|n| n.get()).unwrap_or(1))
+        .max(1);
+    if threads == 1 || files.len() < 2 {
+        return files.iter().map(|(path, base_id, _)| states_from_file(path, base_id, options)).collect();
+    }
+
+    let chunk_size = files.len().div_ceil(threads);
+    return thread::scope(|scope| {
+        let handles: Vec<_> = files.chunks(chunk_size).map(|chunk| {
+            scope.spawn(move || {
+                chunk.iter()
+                    .map(|(path, base_id, _)| states_from_file(path, base_id, options))
+                    .collect::<Vec<_>>()
+            })
+        }).collect();
+        handles.into_iter()
+            .flat_map(|handle| handle.join().expect("state file parser thread panicked"))
+            .collect()
+    });
+}
+
+/// Load one file, following it if it is an Include file
+fn states_from_path(
+    file_path: &Path,
+    base_id: &QualifiedStateId,
+    mount: Option<&QualifiedStateId>,
+    options: &LoadOptions,
+    include_stack: &mut Vec<PathBuf>,
+    sources: &mut Sources,
+) -> Result<(), FormatNestedMachinesError> {
+
+    let contents = states_from_file(file_path, base_id, options).map_err(|err| {
+        FormatNestedMachinesError::StateFile(err)
+    })?;
+    return add_file_contents(file_path, base_id, contents, mount, mount, options, include_stack, sources);
+}
+
+/// Add a parsed file to `sources`. `mount` is where the includes of the file
+/// are placed, `include_mount` where an include placed the file itself.
+#[allow(clippy::too_many_arguments)]
+fn add_file_contents(
+    file_path: &Path,
+    base_id: &QualifiedStateId,
+    contents: StateFileContents,
+    mount: Option<&QualifiedStateId>,
+    include_mount: Option<&QualifiedStateId>,
+    options: &LoadOptions,
+    include_stack: &mut Vec<PathBuf>,
+    sources: &mut Sources,
+) -> Result<(), FormatNestedMachinesError> {
+
+    match contents {
+        StateFileContents::States(states) => {
+            check_mounted_targets(file_path, &states, base_id, include_mount)
+                .map_err(FormatNestedMachinesError::Include)?;
+            sources.files.push(SourceFile {
+                path: file_path.to_path_buf(),
+                base_id: base_id.clone(),
+                mount: include_mount.cloned(),
+                states,
+                variables: BTreeMap::new(),
+                is_include: false,
+            });
+        },
+        StateFileContents::Variables(variables) => {
+            sources.files.push(SourceFile {
+                path: file_path.to_path_buf(),
+                base_id: base_id.clone(),
+                mount: include_mount.cloned(),
+                states: Vec::new(),
+                variables,
+                is_include: false,
+            });
+        },
+        StateFileContents::Includes(includes) => {
+            sources.files.push(SourceFile {
+                path: file_path.to_path_buf(),
+                base_id: base_id.clone(),
+                mount: include_mount.cloned(),
+                states: Vec::new(),
+                variables: BTreeMap::new(),
+                is_include: true,
+            });
+            enter_include(file_path, include_stack)?;
+            for include in includes {
+                states_from_include(file_path, &include, mount, options, include_stack, sources)?;
             }
-            
-            let states = states_from_file(path, &machine_name).map_err(|err| {
-                FormatNestedMachinesError::StateFile(err)
-            })?;
-            let states_with_full_id =
-                states.into_iter()
-                    .map(|state| {
-                        let full_id;
-                        if state_parts.is_empty() {
-                            full_id = state.id.0;
-                        } else {
-                            full_id = state_parts.join("/") + "/" + &state.id.0;
-                        } 
-                        elea::State::new(&full_id, state.arrows)
-                    }).collect();
-            match states_by_machine_id.entry(machine_name) {
-                Entry::Vacant(e) => { e.insert(states_with_full_id); },
-                Entry::Occupied(mut e) => { e.get_mut().extend(states_with_full_id); }
+            include_stack.pop();
+        },
+    }
+    return Ok(());
+}
+
+fn states_from_include(
+    file_path: &Path,
+    include: &Include,
+    mount: Option<&QualifiedStateId>,
+    options: &LoadOptions,
+    include_stack: &mut Vec<PathBuf>,
+    sources: &mut Sources,
+) -> Result<(), FormatNestedMachinesError> {
+
+    let include_path = file_path.parent().unwrap_or(Path::new("")).join(&include.path);
+    if !include_path.exists() {
+        return Err(FormatNestedMachinesError::Include(IncludeError::NotFound {
+            file_path: file_path.display().to_string(),
+            include_path: include.path.clone(),
+        }));
+    }
+
+    let namespaced_mount = match (&include.namespace, mount) {
+        (Some(namespace), Some(mount_id)) => Some(mount_id.join(namespace)),
+        (Some(namespace), None) => Some(QualifiedStateId::machine_root(namespace)),
+        (None, Some(mount_id)) => Some(Ok(mount_id.clone())),
+        (None, None) => None,
+    }.transpose().map_err(|err| {
+        FormatNestedMachinesError::Include(IncludeError::InvalidNamespace {
+            file_path: file_path.display().to_string(),
+            error: err,
+        })
+    })?;
+
+    if include_path.is_dir() {
+        return states_from_dir(&include_path, namespaced_mount.as_ref(), options, include_stack, sources);
+    }
+    let base_id = match &namespaced_mount {
+        Some(mount_id) => mount_id.clone(),
+        None => QualifiedStateId::machine_root(DEFAULT_MACHINE_ID).unwrap(),
+    };
+    return states_from_path(&include_path, &base_id, namespaced_mount.as_ref(), options, include_stack, sources);
+}
+
+/// Fail if an arrow of states placed at `mount` by an include leads outside
+/// the mount. Targets that are not valid ids are left to `Space::validate`.
+pub(crate) fn check_mounted_targets(
+    file_path: &Path,
+    states: &[elea::State],
+    base_id: &QualifiedStateId,
+    mount: Option<&QualifiedStateId>,
+) -> Result<(), IncludeError> {
+    let mount_id = match mount {
+        Some(mount_id) => mount_id,
+        None => return Ok(()),
+    };
+    for state in states {
+        for arrow in &state.arrows {
+            let next = match QualifiedStateId::parse(&arrow.next.0) {
+                Ok(next) => next,
+                Err(_) => continue,
+            };
+            if !mount_id.contains(&next) {
+                let state_id = QualifiedStateId::from_local(base_id.machine(), &state.id)
+                    .map(|id| id.to_string())
+                    .unwrap_or_else(|_| state.id.0.clone());
+                return Err(IncludeError::TargetOutsideMount {
+                    file_path: file_path.display().to_string(),
+                    state: state_id,
+                    arrow: arrow.id.0.clone(),
+                    next: arrow.next.0.clone(),
+                    mount: mount_id.to_string(),
+                });
             }
-            //states_by_machine_id.insert(machine_name, states_with_full_id);
         }
     }
+    return Ok(());
+}
 
-    let mut machines: Vec<elea::Machine> = states_by_machine_id.iter().map(|(m_id, m_states)| {
-        println!("machine id {}", m_id);
-        elea::Machine::new(m_id, m_states.clone())
-    }).collect();
-    machines.push(elea::Machine::new("default", states_default));
-    return Ok(machines);
+/// Track a directory or Include file being loaded, failing if it is already
+/// being loaded further up the chain of includes
+fn enter_include(path: &Path, include_stack: &mut Vec<PathBuf>) -> Result<(), FormatNestedMachinesError> {
+    let canonical_path = path.canonicalize().unwrap_or(path.to_path_buf());
+    if include_stack.contains(&canonical_path) {
+        let mut chain: Vec<String> = include_stack.iter().map(|p| p.display().to_string()).collect();
+        chain.push(canonical_path.display().to_string());
+        return Err(FormatNestedMachinesError::Include(IncludeError::Cycle(chain)));
+    }
+    include_stack.push(canonical_path);
+    return Ok(());
+}
+
+/// The id under which the states of a file are placed, from the file's path
+/// relative to its directory's mount. At the root, files belong to the
+/// default machine, otherwise the first directory is the machine and any
+/// further directories are the state path, e.g. `pattern/fade/levels.yaml`
+/// is placed under `pattern/fade`. Inside a mount, every directory is part of
+/// the state path.
+pub(crate) fn file_base_id(mount: Option<&QualifiedStateId>, relative_path: &Path) -> Result<QualifiedStateId, QualifiedStateIdError> {
+    let dirs: Vec<String> = match relative_path.parent() {
+        Some(parent) => parent.iter().map(|c| c.to_string_lossy().to_string()).collect(),
+        None => Vec::new(),
+    };
+    if let Some(mount_id) = mount {
+        let mut base_id = mount_id.clone();
+        for dir in &dirs {
+            base_id = base_id.child(dir)?;
+        }
+        return Ok(base_id);
+    }
+    return match dirs.split_first() {
+        Some((machine, state_parts)) => {
+            let state_parts: Vec<&str> = state_parts.iter().map(|s| s.as_str()).collect();
+            QualifiedStateId::new(machine, &state_parts)
+        },
+        None => QualifiedStateId::machine_root(DEFAULT_MACHINE_ID),
+    };
 }
 
-fn states_from_file(file_path: &Path, machine_id: &str)->  Result<Vec<elea::State>, StateFileParseError> {
+/// States of one file, with ids local to the machine of `base_id`
+pub(crate) fn states_from_file(file_path: &Path, base_id: &QualifiedStateId, options: &LoadOptions) ->  Result<StateFileContents, StateFileParseError> {
     let extension = file_path.extension().unwrap_or(OsStr::new("__no_ext_or_err__")).to_str().unwrap_or("__error__");
     return match extension {
-        "yaml"             => states_from_yaml_file(file_path, machine_id).map_err(|err| {
+        "yaml"             => states_from_yaml_file(file_path, base_id, options).map_err(|err| {
             StateFileParseError::YAML(err)
         }),
         "__no_ext_or_err__" => Err(StateFileParseError::NoExtensionOrError),
@@ -236,99 +603,125 @@ fn states_from_file(file_path: &Path, machine_id: &str)->  Result<Vec<elea::Stat
 
 /// Parse the different state file YAML formats
 ///
-/// More details on options.
-fn states_from_yaml_file(file_path: &Path, machine_id: &str) -> Result<Vec<elea::State>, StateYAMLFileParseError> {
+/// The file is read once: its format is determined from the parsed YAML
+/// value, which is then converted by the format's parser.
+fn states_from_yaml_file(file_path: &Path, base_id: &QualifiedStateId, options: &LoadOptions) -> Result<StateFileContents, StateYAMLFileParseError> {
+
+    let content = fs::read(file_path).map_err(|err| {
+        StateYAMLFileParseError::File(err.to_string())
+    })?;
+    let cache_dir = match &options.cache_dir {
+        Some(cache_dir) => cache_dir,
+        None => return states_from_yaml(file_path, &content, base_id),
+    };
+
+    let cache_key = cache::key(&content, base_id);
+    if let Some(states) = cache::get(cache_dir, &cache_key) {
+        return Ok(StateFileContents::States(states));
+    }
+    let contents = states_from_yaml(file_path, &content, base_id)?;
+    if let StateFileContents::States(states) = &contents {
+        cache::put(cache_dir, &cache_key, states);
+    }
+    return Ok(contents);
+}
 
-    let format = yaml_state_file_format(file_path)?;
-    println!("Parsing state YAML file [{}] as {}", file_path.display(), format);
+fn states_from_yaml(file_path: &Path, content: &[u8], base_id: &QualifiedStateId) -> Result<StateFileContents, StateYAMLFileParseError> {
+
+    let file_value: serde_yaml::Value = serde_yaml::from_slice(content).map_err(|err| {
+        yaml_error(file_path, StateFileFormat::Unknown, err)
+    })?;
+    let format = yaml_state_file_format(&file_value);
 
     match format {
         // Option 1: Entire file represents one state
         StateFileFormat::State => {
-            return state_from_file(file_path).map(|st| { vec![st] });
+            return state_from_value(file_path, file_value, base_id).map(|st| { StateFileContents::States(vec![st]) });
         },
         // Option 2: File contains a state tree
         StateFileFormat::Tree => {
-            return states_from_tree_file(file_path, machine_id);
+            return states_from_tree_value(file_path, file_value, base_id).map(StateFileContents::States);
+        },
+        // Option 3: File declares the variables of its machine
+        StateFileFormat::Variables => {
+            let variables_file: VariablesFile = serde_yaml::from_value(file_value).map_err(|err| {
+                yaml_error(file_path, StateFileFormat::Variables, err)
+            })?;
+            return Ok(StateFileContents::Variables(variables_file.variables));
+        },
+        // Option 4: File includes other machine directories or files
+        StateFileFormat::Include => {
+            let include_file: IncludeFile = serde_yaml::from_value(file_value).map_err(|err| {
+                yaml_error(file_path, StateFileFormat::Include, err)
+            })?;
+            return Ok(StateFileContents::Includes(include_file.include));
         },
         StateFileFormat::Unknown => {
             return Err(StateYAMLFileParseError::UnknownFormat);
         },
         _ => {
             println!("Error: StateList not implemented!!!");
-            return Ok(Vec::new());
+            return Ok(StateFileContents::States(Vec::new()));
         }
     }
 }
 
-fn state_from_file(file_path: &Path) -> Result<elea::State, StateYAMLFileParseError> {
-    let file = state_yaml_file(file_path)?;     
-    return serde_yaml::from_reader(file)
-            .map_err(|err| {
-                StateYAMLFileParseError::YAML(StateYAMLFileParseYAMLError {
-                    format: StateFileFormat::State, 
-                    file_path: file_path.display().to_string(),
-                    error: err.to_string(),
-                })
-            })
-            .map(|state: elea::State| {
-                // recreate state so that indexes get built and data is sorted
-                elea::State::new(&state.id.0, state.arrows.clone())
-            });
+fn yaml_error(file_path: &Path, format: StateFileFormat, err: serde_yaml::Error) -> StateYAMLFileParseError {
+    return StateYAMLFileParseError::YAML(StateYAMLFileParseYAMLError {
+        format,
+        file_path: file_path.display().to_string(),
+        error: err.to_string(),
+    });
 }
 
-fn states_from_tree_file(file_path: &Path, machine_id: &str) -> Result<Vec<elea::State>, StateYAMLFileParseError> {
-    let file = state_yaml_file(file_path)?;     
-    let state_tree: StateTree = serde_yaml::from_reader(file).map_err(|err| {
-        StateYAMLFileParseError::YAML(StateYAMLFileParseYAMLError {
-            format: StateFileFormat::State, 
-            file_path: file_path.display().to_string(),
-            error: err.to_string(),
-        })
+fn state_from_value(file_path: &Path, file_value: serde_yaml::Value, base_id: &QualifiedStateId) -> Result<elea::State, StateYAMLFileParseError> {
+    let state: elea::State = serde_yaml::from_value(file_value).map_err(|err| {
+        yaml_error(file_path, StateFileFormat::State, err)
+    })?;
+    let state_id = base_id.join(&state.id.0).map_err(StateYAMLFileParseError::StateId)?;
+    // recreate state so that indexes get built and data is sorted
+    let mut new_state = elea::State::new(&state_id.local_id().0, state.arrows.clone());
+    new_state.description = state.description;
+    return Ok(new_state);
+}
+
+fn states_from_tree_value(file_path: &Path, file_value: serde_yaml::Value, base_id: &QualifiedStateId) -> Result<Vec<elea::State>, StateYAMLFileParseError> {
+    let state_tree: StateTree = serde_yaml::from_value(file_value).map_err(|err| {
+        yaml_error(file_path, StateFileFormat::Tree, err)
     })?;
 
     let mut states: Vec<elea::State> = Vec::new();
 
-    let mut unproc_nodes: Vec<(StateTree, String)> = vec![(state_tree.clone(), String::new())];
-    let root_state_id = machine_id.to_owned() + "/" + &state_tree.id;
+    let root_state_id = base_id.join(&state_tree.id).map_err(StateYAMLFileParseError::StateId)?;
+    let mut unproc_nodes: Vec<(StateTree, QualifiedStateId)> = vec![(state_tree.clone(), root_state_id.clone())];
 
-    while let Some((node, prefix)) = unproc_nodes.pop() {
+    while let Some((node, state_id)) = unproc_nodes.pop() {
 
         let mut arrows: Vec<elea::Arrow> = Vec::new();
 
-        let state_id: String;
-        if prefix.is_empty() {
-            state_id = node.id;
-        } else {
-            state_id = prefix.to_owned() + "/" + &node.id;
-        };
-
         for child_node in node.tree {
 
             // if no arrows, then goes to root state and not a new state
-            let arrow: elea::Arrow;
+            let mut arrow: elea::Arrow;
+            let weight = child_node.weight;
             if child_node.tree.is_empty() {
-                arrow = elea::Arrow::new(&child_node.id, &root_state_id);
+                arrow = elea::Arrow::new(&child_node.id, &root_state_id.to_string());
             } else {
-                let arrow_next = machine_id.to_owned() + "/" + &state_id.clone() + "/" + &child_node.id;
-                arrow = elea::Arrow::new(&child_node.id, &arrow_next);
-                unproc_nodes.push((child_node, state_id.clone()));
+                let child_id = state_id.child(&child_node.id).map_err(StateYAMLFileParseError::StateId)?;
+                arrow = elea::Arrow::new(&child_node.id, &child_id.to_string());
+                unproc_nodes.push((child_node, child_id));
             }
+            arrow.weight = weight;
             arrows.push(arrow);
         }
-        let new_state = elea::State::new(&state_id, arrows);
+        let mut new_state = elea::State::new(&state_id.local_id().0, arrows);
+        new_state.description = node.description;
         states.push(new_state);
     }
     
     return Ok(states);
 }
 
-fn state_yaml_file(file_path: &Path) -> Result<fs::File, StateYAMLFileParseError> {
-    return fs::File::open(file_path).map_err(|err| {
-        StateYAMLFileParseError::File(err.to_string())
-    });
-}
-
 //fn dir_has_dirs(dir_path: &str) -> io::Result<bool> {
     //for entry in fs::read_dir(dir_path)? {
         //let path = &entry?.path();
@@ -0,0 +1,60 @@
+//!
+//! ! Cache
+//! On-disk cache of the states parsed from state files. Entries are keyed by
+//! a hash of the file's content and of the id the file is loaded under, since
+//! Tree files derive their arrows from where they are placed. A missing or
+//! unreadable entry is a cache miss, and failing to write one is ignored.
+//!
+//! ----------------------------------------------------------------------------
+//! type: computer for EleaFilesystem
+//! ----------------------------------------------------------------------------
+
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::define::experience::representation::canonical::hex;
+use crate::define::experience::representation::qualified_id::QualifiedStateId;
+use crate::define::experience::representation::rust_pragmatic::State;
+
+/// Part of every key, to be changed whenever the parsers' output changes
+const CACHE_VERSION: &str = "elea-state-cache-7";
+
+pub fn key(content: &[u8], base_id: &QualifiedStateId) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(CACHE_VERSION.as_bytes());
+    hasher.update([0]);
+    hasher.update(base_id.to_string().as_bytes());
+    hasher.update([0]);
+    hasher.update(content);
+    return hex(&hasher.finalize());
+}
+
+pub fn get(cache_dir: &Path, key: &str) -> Option<Vec<State>> {
+    let content = fs::read(entry_path(cache_dir, key)).ok()?;
+    let states: Vec<State> = serde_json::from_slice(&content).ok()?;
+    // indexes are not serialized
+    return Some(states.into_iter().map(|state| {
+        let mut indexed = State::new(&state.id.0, state.arrows);
+        indexed.description = state.description;
+        indexed
+    }).collect());
+}
+
+pub fn put(cache_dir: &Path, key: &str, states: &[State]) {
+    if fs::create_dir_all(cache_dir).is_err() {
+        return;
+    }
+    if let Ok(content) = serde_json::to_vec(states) {
+        // write then rename, so parallel loaders never read a partial entry
+        let path = entry_path(cache_dir, key);
+        let temp_path = path.with_extension(format!("{}.tmp", std::process::id()));
+        if fs::write(&temp_path, content).is_ok() && fs::rename(&temp_path, &path).is_err() {
+            let _ = fs::remove_file(&temp_path);
+        }
+    }
+}
+
+fn entry_path(cache_dir: &Path, key: &str) -> PathBuf {
+    return cache_dir.join(format!("{}.json", key));
+}
@@ -0,0 +1,345 @@
+//!
+//! ! Formatter
+//! Canonical layout for state YAML files, whatever their format. Mapping keys
+//! are ordered `id`, `next`, `path`, `as`, `description`, then other scalar
+//! keys alphabetically, then `arrows`, `tree`, `states` and `include`. Nested
+//! blocks are indented by two spaces, sequences included, null values are
+//! left out and strings are only quoted when YAML requires it.
+//!
+//! The layout is built from the parsed YAML, which has no comments and does
+//! not tell how a multi-line string was written. Files with comments, block
+//! scalars (`|`, `>`), anchors and aliases (`&`, `*`), tags (`!`) or document
+//! markers (`---`, `...`) are therefore never rewritten: they are reported as
+//! skipped, with the first line the layout would lose.
+//!
+//! ----------------------------------------------------------------------------
+//! type: computer for EleaFilesystem
+//! ----------------------------------------------------------------------------
+
+use serde_yaml::{Mapping, Value};
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use walkdir::WalkDir;
+
+use crate::define::experience::representation::filesystem::is_hidden;
+
+/// Keys written before any other key of a mapping, in this order
+const LEADING_KEYS: [&str; 5] = ["id", "next", "path", "as", "description"];
+
+/// Keys written after any other key of a mapping, in this order
+const TRAILING_KEYS: [&str; 4] = ["arrows", "tree", "states", "include"];
+
+const INDENT: &str = "  ";
+
+//------------------------------------------------------------------------------
+// FORMATTER
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum FormatMode {
+    /// Rewrite files that are not formatted
+    Write,
+    /// Only report files that are not formatted
+    Check,
+}
+
+/// What formatting did to a file
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum FileStatus {
+    /// The file is in the canonical layout
+    Formatted,
+    /// The file was, or with `Check` would be, rewritten
+    Unformatted,
+    /// The file is left as it is, as rewriting it would lose this content
+    Skipped(Preserved),
+}
+
+/// Content of a file that the canonical layout cannot keep. Lines are
+/// numbered from 1.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Preserved {
+    Comment { line: usize },
+    BlockScalar { line: usize },
+    /// An anchor or an alias to it
+    Anchor { line: usize },
+    Tag { line: usize },
+    DocumentMarker { line: usize },
+}
+
+/// Files of a directory that are not in the canonical layout, in path order
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct FormatReport {
+    /// Files that were, or with `Check` would be, rewritten
+    pub unformatted: Vec<PathBuf>,
+    /// Files left as they are, see `FileStatus::Skipped`
+    pub skipped: Vec<(PathBuf, Preserved)>,
+}
+
+#[derive(Debug)]
+pub enum FormatterError {
+    File { file_path: String, error: String },
+    YAML { file_path: String, error: String },
+    /// A value, such as a multi-line tagged value, with no canonical layout
+    Unsupported { file_path: String, value: String },
+    /// Content the canonical layout would lose
+    Preserved { file_path: String, preserved: Preserved },
+}
+
+impl fmt::Display for FormatterError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            FormatterError::File { file_path, error } => write!(f, "{}: {}", file_path, error),
+            FormatterError::YAML { file_path, error } => write!(f, "{}: invalid YAML: {}", file_path, error),
+            FormatterError::Unsupported { file_path, value } => {
+                write!(f, "{}: cannot format value [{}]", file_path, value)
+            },
+            FormatterError::Preserved { file_path, preserved } => {
+                write!(f, "{}: cannot format without losing the {}", file_path, preserved)
+            },
+        }
+    }
+}
+
+impl fmt::Display for Preserved {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Preserved::Comment { line } => write!(f, "comment at line {}", line),
+            Preserved::BlockScalar { line } => write!(f, "block scalar at line {}", line),
+            Preserved::Anchor { line } => write!(f, "anchor or alias at line {}", line),
+            Preserved::Tag { line } => write!(f, "tag at line {}", line),
+            Preserved::DocumentMarker { line } => write!(f, "document marker at line {}", line),
+        }
+    }
+}
+
+/// Canonical layout of one state file's content. Content the layout would
+/// lose, see `Preserved`, is refused.
+pub fn format_yaml(content: &str) -> Result<String, FormatterError> {
+    return format_content(content, "<string>");
+}
+
+/// Canonical layout of a parsed state file
+pub fn format_value(value: &Value) -> Result<String, FormatterError> {
+    return emit_document(value, "<value>");
+}
+
+/// Format one file
+pub fn format_file(file_path: &Path, mode: FormatMode) -> Result<FileStatus, FormatterError> {
+    let file_error = |err: std::io::Error| FormatterError::File {
+        file_path: file_path.display().to_string(),
+        error: err.to_string(),
+    };
+    let content = fs::read_to_string(file_path).map_err(file_error)?;
+    let formatted = match format_content(&content, &file_path.display().to_string()) {
+        Ok(formatted) => formatted,
+        Err(FormatterError::Preserved { preserved, .. }) => return Ok(FileStatus::Skipped(preserved)),
+        Err(err) => return Err(err),
+    };
+    if formatted == content {
+        return Ok(FileStatus::Formatted);
+    }
+    if mode == FormatMode::Write {
+        fs::write(file_path, formatted).map_err(file_error)?;
+    }
+    return Ok(FileStatus::Unformatted);
+}
+
+/// Format every YAML file below a machines directory, skipping hidden files
+pub fn format_dir(machines_path: &Path, mode: FormatMode) -> Result<FormatReport, FormatterError> {
+    let mut report = FormatReport::default();
+    for entry in WalkDir::new(machines_path)
+        .sort_by_file_name()
+        .into_iter()
+        .filter_entry(|e| !is_hidden(e))
+        .filter_map(|e| e.ok())
+        .filter(|e| e.file_type().is_file())
+        .filter(|e| e.path().extension().is_some_and(|ext| ext == "yaml"))
+    {
+        match format_file(entry.path(), mode)? {
+            FileStatus::Formatted => {},
+            FileStatus::Unformatted => report.unformatted.push(entry.path().to_path_buf()),
+            FileStatus::Skipped(preserved) => report.skipped.push((entry.path().to_path_buf(), preserved)),
+        }
+    }
+    return Ok(report);
+}
+
+//------------------------------------------------------------------------------
+// FORMATTER / Emitter
+
+fn format_content(content: &str, file_path: &str) -> Result<String, FormatterError> {
+    let value: Value = serde_yaml::from_str(content).map_err(|err| FormatterError::YAML {
+        file_path: file_path.to_string(),
+        error: err.to_string(),
+    })?;
+    if let Some(preserved) = preserved_content(content) {
+        return Err(FormatterError::Preserved { file_path: file_path.to_string(), preserved });
+    }
+    return emit_document(&value, file_path);
+}
+
+/// The first content of valid YAML that the layout would lose. Quotes are
+/// followed within a line, so a `#` in a quoted string spanning lines may be
+/// taken for a comment, leaving the file as it is.
+fn preserved_content(content: &str) -> Option<Preserved> {
+    for (index, line) in content.lines().enumerate() {
+        let is_marker = ["---", "..."].iter().any(|marker| {
+            line.strip_prefix(marker).is_some_and(|rest| rest.is_empty() || rest.starts_with(char::is_whitespace))
+        });
+        if is_marker {
+            return Some(Preserved::DocumentMarker { line: index + 1 });
+        }
+        // outside quotes, `#` starts a comment at the start of a line or
+        // after a space, and `&`, `*` and `!` start an anchor, an alias or a
+        // tag at the start of a value
+        let mut quote: Option<char> = None;
+        let mut previous = ' ';
+        let mut last_code: Option<char> = None;
+        let mut chars = line.chars().peekable();
+        while let Some(c) = chars.next() {
+            let starts_value = (previous.is_whitespace() || "[{,".contains(previous))
+                && last_code.is_none_or(|code| ":-?[{,".contains(code))
+                && chars.peek().is_some_and(|next| !next.is_whitespace());
+            match (quote, c) {
+                (None, '#') if previous.is_whitespace() => {
+                    return Some(Preserved::Comment { line: index + 1 });
+                },
+                (None, '&' | '*') if starts_value => return Some(Preserved::Anchor { line: index + 1 }),
+                (None, '!') if starts_value => return Some(Preserved::Tag { line: index + 1 }),
+                (None, '\'' | '"') if previous.is_whitespace() || "[{:,-".contains(previous) => quote = Some(c),
+                (Some('"'), '\\') => {
+                    chars.next();
+                },
+                (Some(open), _) if c == open => {
+                    // `''` is an escaped quote within single quotes
+                    if open == '\'' && chars.peek() == Some(&'\'') {
+                        chars.next();
+                    } else {
+                        quote = None;
+                    }
+                },
+                _ => {},
+            }
+            previous = c;
+            if !c.is_whitespace() {
+                last_code = Some(c);
+            }
+        }
+        let code = line.trim_end();
+        let last = code.rsplit(char::is_whitespace).next().unwrap_or_default();
+        let is_indicator = last.starts_with(['|', '>'])
+            && last[1..].chars().all(|c| c.is_ascii_digit() || c == '+' || c == '-');
+        let before = code[..code.len() - last.len()].trim_end();
+        if quote.is_none() && is_indicator && (before.is_empty() || before.ends_with(':') || before.ends_with('-')) {
+            return Some(Preserved::BlockScalar { line: index + 1 });
+        }
+    }
+    return None;
+}
+
+fn emit_document(value: &Value, file_path: &str) -> Result<String, FormatterError> {
+    let mut out = String::new();
+    match value {
+        Value::Mapping(mapping) if !mapping.is_empty() => emit_mapping(&mut out, mapping, 0, file_path)?,
+        Value::Sequence(sequence) if !sequence.is_empty() => emit_sequence(&mut out, sequence, 0, file_path)?,
+        _ => {
+            out.push_str(&scalar(value, file_path)?);
+            out.push('\n');
+        },
+    }
+    return Ok(out);
+}
+
+fn emit_mapping(out: &mut String, mapping: &Mapping, indent: usize, file_path: &str) -> Result<(), FormatterError> {
+    let mut first = true;
+    for (key, value) in sorted_entries(mapping) {
+        // the first entry of a sequence item follows its `- `
+        if !(first && out.ends_with("- ")) {
+            out.push_str(&INDENT.repeat(indent));
+        }
+        first = false;
+        out.push_str(&scalar(key, file_path)?);
+        out.push(':');
+        emit_nested(out, value, indent, file_path)?;
+    }
+    return Ok(());
+}
+
+fn emit_sequence(out: &mut String, sequence: &[Value], indent: usize, file_path: &str) -> Result<(), FormatterError> {
+    for item in sequence {
+        out.push_str(&INDENT.repeat(indent));
+        out.push('-');
+        match item {
+            Value::Mapping(mapping) if !mapping.is_empty() => {
+                out.push(' ');
+                emit_mapping(out, mapping, indent + 1, file_path)?;
+            },
+            _ => emit_nested(out, item, indent, file_path)?,
+        }
+    }
+    return Ok(());
+}
+
+/// The rest of a line ending in `:` or `-`, and any block below it
+fn emit_nested(out: &mut String, value: &Value, indent: usize, file_path: &str) -> Result<(), FormatterError> {
+    match value {
+        Value::Mapping(mapping) if !mapping.is_empty() => {
+            out.push('\n');
+            emit_mapping(out, mapping, indent + 1, file_path)?;
+        },
+        Value::Sequence(sequence) if !sequence.is_empty() => {
+            out.push('\n');
+            emit_sequence(out, sequence, indent + 1, file_path)?;
+        },
+        _ => {
+            out.push(' ');
+            out.push_str(&scalar(value, file_path)?);
+            out.push('\n');
+        },
+    }
+    return Ok(());
+}
+
+/// Entries in canonical key order, without null values
+fn sorted_entries(mapping: &Mapping) -> Vec<(&Value, &Value)> {
+    let rank = |key: &Value| -> (usize, String) {
+        let name = key.as_str().unwrap_or_default().to_string();
+        if let Some(i) = LEADING_KEYS.iter().position(|k| *k == name) {
+            return (0, i.to_string());
+        }
+        if let Some(i) = TRAILING_KEYS.iter().position(|k| *k == name) {
+            return (2, i.to_string());
+        }
+        return (1, name);
+    };
+    let mut entries: Vec<(&Value, &Value)> = mapping.iter()
+        .filter(|(_, value)| !value.is_null())
+        .collect();
+    entries.sort_by_key(|(key, _)| rank(key));
+    return entries;
+}
+
+/// A value written on one line
+fn scalar(value: &Value, file_path: &str) -> Result<String, FormatterError> {
+    match value {
+        Value::Mapping(_) => return Ok("{}".to_string()),
+        Value::Sequence(_) => return Ok("[]".to_string()),
+        // double quoted JSON strings are valid YAML
+        Value::String(string) if string.contains('\n') => {
+            return Ok(serde_json::to_string(string).unwrap_or_default());
+        },
+        _ => {},
+    }
+    let rendered = serde_yaml::to_string(value).map_err(|err| FormatterError::YAML {
+        file_path: file_path.to_string(),
+        error: err.to_string(),
+    })?;
+    let rendered = rendered.trim_end_matches('\n');
+    if rendered.contains('\n') {
+        return Err(FormatterError::Unsupported {
+            file_path: file_path.to_string(),
+            value: rendered.to_string(),
+        });
+    }
+    return Ok(rendered.to_string());
+}
@@ -0,0 +1,356 @@
+//!
+//! ! Watch
+//! Keep a Space in sync with a NestedMachines directory. The watcher polls
+//! the modification time and size of every loaded file, re-parses only the
+//! files that changed and rebuilds only the machines they belong to. A change
+//! to an Include file, or a new Include file, reloads the whole directory.
+//!
+//! ----------------------------------------------------------------------------
+//! type: computer for EleaFilesystem
+//! ----------------------------------------------------------------------------
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::{Duration, SystemTime};
+use walkdir::WalkDir;
+
+use crate::define::experience::representation::filesystem::{
+    check_mounted_targets, file_base_id, is_hidden, machines_from_files, machines_from_sources,
+    states_from_file, to_sources_with, Format, FormatNestedMachinesError, InvalidPathError,
+    LoadOptions, MachinesError, SourceFile, SourceDir, Sources, StateFileContents,
+    DEFAULT_MACHINE_ID,
+};
+use crate::define::experience::representation::qualified_id::QualifiedStateId;
+use crate::define::experience::representation::rust_minimal::{MachineId, StateId};
+use crate::define::experience::representation::rust_pragmatic::{
+    Space, SpaceError, State
+};
+
+//------------------------------------------------------------------------------
+// WATCHER
+
+/// Watcher
+///
+/// Owns the Space loaded from a machines directory and patches it on `poll`
+pub struct Watcher {
+    machines_path: PathBuf,
+    options: LoadOptions,
+    sources: Sources,
+    stamps: BTreeMap<PathBuf, FileStamp>,
+    space: Space,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+struct FileStamp {
+    modified: Option<SystemTime>,
+    len: u64,
+}
+
+/// What a reload changed. State ids are fully qualified.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct ReloadReport {
+    pub added_files: Vec<PathBuf>,
+    pub modified_files: Vec<PathBuf>,
+    pub removed_files: Vec<PathBuf>,
+    pub added_states: Vec<StateId>,
+    pub removed_states: Vec<StateId>,
+    pub changed_states: Vec<StateId>,
+    /// Whether the whole directory was reloaded
+    pub full_reload: bool,
+    /// Validation of the reloaded Space
+    pub errors: Vec<SpaceError>,
+}
+
+impl Watcher {
+
+    pub fn new(machines_path: &Path) -> Result<Self, MachinesError> {
+        return Self::with_options(machines_path, LoadOptions::default());
+    }
+
+    pub fn with_options(machines_path: &Path, options: LoadOptions) -> Result<Self, MachinesError> {
+        let sources = to_sources_with(machines_path, Format::NestedMachines, &options)?;
+        let space = Space::new(machines_from_sources(&sources));
+        let stamps = scan(&sources);
+        return Ok(Self {
+            machines_path: machines_path.to_path_buf(),
+            options,
+            sources,
+            stamps,
+            space,
+        });
+    }
+
+    pub fn space(&self) -> &Space {
+        return &self.space;
+    }
+
+    pub fn sources(&self) -> &Sources {
+        return &self.sources;
+    }
+
+    /// Check for changed files once. Returns `None` when nothing changed.
+    ///
+    /// On a parse error the Space is left as it was, and the failing file is
+    /// retried once it changes again.
+    pub fn poll(&mut self) -> Result<Option<ReloadReport>, MachinesError> {
+        let stamps = scan(&self.sources);
+
+        let mut report = ReloadReport::default();
+        for (path, stamp) in &stamps {
+            match self.stamps.get(path) {
+                None => report.added_files.push(path.clone()),
+                Some(old_stamp) if old_stamp != stamp => report.modified_files.push(path.clone()),
+                Some(_) => {},
+            }
+        }
+        for path in self.stamps.keys() {
+            if !stamps.contains_key(path) {
+                report.removed_files.push(path.clone());
+            }
+        }
+        if report.added_files.is_empty() && report.modified_files.is_empty() && report.removed_files.is_empty() {
+            return Ok(None);
+        }
+        self.stamps = stamps;
+
+        let patched = self.patched_sources(&report)?;
+        let (sources, machine_ids) = match patched {
+            Some(patch) => patch,
+            None => {
+                report.full_reload = true;
+                let sources = to_sources_with(&self.machines_path, Format::NestedMachines, &self.options)?;
+                // new include targets are only known after loading
+                self.stamps = scan(&sources);
+                let machine_ids = machine_ids(&self.space, &sources);
+                (sources, machine_ids)
+            },
+        };
+
+        let space = patch_space(&self.space, &sources, &machine_ids);
+        diff_states(&self.space, &space, &machine_ids, &mut report);
+        report.errors = space.validate();
+
+        self.sources = sources;
+        self.space = space;
+        return Ok(Some(report));
+    }
+
+    /// Poll every `interval` until `on_reload` returns false
+    pub fn watch<F>(&mut self, interval: Duration, mut on_reload: F)
+    where
+        F: FnMut(&Space, Result<ReloadReport, MachinesError>) -> bool
+    {
+        loop {
+            let keep_watching = match self.poll() {
+                Ok(Some(report)) => on_reload(&self.space, Ok(report)),
+                Ok(None) => true,
+                Err(err) => on_reload(&self.space, Err(err)),
+            };
+            if !keep_watching {
+                return;
+            }
+            thread::sleep(interval);
+        }
+    }
+
+    /// Sources with the changed files re-parsed, and the machines they touch,
+    /// or `None` when an Include file is involved and everything must be
+    /// reloaded
+    fn patched_sources(&self, report: &ReloadReport) -> Result<Option<(Sources, BTreeSet<MachineId>)>, MachinesError> {
+        let mut sources = self.sources.clone();
+        let mut machine_ids = BTreeSet::new();
+
+        for path in &report.removed_files {
+            if let Some(file) = sources.files.iter().find(|f| &f.path == path) {
+                if file.is_include {
+                    return Ok(None);
+                }
+                machine_ids.insert(file.base_id.machine().clone());
+            }
+            sources.files.retain(|f| &f.path != path);
+        }
+
+        for path in report.added_files.iter().chain(report.modified_files.iter()) {
+            let existing = sources.files.iter().position(|f| &f.path == path);
+            let (base_id, mount) = match existing {
+                Some(index) => {
+                    if sources.files[index].is_include {
+                        return Ok(None);
+                    }
+                    (sources.files[index].base_id.clone(), sources.files[index].mount.clone())
+                },
+                None => added_file_base_id(&sources, path)?,
+            };
+            let contents = states_from_file(path, &base_id, &self.options).map_err(|err| {
+                MachinesError::NestedMachines(FormatNestedMachinesError::StateFile(err))
+            })?;
+            let (states, variables) = match contents {
+                StateFileContents::States(states) => (states, BTreeMap::new()),
+                StateFileContents::Variables(variables) => (Vec::new(), variables),
+                StateFileContents::Includes(_) => return Ok(None),
+            };
+            check_mounted_targets(path, &states, &base_id, mount.as_ref()).map_err(|err| {
+                MachinesError::NestedMachines(FormatNestedMachinesError::Include(err))
+            })?;
+            if let Some(index) = existing {
+                machine_ids.insert(sources.files[index].base_id.machine().clone());
+                sources.files[index].states = states;
+                sources.files[index].variables = variables;
+            } else {
+                sources.files.push(SourceFile {
+                    path: path.clone(),
+                    base_id: base_id.clone(),
+                    mount,
+                    states,
+                    variables,
+                    is_include: false,
+                });
+            }
+            machine_ids.insert(base_id.machine().clone());
+        }
+
+        return Ok(Some((sources, machine_ids)));
+    }
+}
+
+//------------------------------------------------------------------------------
+// WATCHER / Report
+
+impl fmt::Display for ReloadReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.full_reload {
+            writeln!(f, "reloaded all files")?;
+        }
+        for path in &self.added_files {
+            writeln!(f, "added file    {}", path.display())?;
+        }
+        for path in &self.modified_files {
+            writeln!(f, "modified file {}", path.display())?;
+        }
+        for path in &self.removed_files {
+            writeln!(f, "removed file  {}", path.display())?;
+        }
+        for state in &self.added_states {
+            writeln!(f, "+ {}", state.0)?;
+        }
+        for state in &self.removed_states {
+            writeln!(f, "- {}", state.0)?;
+        }
+        for state in &self.changed_states {
+            writeln!(f, "~ {}", state.0)?;
+        }
+        for error in &self.errors {
+            writeln!(f, "error: {}", error)?;
+        }
+        return Ok(());
+    }
+}
+
+//------------------------------------------------------------------------------
+// WATCHER / Helpers
+
+/// Stamps of every file in the walked directories, and of files loaded
+/// through file includes
+fn scan(sources: &Sources) -> BTreeMap<PathBuf, FileStamp> {
+    let mut stamps = BTreeMap::new();
+    for dir in &sources.dirs {
+        for entry in WalkDir::new(&dir.path)
+            .into_iter()
+            .filter_entry(|e| !is_hidden(e))
+            .filter_map(|e| e.ok())
+            .filter(|e| !e.file_type().is_dir())
+        {
+            if let Some(stamp) = file_stamp(entry.path()) {
+                stamps.insert(entry.path().to_path_buf(), stamp);
+            }
+        }
+    }
+    for file in &sources.files {
+        if let Some(stamp) = file_stamp(&file.path) {
+            stamps.insert(file.path.clone(), stamp);
+        }
+    }
+    return stamps;
+}
+
+fn file_stamp(path: &Path) -> Option<FileStamp> {
+    let metadata = fs::metadata(path).ok()?;
+    return Some(FileStamp {
+        modified: metadata.modified().ok(),
+        len: metadata.len(),
+    });
+}
+
+/// Base id of a new file, from the innermost walked directory containing it,
+/// and the mount an include placed that directory at
+fn added_file_base_id(sources: &Sources, path: &Path) -> Result<(QualifiedStateId, Option<QualifiedStateId>), MachinesError> {
+    let dir: Option<&SourceDir> = sources.dirs.iter()
+        .filter(|dir| path.starts_with(&dir.path))
+        .max_by_key(|dir| dir.path.components().count());
+    let invalid_path = |error| {
+        MachinesError::NestedMachines(FormatNestedMachinesError::InvalidPath(InvalidPathError {
+            file_path: path.display().to_string(),
+            error,
+        }))
+    };
+    return match dir {
+        Some(dir) => {
+            let relative_path = path.strip_prefix(&dir.path).unwrap_or(path);
+            file_base_id(dir.mount.as_ref(), relative_path).map(|id| (id, dir.mount.clone())).map_err(invalid_path)
+        },
+        // scan only finds files below walked directories or already loaded
+        None => file_base_id(None, Path::new(path.file_name().unwrap_or_default())).map(|id| (id, None)).map_err(invalid_path),
+    };
+}
+
+/// Every machine of the old Space and of the new sources
+fn machine_ids(space: &Space, sources: &Sources) -> BTreeSet<MachineId> {
+    let mut machine_ids: BTreeSet<MachineId> = space.machines.iter().map(|m| m.id.clone()).collect();
+    for file in &sources.files {
+        machine_ids.insert(file.base_id.machine().clone());
+    }
+    return machine_ids;
+}
+
+/// Replace the given machines of `space` with the ones built from their
+/// files in `sources`, leaving the other machines as they are
+fn patch_space(space: &Space, sources: &Sources, machine_ids: &BTreeSet<MachineId>) -> Space {
+    let mut machines: Vec<_> = space.machines.iter()
+        .filter(|m| !machine_ids.contains(&m.id))
+        .cloned()
+        .collect();
+    let files = sources.files.iter().filter(|file| machine_ids.contains(file.base_id.machine()));
+    machines.extend(
+        machines_from_files(files).into_iter()
+            .filter(|m| machine_ids.contains(&m.id))
+            // a machine whose last file was removed disappears
+            .filter(|m| !m.states.is_empty() || m.id.0 == DEFAULT_MACHINE_ID)
+    );
+    return Space::new(machines);
+}
+
+fn diff_states(old_space: &Space, new_space: &Space, machine_ids: &BTreeSet<MachineId>, report: &mut ReloadReport) {
+    let states_of = |space: &Space| -> BTreeMap<String, State> {
+        return space.states().into_iter()
+            .filter(|(id, _)| machine_ids.contains(id.machine()))
+            .map(|(id, state)| (id.to_string(), state.clone()))
+            .collect();
+    };
+    let old_states = states_of(old_space);
+    let new_states = states_of(new_space);
+    for (id, state) in &new_states {
+        match old_states.get(id) {
+            None => report.added_states.push(StateId(id.clone())),
+            Some(old_state) if old_state != state => report.changed_states.push(StateId(id.clone())),
+            Some(_) => {},
+        }
+    }
+    for id in old_states.keys() {
+        if !new_states.contains_key(id) {
+            report.removed_states.push(StateId(id.clone()));
+        }
+    }
+}
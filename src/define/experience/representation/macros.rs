@@ -0,0 +1,120 @@
+//!
+//! ! Macros
+//! `space!` declares a Space inline. Machines hold states, states hold
+//! arrows, and arrows name the fully qualified state they lead to:
+//!
+//! ```
+//! let space = elea::space! {
+//!     default {
+//!         on { off -> default/off, pattern -> pattern/fade/levels }
+//!         off { on -> default/on }
+//!     }
+//!     pattern {
+//!         fade/levels { 20_percent -> default/on }
+//!     }
+//! };
+//! assert!(space.resolve("pattern/fade/levels").is_some());
+//! ```
+//!
+//! Ids are written as Rust tokens, so they are made of letters, digits and
+//! `_`. Every arrow target must be a state declared in the same macro; one
+//! that is not fails to compile:
+//!
+//! ```compile_fail
+//! let space = elea::space! {
+//!     default {
+//!         on { off -> default/off }
+//!     }
+//! };
+//! ```
+//!
+//! ----------------------------------------------------------------------------
+//! type: computer for Elea
+//! ----------------------------------------------------------------------------
+
+//------------------------------------------------------------------------------
+// MACROS
+
+#[macro_export]
+macro_rules! space {
+    (
+        $(
+            $machine:tt {
+                $(
+                    $state:tt $(/ $state_rest:tt)* {
+                        $( $arrow:tt -> $next:tt $(/ $next_rest:tt)* ),* $(,)?
+                    }
+                )*
+            }
+        )*
+    ) => {{
+        // fully qualified ids of every declared state
+        const STATES: &[&str] = &[
+            $($(
+                concat!(stringify!($machine), "/", stringify!($state) $(, "/", stringify!($state_rest))*),
+            )*)*
+        ];
+        $($($(
+            const _: () = assert!(
+                $crate::define::experience::representation::macros::contains_state(
+                    STATES,
+                    concat!(stringify!($next) $(, "/", stringify!($next_rest))*),
+                ),
+                concat!(
+                    "arrow [", stringify!($arrow), "] points at unknown state [",
+                    stringify!($next) $(, "/", stringify!($next_rest))*, "]",
+                ),
+            );
+        )*)*)*
+        $crate::define::experience::representation::rust_pragmatic::Space::new(vec![
+            $(
+                $crate::define::experience::representation::rust_pragmatic::Machine::new(stringify!($machine), vec![
+                    $(
+                        $crate::define::experience::representation::rust_pragmatic::State::new(
+                            concat!(stringify!($state) $(, "/", stringify!($state_rest))*),
+                            vec![
+                                $(
+                                    $crate::define::experience::representation::rust_pragmatic::Arrow::new(
+                                        stringify!($arrow),
+                                        concat!(stringify!($next) $(, "/", stringify!($next_rest))*),
+                                    ),
+                                )*
+                            ],
+                        ),
+                    )*
+                ]),
+            )*
+        ])
+    }};
+}
+
+//------------------------------------------------------------------------------
+// MACROS / Compile Time Checks
+
+/// Whether `id` is one of `states`, usable in constants
+#[doc(hidden)]
+pub const fn contains_state(states: &[&str], id: &str) -> bool {
+    let mut i = 0;
+    while i < states.len() {
+        if str_eq(states[i], id) {
+            return true;
+        }
+        i += 1;
+    }
+    return false;
+}
+
+const fn str_eq(s1: &str, s2: &str) -> bool {
+    let (b1, b2) = (s1.as_bytes(), s2.as_bytes());
+    if b1.len() != b2.len() {
+        return false;
+    }
+    let mut i = 0;
+    while i < b1.len() {
+        if b1[i] != b2[i] {
+            return false;
+        }
+        i += 1;
+    }
+    return true;
+}
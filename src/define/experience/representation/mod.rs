@@ -1,5 +1,9 @@
 //! Representation representations
 
+pub mod canonical;
+pub mod codegen;
 pub mod filesystem;
+pub mod macros;
+pub mod qualified_id;
 pub mod rust_minimal;
 pub mod rust_pragmatic;
@@ -0,0 +1,225 @@
+//!
+//! ! Qualified State Id
+//! A fully qualified state id names a state across a whole Space. Its first
+//! segment is the machine and the remaining segments are the state's path
+//! within that machine, e.g. `broken/fix/power/battery` is the state
+//! `fix/power/battery` of machine `broken`.
+//!
+//! ----------------------------------------------------------------------------
+//! type: computer for Elea
+//! ----------------------------------------------------------------------------
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::fmt;
+use std::str::FromStr;
+
+use crate::define::experience::representation::rust_minimal::{
+    MachineId, StateId
+};
+
+//------------------------------------------------------------------------------
+// QUALIFIED STATE ID
+
+pub const SEPARATOR: char = '/';
+
+/// Qualified State Id
+///
+/// A machine id followed by at least one state path segment. A bare machine
+/// (no state path) is represented with an empty path and is only useful as
+/// a base for `join` and `child`.
+#[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct QualifiedStateId {
+    machine: MachineId,
+    path: Vec<String>,
+}
+
+impl QualifiedStateId {
+
+    /// Create an id from a machine and the state's path segments
+    pub fn new(machine: &str, path: &[&str]) -> Result<Self, QualifiedStateIdError> {
+        validate_segment(machine)?;
+        for segment in path {
+            validate_segment(segment)?;
+        }
+        return Ok(Self {
+            machine: MachineId(machine.to_string()),
+            path: path.iter().map(|s| s.to_string()).collect(),
+        });
+    }
+
+    /// The bare machine, used as a base for building state ids
+    pub fn machine_root(machine: &str) -> Result<Self, QualifiedStateIdError> {
+        return Self::new(machine, &[]);
+    }
+
+    /// Parse a fully qualified id such as `broken/fix/power`
+    pub fn parse(id: &str) -> Result<Self, QualifiedStateIdError> {
+        if id.is_empty() {
+            return Err(QualifiedStateIdError::Empty);
+        }
+        let mut segments = id.split(SEPARATOR);
+        // split always yields at least one item
+        let machine = segments.next().unwrap_or_default();
+        let path: Vec<&str> = segments.collect();
+        if path.is_empty() {
+            return Err(QualifiedStateIdError::MissingStatePath(id.to_string()));
+        }
+        return Self::new(machine, &path);
+    }
+
+    /// Build the qualified id of a machine-local state id, e.g. machine
+    /// `pattern` and local id `fade/levels`
+    pub fn from_local(machine: &MachineId, local_id: &StateId) -> Result<Self, QualifiedStateIdError> {
+        return Self::machine_root(&machine.0)?.join(&local_id.0);
+    }
+
+    pub fn machine(&self) -> &MachineId {
+        return &self.machine;
+    }
+
+    /// State path segments within the machine
+    pub fn path(&self) -> &[String] {
+        return &self.path;
+    }
+
+    /// Whether this id names a machine rather than a state
+    pub fn is_machine_root(&self) -> bool {
+        return self.path.is_empty();
+    }
+
+    /// Last segment of the path, the state's own name
+    pub fn name(&self) -> Option<&str> {
+        return self.path.last().map(|s| s.as_str());
+    }
+
+    /// The state id as stored in its machine, e.g. `fix/power`
+    pub fn local_id(&self) -> StateId {
+        return StateId(self.path.join("/"));
+    }
+
+    /// The state id as referenced by arrows, e.g. `broken/fix/power`
+    pub fn to_state_id(&self) -> StateId {
+        return StateId(self.to_string());
+    }
+
+    /// The enclosing id, or `None` for a machine root
+    pub fn parent(&self) -> Option<Self> {
+        if self.path.is_empty() {
+            return None;
+        }
+        let mut parent = self.clone();
+        parent.path.pop();
+        return Some(parent);
+    }
+
+    /// A direct child, where `segment` is a single path segment
+    pub fn child(&self, segment: &str) -> Result<Self, QualifiedStateIdError> {
+        validate_segment(segment)?;
+        let mut child = self.clone();
+        child.path.push(segment.to_string());
+        return Ok(child);
+    }
+
+    /// Append a relative path of one or more segments, e.g. `power/battery`
+    pub fn join(&self, relative: &str) -> Result<Self, QualifiedStateIdError> {
+        let mut joined = self.clone();
+        for segment in relative.split(SEPARATOR) {
+            validate_segment(segment)?;
+            joined.path.push(segment.to_string());
+        }
+        return Ok(joined);
+    }
+
+    /// Whether `other` is this id or lies below it
+    pub fn contains(&self, other: &Self) -> bool {
+        return self.machine == other.machine && other.path.starts_with(&self.path);
+    }
+
+    /// Whether `other` lies exactly one segment below this id
+    pub fn is_parent_of(&self, other: &Self) -> bool {
+        return other.path.len() == self.path.len() + 1 && self.contains(other);
+    }
+}
+
+impl fmt::Display for QualifiedStateId {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.machine.0)?;
+        for segment in &self.path {
+            write!(f, "{}{}", SEPARATOR, segment)?;
+        }
+        return Ok(());
+    }
+}
+
+impl FromStr for QualifiedStateId {
+    type Err = QualifiedStateIdError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        return Self::parse(s);
+    }
+}
+
+impl TryFrom<&StateId> for QualifiedStateId {
+    type Error = QualifiedStateIdError;
+
+    fn try_from(state_id: &StateId) -> Result<Self, Self::Error> {
+        return Self::parse(&state_id.0);
+    }
+}
+
+impl Serialize for QualifiedStateId {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        return serializer.serialize_str(&self.to_string());
+    }
+}
+
+impl<'de> Deserialize<'de> for QualifiedStateId {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let id = String::deserialize(deserializer)?;
+        return Self::parse(&id).map_err(serde::de::Error::custom);
+    }
+}
+
+//------------------------------------------------------------------------------
+// QUALIFIED STATE ID / Validation
+
+/// A segment must be non-empty, must not be a relative path component and
+/// must not contain the separator, whitespace or control characters.
+fn validate_segment(segment: &str) -> Result<(), QualifiedStateIdError> {
+    if segment.is_empty() {
+        return Err(QualifiedStateIdError::EmptySegment);
+    }
+    if segment == "." || segment == ".." {
+        return Err(QualifiedStateIdError::IllegalSegment(segment.to_string()));
+    }
+    if segment.chars().any(|c| c == SEPARATOR || c.is_whitespace() || c.is_control()) {
+        return Err(QualifiedStateIdError::IllegalSegment(segment.to_string()));
+    }
+    return Ok(());
+}
+
+//------------------------------------------------------------------------------
+// QUALIFIED STATE ID / Errors
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub enum QualifiedStateIdError {
+    Empty,
+    EmptySegment,
+    IllegalSegment(String),
+    MissingStatePath(String),
+}
+
+impl fmt::Display for QualifiedStateIdError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            QualifiedStateIdError::Empty => write!(f, "state id is empty"),
+            QualifiedStateIdError::EmptySegment => write!(f, "state id has an empty segment"),
+            QualifiedStateIdError::IllegalSegment(segment) => {
+                write!(f, "state id segment [{}] is not allowed", segment)
+            },
+            QualifiedStateIdError::MissingStatePath(id) => {
+                write!(f, "state id [{}] names a machine but no state", id)
+            },
+        }
+    }
+}
@@ -8,10 +8,15 @@
 //! type: computer for Elea
 //! ----------------------------------------------------------------------------
 
-use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use serde::{Deserialize, Deserializer, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fmt;
 
 
+use crate::define::experience::language::context::{Type, Value};
+use crate::define::experience::representation::qualified_id::{
+    QualifiedStateId, QualifiedStateIdError
+};
 use crate::define::experience::representation::rust_minimal::{
     MachineId, StateId, ArrowId
 };
@@ -45,6 +50,141 @@ impl Space {
             machine_by_id
         };
     }
+
+    /// Find the state named by a fully qualified id, e.g. `broken/fix/power`
+    pub fn resolve(&self, id: &str) -> Option<&State> {
+        let qualified_id = QualifiedStateId::parse(id).ok()?;
+        return self.resolve_id(&qualified_id);
+    }
+
+    pub fn resolve_id(&self, id: &QualifiedStateId) -> Option<&State> {
+        let machine = self.machine_by_id.get(id.machine())?;
+        return machine.state_by_id.get(&id.local_id());
+    }
+
+    /// States lying directly below `id`, in machine order
+    pub fn children(&self, id: &QualifiedStateId) -> Vec<&State> {
+        return match self.machine_by_id.get(id.machine()) {
+            Some(machine) => {
+                machine.states.iter()
+                    .filter(|state| {
+                        QualifiedStateId::from_local(&machine.id, &state.id)
+                            .map(|state_id| id.is_parent_of(&state_id))
+                            .unwrap_or(false)
+                    })
+                    .collect()
+            },
+            None => Vec::new(),
+        };
+    }
+
+    /// Structural problems: states whose id is not a valid path, arrows
+    /// whose target does not resolve, states defined more than once in a
+    /// machine and variables declared differently by two machines
+    pub fn validate(&self) -> Vec<SpaceError> {
+        let mut errors = Vec::new();
+        for machine in &self.machines {
+            let mut seen: HashSet<&StateId> = HashSet::new();
+            for state in &machine.states {
+                let state_id = match QualifiedStateId::from_local(&machine.id, &state.id) {
+                    Ok(id) => id.to_state_id(),
+                    Err(err) => {
+                        errors.push(SpaceError::InvalidStateId {
+                            machine: machine.id.clone(),
+                            state: state.id.clone(),
+                            error: err,
+                        });
+                        continue;
+                    },
+                };
+                if !seen.insert(&state.id) {
+                    errors.push(SpaceError::DuplicateState(state_id.clone()));
+                }
+                for arrow in &state.arrows {
+                    if self.resolve(&arrow.next.0).is_none() {
+                        errors.push(SpaceError::UnresolvedArrow {
+                            state: state_id.clone(),
+                            arrow: arrow.id.clone(),
+                            next: arrow.next.clone(),
+                        });
+                    }
+                }
+            }
+        }
+        errors.extend(self.conflicting_variables());
+        return errors;
+    }
+
+    /// Variables declared differently by two machines, in machine order.
+    /// Machines share one context, so such a variable would read the value
+    /// and type of whichever machine set it last.
+    pub fn conflicting_variables(&self) -> Vec<SpaceError> {
+        let mut errors = Vec::new();
+        let mut declared_by: BTreeMap<&String, (&MachineId, &Variable)> = BTreeMap::new();
+        for machine in &self.machines {
+            for (name, variable) in &machine.variables {
+                match declared_by.get(name) {
+                    Some((first, declared)) => {
+                        if *declared != variable {
+                            errors.push(SpaceError::ConflictingVariable {
+                                name: name.clone(),
+                                first: (*first).clone(),
+                                second: machine.id.clone(),
+                            });
+                        }
+                    },
+                    None => {
+                        declared_by.insert(name, (&machine.id, variable));
+                    },
+                }
+            }
+        }
+        return errors;
+    }
+
+    /// Every state paired with its fully qualified id. States whose local id
+    /// is not a valid path are skipped.
+    pub fn states(&self) -> Vec<(QualifiedStateId, &State)> {
+        let mut states = Vec::new();
+        for machine in &self.machines {
+            for state in &machine.states {
+                if let Ok(id) = QualifiedStateId::from_local(&machine.id, &state.id) {
+                    states.push((id, state));
+                }
+            }
+        }
+        return states;
+    }
+}
+
+// Space Error
+//
+// State ids are fully qualified
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub enum SpaceError {
+    UnresolvedArrow { state: StateId, arrow: ArrowId, next: StateId },
+    DuplicateState(StateId),
+    /// A machine-local state id that is not a valid state path
+    InvalidStateId { machine: MachineId, state: StateId, error: QualifiedStateIdError },
+    /// Two machines declare a variable of the same name differently
+    ConflictingVariable { name: String, first: MachineId, second: MachineId },
+}
+
+impl fmt::Display for SpaceError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SpaceError::UnresolvedArrow { state, arrow, next } => {
+                write!(f, "arrow [{}] of [{}] points at unknown state [{}]", arrow.0, state.0, next.0)
+            },
+            SpaceError::DuplicateState(state) => write!(f, "state [{}] is defined more than once", state.0),
+            SpaceError::InvalidStateId { machine, state, error } => {
+                write!(f, "state [{}] of machine [{}] has an invalid id: {}", state.0, machine.0, error)
+            },
+            SpaceError::ConflictingVariable { name, first, second } => {
+                write!(f, "variable [{}] is declared differently by [{}] and [{}]", name, first.0, second.0)
+            },
+        }
+    }
 }
 
 // Machine
@@ -56,6 +196,10 @@ pub struct Machine {
     pub states: Vec<State>,
     #[serde(skip_serializing)]
     pub state_by_id: HashMap<StateId, State>,
+    /// Typed variables of the machine, by name, that arrow effects assign,
+    /// see `define::experience::language::effect`
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub variables: BTreeMap<String, Variable>,
 }
 
 impl Machine {
@@ -70,6 +214,7 @@ impl Machine {
             id: MachineId(id.to_string()),
             states: states_,
             state_by_id: state_by_id_,
+            variables: BTreeMap::new(),
         }
     }
     
@@ -77,11 +222,21 @@ impl Machine {
   
 impl PartialEq for Machine {
     fn eq(&self, other: &Self) -> bool {
-        self.id == other.id && self.states == other.states
+        self.id == other.id && self.states == other.states && self.variables == other.variables
     }
 }
 impl Eq for Machine {}
 
+// Variable
+//
+// A variable of a machine, and the value it starts with
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct Variable {
+    #[serde(rename = "type")]
+    pub type_: Type,
+    pub default: Value,
+}
+
 // State
 //
 // Description
@@ -127,6 +282,27 @@ pub struct Arrow {
     pub id: ArrowId,
     pub next: StateId,
     pub description: Option<String>,
+    /// Relative likelihood of taking this arrow rather than another arrow of
+    /// its state, 1 when left out. Normalized over a state's arrows, weights
+    /// are the arrows' probabilities. Weights are finite and not negative.
+    #[serde(
+        default, alias = "probability", deserialize_with = "deserialize_weight",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub weight: Option<f64>,
+    /// Condition over the runtime context for taking this arrow, see
+    /// `define::experience::language::guard`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub guard: Option<String>,
+    /// Assignments to variables of the machine applied when the arrow is
+    /// taken, in order, e.g. `set brightness = 40`, see
+    /// `define::experience::language::effect`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub effects: Vec<String>,
+    /// Time the runtime stays in the state before the arrow can be taken,
+    /// e.g. `10s`, see `define::experience::language::duration`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub after: Option<String>,
 }
 
 impl Arrow {
@@ -136,6 +312,10 @@ impl Arrow {
             id: ArrowId(id.to_string()),
             next: StateId(next_state.to_string()),
             description: None,
+            weight: None,
+            guard: None,
+            effects: Vec::new(),
+            after: None,
         }
     }
 }
@@ -143,6 +323,20 @@ impl Arrow {
 impl PartialEq for Arrow {
     fn eq(&self, other: &Self) -> bool {
         self.id == other.id && self.next == other.next && self.description == other.description
+            && self.weight == other.weight && self.guard == other.guard && self.effects == other.effects
+            && self.after == other.after
     }
 }
 impl Eq for Arrow {}
+
+/// A weight is rejected when read, rather than when a graph is built, so no
+/// Arrow of a loaded Space has a NaN, infinite or negative weight
+pub(crate) fn deserialize_weight<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<f64>, D::Error> {
+    let weight = Option::<f64>::deserialize(deserializer)?;
+    if let Some(weight) = weight {
+        if !weight.is_finite() || weight < 0.0 {
+            return Err(serde::de::Error::custom(format!("weight {} must be finite and not negative", weight)));
+        }
+    }
+    return Ok(weight);
+}
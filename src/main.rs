@@ -0,0 +1,240 @@
+//!
+//! ! Elea Command Line
+//!
+//! ----------------------------------------------------------------------------
+//! type: computer for Elea
+//! ----------------------------------------------------------------------------
+
+use clap::{Parser, Subcommand, ValueEnum};
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use std::time::Duration;
+
+use elea::define::change::diff;
+use elea::define::change::rename::{self, RenameOptions};
+use elea::define::experience::lint::{self, Severity};
+use elea::define::experience::representation::codegen;
+use elea::define::experience::representation::filesystem;
+use elea::define::experience::representation::filesystem::formatter::{self, FormatMode};
+use elea::define::experience::representation::filesystem::watch::Watcher;
+use elea::define::experience::representation::rust_pragmatic::Space;
+
+
+#[derive(Parser)]
+#[command(name = "elea", about = "Work with Elea machine directories")]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Reload a NestedMachines directory whenever its files change
+    Watch {
+        machines_path: PathBuf,
+        /// Milliseconds between checks for changed files
+        #[arg(long, default_value_t = 500)]
+        interval: u64,
+    },
+    /// Show what changed between two NestedMachines directories. Exits with
+    /// 1 when they differ.
+    Diff {
+        old_machines_path: PathBuf,
+        new_machines_path: PathBuf,
+        /// Print the differences as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Check a NestedMachines directory against its lint rules. Exits with 1
+    /// when an error is found.
+    Lint {
+        machines_path: PathBuf,
+    },
+    /// Rewrite the state files of a directory in the canonical layout. Files
+    /// with content the layout would lose, such as comments, are reported and
+    /// left as they are.
+    Fmt {
+        machines_path: PathBuf,
+        /// Only list unformatted files, and exit with 1 if there are any
+        #[arg(long)]
+        check: bool,
+    },
+    /// Rename a state, moving its file and rewriting every arrow to it
+    Rename {
+        machines_path: PathBuf,
+        /// Fully qualified id of the state
+        from: String,
+        /// Its new fully qualified id
+        to: String,
+        /// Only show what would change
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Generate code for the machines of a directory
+    Codegen {
+        machines_path: PathBuf,
+        #[arg(long, value_enum)]
+        language: Language,
+        /// File to write, instead of printing the code
+        #[arg(long)]
+        out: Option<PathBuf>,
+    },
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum Language {
+    Rust,
+    Typescript,
+}
+
+fn main() -> ExitCode {
+    let cli = Cli::parse();
+    match cli.command {
+        Command::Watch { machines_path, interval } => {
+            return watch(&machines_path, Duration::from_millis(interval));
+        },
+        Command::Diff { old_machines_path, new_machines_path, json } => {
+            return diff(&old_machines_path, &new_machines_path, json);
+        },
+        Command::Lint { machines_path } => {
+            return lint(&machines_path);
+        },
+        Command::Fmt { machines_path, check } => {
+            return fmt(&machines_path, check);
+        },
+        Command::Rename { machines_path, from, to, dry_run } => {
+            return rename(&machines_path, &from, &to, dry_run);
+        },
+        Command::Codegen { machines_path, language, out } => {
+            return generate(&machines_path, language, out.as_deref());
+        },
+    }
+}
+
+//------------------------------------------------------------------------------
+// COMMANDS
+
+fn watch(machines_path: &Path, interval: Duration) -> ExitCode {
+    let mut watcher = match Watcher::new(machines_path) {
+        Ok(watcher) => watcher,
+        Err(err) => {
+            eprintln!("Could not load [{}]: {:?}", machines_path.display(), err);
+            return ExitCode::FAILURE;
+        },
+    };
+    println!("Watching [{}], {} machines loaded", machines_path.display(), watcher.space().machines.len());
+    for error in watcher.space().validate() {
+        println!("error: {}", error);
+    }
+    watcher.watch(interval, |_, reload| {
+        match reload {
+            Ok(report) => print!("{}", report),
+            Err(err) => eprintln!("Could not reload: {:?}", err),
+        }
+        return true;
+    });
+    return ExitCode::SUCCESS;
+}
+
+fn diff(old_machines_path: &Path, new_machines_path: &Path, json: bool) -> ExitCode {
+    let (old_space, new_space) = match (load_space(old_machines_path), load_space(new_machines_path)) {
+        (Some(old_space), Some(new_space)) => (old_space, new_space),
+        _ => return ExitCode::from(2),
+    };
+    let space_diff = diff::diff(&old_space, &new_space);
+    if json {
+        match serde_json::to_string_pretty(&space_diff) {
+            Ok(output) => println!("{}", output),
+            Err(err) => {
+                eprintln!("Could not write diff: {}", err);
+                return ExitCode::from(2);
+            },
+        }
+    } else {
+        print!("{}", space_diff);
+    }
+    return if space_diff.is_empty() { ExitCode::SUCCESS } else { ExitCode::FAILURE };
+}
+
+fn lint(machines_path: &Path) -> ExitCode {
+    let findings = match lint::lint_dir(machines_path) {
+        Ok(findings) => findings,
+        Err(err) => {
+            eprintln!("Could not lint [{}]: {:?}", machines_path.display(), err);
+            return ExitCode::from(2);
+        },
+    };
+    for finding in &findings {
+        println!("{}", finding);
+    }
+    return if findings.iter().any(|f| f.severity == Severity::Error) { ExitCode::FAILURE } else { ExitCode::SUCCESS };
+}
+
+fn fmt(machines_path: &Path, check: bool) -> ExitCode {
+    let mode = if check { FormatMode::Check } else { FormatMode::Write };
+    let report = match formatter::format_dir(machines_path, mode) {
+        Ok(report) => report,
+        Err(err) => {
+            eprintln!("Could not format: {}", err);
+            return ExitCode::from(2);
+        },
+    };
+    for file_path in &report.unformatted {
+        println!("{}", file_path.display());
+    }
+    for (file_path, preserved) in &report.skipped {
+        eprintln!("skipped {}: formatting would lose the {}", file_path.display(), preserved);
+    }
+    return if check && !report.unformatted.is_empty() { ExitCode::FAILURE } else { ExitCode::SUCCESS };
+}
+
+fn rename(machines_path: &Path, from: &str, to: &str, dry_run: bool) -> ExitCode {
+    return match rename::rename_state(machines_path, from, to, RenameOptions { dry_run }) {
+        Ok(report) => {
+            print!("{}", report);
+            ExitCode::SUCCESS
+        },
+        Err(err) => {
+            eprintln!("Could not rename: {}", err);
+            ExitCode::FAILURE
+        },
+    };
+}
+
+fn generate(machines_path: &Path, language: Language, out: Option<&Path>) -> ExitCode {
+    let generated = codegen::load_space(machines_path).and_then(|space| {
+        let code = match language {
+            Language::Rust => codegen::rust::generate(&space)?,
+            Language::Typescript => codegen::typescript::generate(&space)?,
+        };
+        if let Some(file_path) = out {
+            std::fs::write(file_path, &code).map_err(|err| codegen::CodegenError::File {
+                file_path: file_path.display().to_string(),
+                error: err.to_string(),
+            })?;
+        } else {
+            print!("{}", code);
+        }
+        Ok(())
+    });
+    return match generated {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => {
+            eprintln!("Could not generate code: {}", err);
+            ExitCode::FAILURE
+        },
+    };
+}
+
+//------------------------------------------------------------------------------
+// COMMANDS / Helpers
+
+fn load_space(machines_path: &Path) -> Option<Space> {
+    return match filesystem::to_machines(machines_path, filesystem::Format::NestedMachines) {
+        Ok(machines) => Some(Space::new(machines)),
+        Err(err) => {
+            eprintln!("Could not load [{}]: {:?}", machines_path.display(), err);
+            None
+        },
+    };
+}
@@ -0,0 +1,746 @@
+//!
+//! ! Check
+//! Model checking of temporal logic properties, see `temporal`, over the
+//! states and arrows of a Space. A property holds when it holds in every
+//! initial state. Paths are infinite: a state without arrows stays where it
+//! is forever.
+//!
+//! CTL formulas are checked by labeling states with the subformulas they
+//! satisfy. LTL formulas are checked by searching the product of the Space
+//! with a tableau of the negated formula for a fair cycle, which is a path
+//! breaking the property.
+//!
+//! A failing property comes with a counterexample path, as the arrows taken
+//! from an initial state. For CTL, the path leads to where the property
+//! breaks, for instance to the state where `AG` fails or along the cycle
+//! that never reaches what `AF` waits for. Failures of existential formulas,
+//! such as `EF`, hold on no single path and stop at the initial state.
+//!
+//! ----------------------------------------------------------------------------
+//! type: computer for Elea
+//! ----------------------------------------------------------------------------
+
+use serde::{Deserialize, Serialize};
+use std::collections::hash_map::Entry;
+use std::collections::{HashMap, VecDeque};
+use std::fmt;
+
+use crate::define::experience::representation::rust_minimal::{ArrowId, StateId};
+use crate::define::experience::representation::rust_pragmatic::Space;
+use crate::plan::graph::{self, GraphError, StateGraph};
+use crate::plan::temporal::{Formula, FormulaError, Logic};
+
+/// LTL tableaux have up to `2^n` states for `n` temporal subformulas
+const MAX_LTL_TEMPORAL_SUBFORMULAS: usize = 16;
+
+//------------------------------------------------------------------------------
+// CHECK
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct CheckResult {
+    pub holds: bool,
+    pub counterexample: Option<Counterexample>,
+}
+
+/// Counterexample
+///
+/// A path from an initial state. `states` are the fully qualified states
+/// visited, the initial state first, then one state per arrow of `prefix`
+/// and `cycle`.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct Counterexample {
+    pub states: Vec<StateId>,
+    pub prefix: Vec<ArrowId>,
+    /// Arrows taken forever after the prefix. Empty when the path stays in a
+    /// state without arrows, `None` when the path ends where the property
+    /// breaks.
+    pub cycle: Option<Vec<ArrowId>>,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum CheckError {
+    Formula(FormulaError),
+    Graph(GraphError),
+    /// A state pattern naming no state, most likely a typo
+    UnmatchedPattern(String),
+    /// An LTL formula with too many temporal operators
+    TooComplex(usize),
+}
+
+impl fmt::Display for CheckError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CheckError::Formula(err) => write!(f, "invalid formula {}", err),
+            CheckError::Graph(err) => write!(f, "{}", err),
+            CheckError::UnmatchedPattern(pattern) => write!(f, "[{}] names no state", pattern),
+            CheckError::TooComplex(count) => {
+                write!(f, "{} temporal subformulas, at most {} are supported in LTL", count, MAX_LTL_TEMPORAL_SUBFORMULAS)
+            },
+        }
+    }
+}
+
+impl Counterexample {
+
+    /// Every arrow of the path, the cycle once
+    pub fn arrows(&self) -> Vec<ArrowId> {
+        let mut arrows = self.prefix.clone();
+        arrows.extend(self.cycle.iter().flatten().cloned());
+        return arrows;
+    }
+}
+
+impl fmt::Display for Counterexample {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut states = self.states.iter();
+        write!(f, "{}", states.next().map(|s| s.0.as_str()).unwrap_or_default())?;
+        for arrow in &self.prefix {
+            write!(f, " -{}-> {}", arrow.0, states.next().map(|s| s.0.as_str()).unwrap_or_default())?;
+        }
+        match &self.cycle {
+            Some(cycle) if cycle.is_empty() => write!(f, " (stays forever)")?,
+            Some(cycle) => {
+                write!(f, " (repeat")?;
+                for arrow in cycle {
+                    write!(f, " -{}-> {}", arrow.0, states.next().map(|s| s.0.as_str()).unwrap_or_default())?;
+                }
+                write!(f, ")")?;
+            },
+            None => {},
+        }
+        return Ok(());
+    }
+}
+
+/// Check a formula from the given fully qualified initial states, or from
+/// every state when there are none
+pub fn check(space: &Space, formula: &Formula, initial: &[&str]) -> Result<CheckResult, CheckError> {
+    let logic = formula.logic().map_err(CheckError::Formula)?;
+    let model = Model::new(space, formula)?;
+    let initial: Vec<usize> = match initial.is_empty() {
+        true => (0..model.graph.len()).collect(),
+        false => initial.iter()
+            .map(|state| model.graph.index_of(state))
+            .collect::<Result<_, _>>()
+            .map_err(CheckError::Graph)?,
+    };
+
+    let counterexample = match logic {
+        Logic::Ctl => {
+            let holds = model.sat(formula);
+            initial.iter()
+                .find(|state| !holds[**state])
+                .map(|state| model.counterexample(*state, model.explain_false(formula, *state)))
+        },
+        Logic::Ltl => Tableau::new(&model, formula)?.counterexample(&initial),
+    };
+    return Ok(CheckResult { holds: counterexample.is_none(), counterexample });
+}
+
+/// Parse and check a formula, see `check`
+pub fn check_str(space: &Space, formula: &str, initial: &[&str]) -> Result<CheckResult, CheckError> {
+    let formula = Formula::parse(formula).map_err(CheckError::Formula)?;
+    return check(space, &formula, initial);
+}
+
+//------------------------------------------------------------------------------
+// CHECK / Model
+
+/// The graph of a Space with the states each pattern of a formula names
+struct Model {
+    graph: StateGraph,
+    labels: HashMap<String, Vec<bool>>,
+    predecessors: Vec<Vec<usize>>,
+}
+
+/// An arrow taken, or none for staying in a state without arrows
+#[derive(Clone, Debug)]
+struct Step {
+    arrow: Option<ArrowId>,
+    to: usize,
+}
+
+/// A path from a state: finite, or ending in a cycle
+#[derive(Clone, Debug, Default)]
+struct Witness {
+    prefix: Vec<Step>,
+    cycle: Option<Vec<Step>>,
+}
+
+impl Witness {
+
+    fn is_empty(&self) -> bool {
+        return self.prefix.is_empty() && self.cycle.is_none();
+    }
+
+    /// `steps`, then the path `rest` continues with
+    fn then(mut steps: Vec<Step>, rest: Witness) -> Witness {
+        steps.extend(rest.prefix);
+        return Witness { prefix: steps, cycle: rest.cycle };
+    }
+}
+
+impl Model {
+
+    fn new(space: &Space, formula: &Formula) -> Result<Self, CheckError> {
+        let graph = StateGraph::new(space).map_err(CheckError::Graph)?;
+        let mut labels = HashMap::new();
+        for pattern in formula.patterns() {
+            let label: Vec<bool> = graph.states.iter().map(|state| Formula::matches(pattern, &state.0)).collect();
+            if !label.contains(&true) {
+                return Err(CheckError::UnmatchedPattern(pattern.to_string()));
+            }
+            labels.insert(pattern.to_string(), label);
+        }
+        let mut predecessors = vec![Vec::new(); graph.len()];
+        for state in 0..graph.len() {
+            for step in Self::steps_of(&graph, state) {
+                predecessors[step.to].push(state);
+            }
+        }
+        return Ok(Self { graph, labels, predecessors });
+    }
+
+    fn steps_of(graph: &StateGraph, state: usize) -> Vec<Step> {
+        if graph.edges[state].is_empty() {
+            return vec![Step { arrow: None, to: state }];
+        }
+        return graph.edges[state].iter().map(|edge| Step { arrow: Some(edge.arrow.clone()), to: edge.to }).collect();
+    }
+
+    fn steps(&self, state: usize) -> Vec<Step> {
+        return Self::steps_of(&self.graph, state);
+    }
+
+    fn counterexample(&self, start: usize, witness: Witness) -> Counterexample {
+        let mut states = vec![self.graph.states[start].clone()];
+        let mut arrows_of = |steps: &[Step]| -> Vec<ArrowId> {
+            return steps.iter()
+                .filter_map(|step| {
+                    let arrow = step.arrow.clone()?;
+                    states.push(self.graph.states[step.to].clone());
+                    Some(arrow)
+                })
+                .collect();
+        };
+        let prefix = arrows_of(&witness.prefix);
+        let cycle = witness.cycle.map(|cycle| arrows_of(&cycle));
+        return Counterexample { states, prefix, cycle };
+    }
+
+    //--------------------------------------------------------------------------
+    // CHECK / Model / CTL
+
+    /// States satisfying a CTL formula
+    fn sat(&self, formula: &Formula) -> Vec<bool> {
+        let n = self.graph.len();
+        let not = |set: Vec<bool>| -> Vec<bool> { set.into_iter().map(|b| !b).collect() };
+        let and = |s1: Vec<bool>, s2: Vec<bool>| -> Vec<bool> { s1.into_iter().zip(s2).map(|(b1, b2)| b1 && b2).collect() };
+        let or = |s1: Vec<bool>, s2: Vec<bool>| -> Vec<bool> { s1.into_iter().zip(s2).map(|(b1, b2)| b1 || b2).collect() };
+        return match formula {
+            Formula::True => vec![true; n],
+            Formula::False => vec![false; n],
+            Formula::State(pattern) => self.labels[pattern].clone(),
+            Formula::Not(f) => not(self.sat(f)),
+            Formula::And(f1, f2) => and(self.sat(f1), self.sat(f2)),
+            Formula::Or(f1, f2) => or(self.sat(f1), self.sat(f2)),
+            Formula::Implies(f1, f2) => or(not(self.sat(f1)), self.sat(f2)),
+            Formula::EX(f) => {
+                let set = self.sat(f);
+                (0..n).map(|s| self.steps(s).iter().any(|step| set[step.to])).collect()
+            },
+            Formula::AX(f) => {
+                let set = self.sat(f);
+                (0..n).map(|s| self.steps(s).iter().all(|step| set[step.to])).collect()
+            },
+            Formula::EF(f) => self.eu(&vec![true; n], &self.sat(f)),
+            Formula::AG(f) => not(self.eu(&vec![true; n], &not(self.sat(f)))),
+            Formula::EG(f) => self.eg(&self.sat(f)),
+            Formula::AF(f) => not(self.eg(&not(self.sat(f)))),
+            Formula::EU(f1, f2) => self.eu(&self.sat(f1), &self.sat(f2)),
+            Formula::AU(f1, f2) => {
+                let not_f1 = not(self.sat(f1));
+                let not_f2 = not(self.sat(f2));
+                let stopped = self.eu(&not_f2, &and(not_f1, not_f2.clone()));
+                not(or(stopped, self.eg(&not_f2)))
+            },
+            // LTL formulas are checked by the tableau
+            Formula::X(_) | Formula::F(_) | Formula::G(_) | Formula::U(..) => vec![false; n],
+        };
+    }
+
+    /// States with a path through `through` states to a `target` state
+    fn eu(&self, through: &[bool], target: &[bool]) -> Vec<bool> {
+        let mut result = target.to_vec();
+        let mut stack: Vec<usize> = (0..self.graph.len()).filter(|s| target[*s]).collect();
+        while let Some(state) = stack.pop() {
+            for previous in &self.predecessors[state] {
+                if through[*previous] && !result[*previous] {
+                    result[*previous] = true;
+                    stack.push(*previous);
+                }
+            }
+        }
+        return result;
+    }
+
+    /// States with an infinite path of `set` states
+    fn eg(&self, set: &[bool]) -> Vec<bool> {
+        let mut result = set.to_vec();
+        loop {
+            let mut changed = false;
+            for state in 0..self.graph.len() {
+                if result[state] && !self.steps(state).iter().any(|step| result[step.to]) {
+                    result[state] = false;
+                    changed = true;
+                }
+            }
+            if !changed {
+                return result;
+            }
+        }
+    }
+
+    /// Why a CTL formula holds in a state
+    fn explain_true(&self, formula: &Formula, state: usize) -> Witness {
+        return match formula {
+            Formula::Not(f) => self.explain_false(f, state),
+            Formula::And(f1, f2) => {
+                let witness = self.explain_true(f1, state);
+                if witness.is_empty() { self.explain_true(f2, state) } else { witness }
+            },
+            Formula::Or(f1, f2) => {
+                if self.sat(f1)[state] { self.explain_true(f1, state) } else { self.explain_true(f2, state) }
+            },
+            Formula::Implies(f1, f2) => {
+                if self.sat(f1)[state] { self.explain_true(f2, state) } else { self.explain_false(f1, state) }
+            },
+            Formula::EX(f) => {
+                let set = self.sat(f);
+                let step = self.steps(state).into_iter().find(|step| set[step.to]);
+                self.continue_with(step, |to| self.explain_true(f, to))
+            },
+            Formula::EF(f) => self.path_then(state, &vec![true; self.graph.len()], &self.sat(f), |to| self.explain_true(f, to)),
+            Formula::EU(f1, f2) => self.path_then(state, &self.sat(f1), &self.sat(f2), |to| self.explain_true(f2, to)),
+            Formula::EG(_) => self.lasso(state, &self.sat(formula)),
+            _ => Witness::default(),
+        };
+    }
+
+    /// Why a CTL formula fails in a state
+    fn explain_false(&self, formula: &Formula, state: usize) -> Witness {
+        let n = self.graph.len();
+        let not = |set: Vec<bool>| -> Vec<bool> { set.into_iter().map(|b| !b).collect() };
+        return match formula {
+            Formula::Not(f) => self.explain_true(f, state),
+            Formula::And(f1, f2) => {
+                if self.sat(f1)[state] { self.explain_false(f2, state) } else { self.explain_false(f1, state) }
+            },
+            Formula::Or(f1, f2) => {
+                let witness = self.explain_false(f1, state);
+                if witness.is_empty() { self.explain_false(f2, state) } else { witness }
+            },
+            Formula::Implies(_, f2) => self.explain_false(f2, state),
+            Formula::AX(f) => {
+                let set = self.sat(f);
+                let step = self.steps(state).into_iter().find(|step| !set[step.to]);
+                self.continue_with(step, |to| self.explain_false(f, to))
+            },
+            Formula::AG(f) => self.path_then(state, &vec![true; n], &not(self.sat(f)), |to| self.explain_false(f, to)),
+            Formula::AF(f) => self.lasso(state, &self.eg(&not(self.sat(f)))),
+            Formula::AU(f1, f2) => {
+                let not_f1 = not(self.sat(f1));
+                let not_f2 = not(self.sat(f2));
+                let stopped: Vec<bool> = not_f1.iter().zip(&not_f2).map(|(b1, b2)| *b1 && *b2).collect();
+                if self.eu(&not_f2, &stopped)[state] {
+                    self.path_then(state, &not_f2, &stopped, |to| self.explain_false(f1, to))
+                } else {
+                    self.lasso(state, &self.eg(&not_f2))
+                }
+            },
+            _ => Witness::default(),
+        };
+    }
+
+    fn continue_with<E>(&self, step: Option<Step>, explain: E) -> Witness
+    where
+        E: Fn(usize) -> Witness
+    {
+        return match step {
+            Some(step) => {
+                let to = step.to;
+                Witness::then(vec![step], explain(to))
+            },
+            None => Witness::default(),
+        };
+    }
+
+    /// Shortest path through `through` states to a `target` state, then the
+    /// explanation of the target
+    fn path_then<E>(&self, state: usize, through: &[bool], target: &[bool], explain: E) -> Witness
+    where
+        E: Fn(usize) -> Witness
+    {
+        return match shortest_path(state, |s| self.steps(s), |s| through[s], |s| target[s], false) {
+            Some(steps) => {
+                let to = steps.last().map(|step| step.to).unwrap_or(state);
+                Witness::then(steps, explain(to))
+            },
+            None => Witness::default(),
+        };
+    }
+
+    /// A path staying in `set` forever, which every state of `set` has when
+    /// `set` is the result of `eg`
+    fn lasso(&self, state: usize, set: &[bool]) -> Witness {
+        let mut position_of: HashMap<usize, usize> = HashMap::new();
+        let mut steps: Vec<Step> = Vec::new();
+        let mut current = state;
+        loop {
+            if let Some(position) = position_of.get(&current) {
+                let cycle = steps.split_off(*position);
+                return Witness { prefix: steps, cycle: Some(cycle) };
+            }
+            position_of.insert(current, steps.len());
+            match self.steps(current).into_iter().find(|step| set[step.to]) {
+                Some(step) => {
+                    current = step.to;
+                    steps.push(step);
+                },
+                None => return Witness { prefix: steps, cycle: None },
+            }
+        }
+    }
+}
+
+//------------------------------------------------------------------------------
+// CHECK / LTL
+
+/// A node of an LTL formula, with `F`, `G` and `->` rewritten in terms of `U`,
+/// `!` and `|`. Children come before their parents.
+#[derive(Clone, Debug)]
+enum Node {
+    True,
+    False,
+    State(String),
+    Not(usize),
+    And(usize, usize),
+    Or(usize, usize),
+    X(usize),
+    U(usize, usize),
+}
+
+/// Tableau
+///
+/// A product state is a state of the Space with the truth of each `X` and
+/// `U` node in the next step, as the bits of a mask. Only the masks that
+/// agree with a state and with what the previous step promised are built.
+struct Tableau<'a> {
+    model: &'a Model,
+    nodes: Vec<Node>,
+    root: usize,
+    /// `X` and `U` nodes, one mask bit each
+    temporal: Vec<usize>,
+}
+
+type ProductState = (usize, u32);
+
+impl<'a> Tableau<'a> {
+
+    fn new(model: &'a Model, formula: &Formula) -> Result<Self, CheckError> {
+        let mut tableau = Self { model, nodes: Vec::new(), root: 0, temporal: Vec::new() };
+        let root = tableau.add(formula);
+        // look for paths breaking the formula
+        tableau.root = tableau.push(Node::Not(root));
+        tableau.temporal = tableau.nodes.iter()
+            .enumerate()
+            .filter(|(_, node)| matches!(node, Node::X(_) | Node::U(..)))
+            .map(|(index, _)| index)
+            .collect();
+        if tableau.temporal.len() > MAX_LTL_TEMPORAL_SUBFORMULAS {
+            return Err(CheckError::TooComplex(tableau.temporal.len()));
+        }
+        return Ok(tableau);
+    }
+
+    fn push(&mut self, node: Node) -> usize {
+        self.nodes.push(node);
+        return self.nodes.len() - 1;
+    }
+
+    fn add(&mut self, formula: &Formula) -> usize {
+        let node = match formula {
+            Formula::True => Node::True,
+            Formula::False => Node::False,
+            Formula::State(pattern) => Node::State(pattern.clone()),
+            Formula::Not(f) => Node::Not(self.add(f)),
+            Formula::And(f1, f2) => Node::And(self.add(f1), self.add(f2)),
+            Formula::Or(f1, f2) => Node::Or(self.add(f1), self.add(f2)),
+            Formula::Implies(f1, f2) => {
+                let f1 = self.add(f1);
+                let not_f1 = self.push(Node::Not(f1));
+                Node::Or(not_f1, self.add(f2))
+            },
+            Formula::X(f) => Node::X(self.add(f)),
+            Formula::F(f) => {
+                let always = self.push(Node::True);
+                Node::U(always, self.add(f))
+            },
+            Formula::G(f) => {
+                let always = self.push(Node::True);
+                let f = self.add(f);
+                let not_f = self.push(Node::Not(f));
+                let eventually_not_f = self.push(Node::U(always, not_f));
+                Node::Not(eventually_not_f)
+            },
+            Formula::U(f1, f2) => Node::U(self.add(f1), self.add(f2)),
+            // CTL formulas are checked by labeling
+            _ => Node::False,
+        };
+        return self.push(node);
+    }
+
+    fn bit(&self, mask: u32, node: usize) -> bool {
+        let bit = self.temporal.iter().position(|t| *t == node).unwrap_or(0);
+        return mask & (1 << bit) != 0;
+    }
+
+    /// Truth of every node in a product state
+    fn eval(&self, (state, mask): ProductState) -> Vec<bool> {
+        let mut values = vec![false; self.nodes.len()];
+        let next = |node: usize| self.bit(mask, node);
+        for (index, node) in self.nodes.iter().enumerate() {
+            values[index] = match node {
+                Node::True => true,
+                Node::False => false,
+                Node::State(pattern) => self.model.labels[pattern][state],
+                Node::Not(n) => !values[*n],
+                Node::And(n1, n2) => values[*n1] && values[*n2],
+                Node::Or(n1, n2) => values[*n1] || values[*n2],
+                Node::X(_) => next(index),
+                Node::U(n1, n2) => values[*n2] || (values[*n1] && next(index)),
+            };
+        }
+        return values;
+    }
+
+    /// Value of each node the next step must have, as promised by the mask
+    /// of a step
+    fn promised(&self, mask: u32) -> Vec<Option<bool>> {
+        let mut required = vec![None; self.nodes.len()];
+        for node in &self.temporal {
+            let promised = match self.nodes[*node] {
+                Node::X(n) => n,
+                _ => *node,
+            };
+            required[promised] = Some(self.bit(mask, *node));
+        }
+        return required;
+    }
+
+    /// The masks of a state in which every node has its `required` value.
+    /// Nodes are evaluated in order, choosing the bit of each temporal node
+    /// as it comes, so a choice breaking a requirement is dropped with every
+    /// mask it would lead to.
+    fn masks(&self, state: usize, required: &[Option<bool>]) -> Vec<u32> {
+        let mut masks = Vec::new();
+        let mut values = vec![false; self.nodes.len()];
+        self.extend_masks(state, required, 0, 0, &mut values, &mut masks);
+        return masks;
+    }
+
+    fn extend_masks(
+        &self,
+        state: usize,
+        required: &[Option<bool>],
+        index: usize,
+        mask: u32,
+        values: &mut Vec<bool>,
+        masks: &mut Vec<u32>,
+    ) {
+        if index == self.nodes.len() {
+            masks.push(mask);
+            return;
+        }
+        let bit = self.temporal.iter().position(|t| *t == index);
+        let choices: &[bool] = if bit.is_some() { &[false, true] } else { &[false] };
+        for next in choices {
+            let value = match &self.nodes[index] {
+                Node::True => true,
+                Node::False => false,
+                Node::State(pattern) => self.model.labels[pattern][state],
+                Node::Not(n) => !values[*n],
+                Node::And(n1, n2) => values[*n1] && values[*n2],
+                Node::Or(n1, n2) => values[*n1] || values[*n2],
+                Node::X(_) => *next,
+                Node::U(n1, n2) => values[*n2] || (values[*n1] && *next),
+            };
+            if required[index].is_some_and(|required| required != value) {
+                continue;
+            }
+            values[index] = value;
+            let mask = match (bit, next) {
+                (Some(bit), true) => mask | (1 << bit),
+                _ => mask,
+            };
+            self.extend_masks(state, required, index + 1, mask, values, masks);
+        }
+    }
+
+    /// Whether a product state meets the fairness condition of each `U`
+    /// node: the `U` is false, or its right side holds. Without the
+    /// condition, a `U` could wait forever for its right side.
+    fn accepting(&self, values: &[bool]) -> Vec<bool> {
+        return self.temporal.iter()
+            .filter_map(|node| match self.nodes[*node] {
+                Node::U(_, n2) => Some(!values[*node] || values[n2]),
+                _ => None,
+            })
+            .collect();
+    }
+
+    /// A path from an initial state on which the formula fails
+    fn counterexample(&self, initial: &[usize]) -> Option<Counterexample> {
+        let mut values_of: HashMap<ProductState, Vec<bool>> = HashMap::new();
+        let mut values = |product: ProductState| -> Vec<bool> {
+            return values_of.entry(product).or_insert_with(|| self.eval(product)).clone();
+        };
+
+        // explore the product breadth first from its initial states
+        let mut index_of: HashMap<ProductState, usize> = HashMap::new();
+        let mut products: Vec<ProductState> = Vec::new();
+        let mut parents: Vec<Option<(usize, Step)>> = Vec::new();
+        let mut edges: Vec<Vec<(Step, usize)>> = Vec::new();
+        let mut queue = VecDeque::new();
+        let mut root_required = vec![None; self.nodes.len()];
+        root_required[self.root] = Some(true);
+        for state in initial {
+            for mask in self.masks(*state, &root_required) {
+                let product = (*state, mask);
+                if let Entry::Vacant(entry) = index_of.entry(product) {
+                    entry.insert(products.len());
+                    products.push(product);
+                    parents.push(None);
+                    edges.push(Vec::new());
+                    queue.push_back(products.len() - 1);
+                }
+            }
+        }
+        while let Some(index) = queue.pop_front() {
+            let (state, mask) = products[index];
+            let required = self.promised(mask);
+            for step in self.model.steps(state) {
+                for next_mask in self.masks(step.to, &required) {
+                    let next = (step.to, next_mask);
+                    let next_index = match index_of.get(&next) {
+                        Some(next_index) => *next_index,
+                        None => {
+                            index_of.insert(next, products.len());
+                            products.push(next);
+                            parents.push(Some((index, step.clone())));
+                            edges.push(Vec::new());
+                            queue.push_back(products.len() - 1);
+                            products.len() - 1
+                        },
+                    };
+                    edges[index].push((step.clone(), next_index));
+                }
+            }
+        }
+
+        // a reachable cycle meeting every fairness condition
+        let by_product: Vec<Vec<bool>> = products.iter().map(|p| self.accepting(&values(*p))).collect();
+        let conditions = self.temporal.iter().filter(|node| matches!(self.nodes[**node], Node::U(..))).count();
+        let accepting: Vec<Vec<bool>> = (0..conditions)
+            .map(|c| by_product.iter().map(|accepting| accepting[c]).collect())
+            .collect();
+        let successors: Vec<Vec<usize>> = edges.iter().map(|node_edges| node_edges.iter().map(|(_, to)| *to).collect()).collect();
+        // components come in reverse topological order, look from the start
+        let components = graph::components(&successors);
+        let fair = components.iter().rev().find(|component| {
+            let cyclic = component.len() > 1 || edges[component[0]].iter().any(|(_, to)| *to == component[0]);
+            cyclic && accepting.iter().all(|condition| component.iter().any(|member| condition[*member]))
+        })?;
+
+        // prefix to the cycle, from the breadth first tree
+        let entry = fair[0];
+        let mut prefix = Vec::new();
+        let mut current = entry;
+        while let Some((parent, step)) = &parents[current] {
+            prefix.push(step.clone());
+            current = *parent;
+        }
+        prefix.reverse();
+        let start = products[current].0;
+
+        // cycle through an accepting product state of each condition, then
+        // back to the entry
+        let product_steps = |p: usize| -> Vec<Step> {
+            return edges[p].iter().map(|(step, to)| Step { arrow: step.arrow.clone(), to: *to }).collect();
+        };
+        let in_fair = |p: usize| fair.binary_search(&p).is_ok();
+        let mut cycle: Vec<Step> = Vec::new();
+        let mut at = entry;
+        for condition in &accepting {
+            let steps = shortest_path(at, product_steps, in_fair, |p| condition[p], false)?;
+            at = steps.last().map(|step| step.to).unwrap_or(at);
+            cycle.extend(steps);
+        }
+        cycle.extend(shortest_path(at, product_steps, in_fair, |p| p == entry, cycle.is_empty())?);
+        let cycle = cycle.into_iter().map(|step| Step { arrow: step.arrow, to: products[step.to].0 }).collect();
+
+        return Some(self.model.counterexample(start, Witness { prefix, cycle: Some(cycle) }));
+    }
+}
+
+//------------------------------------------------------------------------------
+// CHECK / Helpers
+
+/// Breadth first search from `start` through `through` nodes to a `target`
+/// node. With `nonempty`, `start` itself is not a target before a step.
+fn shortest_path<S, T, G>(start: usize, steps: S, through: T, target: G, nonempty: bool) -> Option<Vec<Step>>
+where
+    S: Fn(usize) -> Vec<Step>,
+    T: Fn(usize) -> bool,
+    G: Fn(usize) -> bool,
+{
+    if !nonempty && target(start) {
+        return Some(Vec::new());
+    }
+    let mut parents: HashMap<usize, (usize, Step)> = HashMap::new();
+    let mut queue = VecDeque::from([start]);
+    while let Some(node) = queue.pop_front() {
+        if node != start && !through(node) {
+            continue;
+        }
+        for step in steps(node) {
+            let to = step.to;
+            if to == start || parents.contains_key(&to) {
+                if to == start && target(start) {
+                    let mut path = vec![step];
+                    let mut current = node;
+                    while let Some((parent, parent_step)) = parents.get(&current) {
+                        path.push(parent_step.clone());
+                        current = *parent;
+                    }
+                    path.reverse();
+                    return Some(path);
+                }
+                continue;
+            }
+            parents.insert(to, (node, step));
+            if target(to) {
+                let mut path = Vec::new();
+                let mut current = to;
+                while let Some((parent, parent_step)) = parents.get(&current) {
+                    path.push(parent_step.clone());
+                    current = *parent;
+                }
+                path.reverse();
+                return Some(path);
+            }
+            queue.push_back(to);
+        }
+    }
+    return None;
+}
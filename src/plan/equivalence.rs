@@ -0,0 +1,376 @@
+//!
+//! ! Equivalence
+//! Whether two Spaces behave the same, for instance before and after a
+//! refactoring of a machines directory. Spaces are equivalent when they are
+//! isomorphic up to state renaming: some one-to-one mapping of their states
+//! maps every arrow to an arrow of the same id, weight, guard, effects and
+//! duration. Optionally, arrows may be renamed as well, by a one-to-one
+//! mapping of arrow ids.
+//!
+//! States are first told apart by color refinement, as in `minimize`, over
+//! both Spaces at once. A mapping is then searched among states of the same
+//! color. When there is none, the difference names a state without
+//! counterpart, and, when arrows keep their ids, the shortest arrow sequence
+//! telling it apart from the closest state of the other Space.
+//!
+//! ----------------------------------------------------------------------------
+//! type: computer for Elea
+//! ----------------------------------------------------------------------------
+
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+use std::fmt;
+
+use crate::define::change::minimize::{self, Label};
+use crate::define::experience::representation::rust_minimal::{ArrowId, StateId};
+use crate::define::experience::representation::rust_pragmatic::Space;
+use crate::plan::graph::{Edge, GraphError, StateGraph};
+
+//------------------------------------------------------------------------------
+// EQUIVALENCE
+
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct EquivalenceOptions {
+    /// Also allow arrows to be renamed
+    pub rename_arrows: bool,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Equivalence {
+    Isomorphic(Isomorphism),
+    Different(Difference),
+}
+
+/// Fully qualified states, and arrows, of the left Space mapped to the right
+/// Space
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Isomorphism {
+    pub states: BTreeMap<StateId, StateId>,
+    pub arrows: BTreeMap<ArrowId, ArrowId>,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Side {
+    Left,
+    Right,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Difference {
+    /// `state` of `side` behaves like no state of the other Space. Taking
+    /// `sequence` from it and from `closest`, the state of the other Space
+    /// agreeing with it the longest, the last arrow is possible from one of
+    /// them only, or has another weight, guard, effects or duration.
+    Distinguished { side: Side, state: StateId, closest: StateId, sequence: Vec<ArrowId> },
+    /// The states of `side` behaving alike outnumber the states behaving
+    /// like them in the other Space, `counterparts`
+    Unmatched { side: Side, states: Vec<StateId>, counterparts: Vec<StateId> },
+    /// States cannot be told apart by their arrows, yet no mapping exists
+    NoMapping,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum EquivalenceError {
+    Left(GraphError),
+    Right(GraphError),
+}
+
+impl fmt::Display for EquivalenceError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            EquivalenceError::Left(err) => write!(f, "left space: {}", err),
+            EquivalenceError::Right(err) => write!(f, "right space: {}", err),
+        }
+    }
+}
+
+impl Equivalence {
+
+    pub fn is_isomorphic(&self) -> bool {
+        return matches!(self, Equivalence::Isomorphic(_));
+    }
+}
+
+impl fmt::Display for Side {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Side::Left => write!(f, "left"),
+            Side::Right => write!(f, "right"),
+        }
+    }
+}
+
+impl fmt::Display for Equivalence {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Equivalence::Isomorphic(isomorphism) => {
+                writeln!(f, "equivalent")?;
+                for (left, right) in isomorphism.states.iter().filter(|(left, right)| left != right) {
+                    writeln!(f, "state {} -> {}", left.0, right.0)?;
+                }
+                for (left, right) in isomorphism.arrows.iter().filter(|(left, right)| left != right) {
+                    writeln!(f, "arrow {} -> {}", left.0, right.0)?;
+                }
+            },
+            Equivalence::Different(Difference::Distinguished { side, state, closest, sequence }) => {
+                let sequence: Vec<&str> = sequence.iter().map(|arrow| arrow.0.as_str()).collect();
+                writeln!(f, "not equivalent")?;
+                writeln!(f, "{} state {} has no counterpart", side, state.0)?;
+                writeln!(f, "closest is {}, told apart by [{}]", closest.0, sequence.join(", "))?;
+            },
+            Equivalence::Different(Difference::Unmatched { side, states, counterparts }) => {
+                let ids = |states: &[StateId]| states.iter().map(|state| state.0.clone()).collect::<Vec<_>>().join(", ");
+                writeln!(f, "not equivalent")?;
+                writeln!(f, "{} states [{}] behave like [{}] only", side, ids(states), ids(counterparts))?;
+            },
+            Equivalence::Different(Difference::NoMapping) => {
+                writeln!(f, "not equivalent")?;
+                writeln!(f, "no mapping of states exists")?;
+            },
+        }
+        return Ok(());
+    }
+}
+
+/// Decide whether two Spaces are isomorphic
+pub fn equivalence(left: &Space, right: &Space, options: EquivalenceOptions) -> Result<Equivalence, EquivalenceError> {
+    let left = StateGraph::new(left).map_err(EquivalenceError::Left)?;
+    let right = StateGraph::new(right).map_err(EquivalenceError::Right)?;
+    let search = Search::new(&left, &right, options);
+
+    if let Some(difference) = search.count_difference() {
+        return Ok(Equivalence::Different(difference));
+    }
+    let mut partial = Partial {
+        state: vec![None; left.len()],
+        inverse: vec![None; right.len()],
+        arrow: BTreeMap::new(),
+        arrow_inverse: BTreeMap::new(),
+    };
+    if !options.rename_arrows {
+        // arrows keep their ids
+        for edge in left.edges.iter().flatten() {
+            partial.arrow.insert(edge.arrow.clone(), edge.arrow.clone());
+            partial.arrow_inverse.insert(edge.arrow.clone(), edge.arrow.clone());
+        }
+    }
+    let partial = match search.search(partial, Vec::new()) {
+        Some(partial) => partial,
+        None => return Ok(Equivalence::Different(Difference::NoMapping)),
+    };
+
+    let states = partial.state.iter()
+        .enumerate()
+        .filter_map(|(l, r)| Some((left.states[l].clone(), right.states[(*r)?].clone())))
+        .collect();
+    let used: HashSet<&ArrowId> = left.edges.iter().flatten().map(|edge| &edge.arrow).collect();
+    let arrows = partial.arrow.into_iter().filter(|(arrow, _)| used.contains(arrow)).collect();
+    return Ok(Equivalence::Isomorphic(Isomorphism { states, arrows }));
+}
+
+//------------------------------------------------------------------------------
+// EQUIVALENCE / Search
+
+/// A mapping being built, left to right
+#[derive(Clone, Debug)]
+struct Partial {
+    state: Vec<Option<usize>>,
+    inverse: Vec<Option<usize>>,
+    arrow: BTreeMap<ArrowId, ArrowId>,
+    arrow_inverse: BTreeMap<ArrowId, ArrowId>,
+}
+
+struct Search<'a> {
+    left: &'a StateGraph,
+    right: &'a StateGraph,
+    options: EquivalenceOptions,
+    /// Colors of left states, then right states
+    colors: Vec<usize>,
+    class_sizes: HashMap<usize, usize>,
+}
+
+impl<'a> Search<'a> {
+
+    fn new(left: &'a StateGraph, right: &'a StateGraph, options: EquivalenceOptions) -> Self {
+        let mut search = Self { left, right, options, colors: Vec::new(), class_sizes: HashMap::new() };
+        search.colors = search.refine();
+        for color in &search.colors[..left.len()] {
+            *search.class_sizes.entry(*color).or_default() += 1;
+        }
+        return search;
+    }
+
+    /// The label of an edge, without its id when arrows may be renamed
+    fn label(&self, edge: &Edge) -> Label {
+        let id = if self.options.rename_arrows { None } else { Some(&edge.arrow) };
+        return Label::new(id, Some(edge.weight), edge.guard.as_ref(), &edge.effects, edge.after.as_ref());
+    }
+
+    /// Edges of a state of both graphs, left states first
+    fn edges(&self, state: usize) -> Vec<(Label, usize)> {
+        return match state < self.left.len() {
+            true => self.left.edges[state].iter().map(|edge| (self.label(edge), edge.to)).collect(),
+            false => {
+                let offset = self.left.len();
+                self.right.edges[state - offset].iter().map(|edge| (self.label(edge), edge.to + offset)).collect()
+            },
+        };
+    }
+
+    fn state_id(&self, state: usize) -> StateId {
+        return match state < self.left.len() {
+            true => self.left.states[state].clone(),
+            false => self.right.states[state - self.left.len()].clone(),
+        };
+    }
+
+    /// Colors of the states of both graphs, the same when they are bisimilar
+    fn refine(&self) -> Vec<usize> {
+        let edges: Vec<Vec<(Label, usize)>> = (0..self.left.len() + self.right.len()).map(|s| self.edges(s)).collect();
+        return minimize::refine(&edges, &vec![0; edges.len()]);
+    }
+
+    /// The first color with more states on one side than on the other
+    fn count_difference(&self) -> Option<Difference> {
+        let offset = self.left.len();
+        let mut members: BTreeMap<usize, (Vec<usize>, Vec<usize>)> = BTreeMap::new();
+        for (state, color) in self.colors.iter().enumerate() {
+            let entry = members.entry(*color).or_default();
+            if state < offset { entry.0.push(state) } else { entry.1.push(state) }
+        }
+        let (left, right) = members.into_values().find(|(left, right)| left.len() != right.len())?;
+        let (side, states, counterparts) = match left.len() > right.len() {
+            true => (Side::Left, left, right),
+            false => (Side::Right, right, left),
+        };
+        if !counterparts.is_empty() || self.options.rename_arrows {
+            let ids = |states: &[usize]| states.iter().map(|state| self.state_id(*state)).collect();
+            return Some(Difference::Unmatched { side, states: ids(&states), counterparts: ids(&counterparts) });
+        }
+
+        // the other state agreeing the longest
+        let state = states[0];
+        let others: Vec<usize> = match side {
+            Side::Left => (offset..offset + self.right.len()).collect(),
+            Side::Right => (0..offset).collect(),
+        };
+        let (closest, sequence) = others.into_iter()
+            .filter_map(|other| Some((other, self.distinguishing_sequence(state, other)?)))
+            .max_by_key(|(other, sequence)| (sequence.len(), std::cmp::Reverse(*other)))?;
+        return Some(Difference::Distinguished {
+            side,
+            state: self.state_id(state),
+            closest: self.state_id(closest),
+            sequence,
+        });
+    }
+
+    /// Shortest arrow sequence possible from both states but for its last
+    /// arrow, which only one of them has, or has with another weight, guard,
+    /// effects or duration
+    fn distinguishing_sequence(&self, s1: usize, s2: usize) -> Option<Vec<ArrowId>> {
+        let mut parents: HashMap<(usize, usize), ((usize, usize), ArrowId)> = HashMap::new();
+        let mut queue = VecDeque::from([(s1, s2)]);
+        let mut seen = HashSet::from([(s1, s2)]);
+        while let Some((u, v)) = queue.pop_front() {
+            let (edges_u, edges_v) = (self.edges(u), self.edges(v));
+            let mut labels_u: Vec<&Label> = edges_u.iter().map(|(label, _)| label).collect();
+            let mut labels_v: Vec<&Label> = edges_v.iter().map(|(label, _)| label).collect();
+            labels_u.sort();
+            labels_v.sort();
+            let differing = labels_u.iter()
+                .filter(|label| !labels_v.contains(label))
+                .chain(labels_v.iter().filter(|label| !labels_u.contains(label)))
+                .filter_map(|label| label.id().cloned())
+                .min();
+            if let Some(arrow) = differing {
+                let mut sequence = vec![arrow];
+                let mut pair = (u, v);
+                while let Some((parent, arrow)) = parents.get(&pair) {
+                    sequence.push(arrow.clone());
+                    pair = *parent;
+                }
+                sequence.reverse();
+                return Some(sequence);
+            }
+            for (label, to_u) in &edges_u {
+                let to_v = edges_v.iter().find(|(other, _)| other == label).map(|(_, to)| *to)?;
+                if seen.insert((*to_u, to_v)) {
+                    parents.insert((*to_u, to_v), ((u, v), label.id()?.clone()));
+                    queue.push_back((*to_u, to_v));
+                }
+            }
+        }
+        return None;
+    }
+
+    /// Map a left state to a right state, returning whether it is new
+    fn assign(&self, partial: &mut Partial, l: usize, r: usize) -> Option<bool> {
+        if partial.state[l] == Some(r) {
+            return Some(false);
+        }
+        if partial.state[l].is_some() || partial.inverse[r].is_some() || self.colors[l] != self.colors[r + self.left.len()] {
+            return None;
+        }
+        partial.state[l] = Some(r);
+        partial.inverse[r] = Some(l);
+        return Some(true);
+    }
+
+    /// Complete a mapping: map the next states of the `pending` pairs, then
+    /// the states left, backtracking over the choices of states and arrows
+    fn search(&self, mut partial: Partial, mut pending: Vec<(usize, usize)>) -> Option<Partial> {
+        while let Some((l, r)) = pending.pop() {
+            let right_edges = &self.right.edges[r];
+            for edge in &self.left.edges[l] {
+                let arrow = match partial.arrow.get(&edge.arrow) {
+                    Some(arrow) => arrow.clone(),
+                    None => {
+                        // try each free arrow of the right state
+                        for candidate in right_edges {
+                            if partial.arrow_inverse.contains_key(&candidate.arrow) || !agrees(edge, candidate) {
+                                continue;
+                            }
+                            let mut next = partial.clone();
+                            next.arrow.insert(edge.arrow.clone(), candidate.arrow.clone());
+                            next.arrow_inverse.insert(candidate.arrow.clone(), edge.arrow.clone());
+                            let mut next_pending = pending.clone();
+                            next_pending.push((l, r));
+                            if let Some(complete) = self.search(next, next_pending) {
+                                return Some(complete);
+                            }
+                        }
+                        return None;
+                    },
+                };
+                let other = right_edges.iter().find(|other| other.arrow == arrow && agrees(edge, other))?;
+                if self.assign(&mut partial, edge.to, other.to)? {
+                    pending.push((edge.to, other.to));
+                }
+            }
+        }
+
+        // the unmapped left state with the fewest candidates
+        let next = (0..self.left.len())
+            .filter(|l| partial.state[*l].is_none())
+            .min_by_key(|l| (self.class_sizes[&self.colors[*l]], *l));
+        let l = match next {
+            Some(l) => l,
+            None => return Some(partial),
+        };
+        for r in 0..self.right.len() {
+            let mut next = partial.clone();
+            if self.assign(&mut next, l, r) == Some(true) {
+                if let Some(complete) = self.search(next, vec![(l, r)]) {
+                    return Some(complete);
+                }
+            }
+        }
+        return None;
+    }
+}
+
+/// Whether two arrows agree in all but their id and next state
+fn agrees(left: &Edge, right: &Edge) -> bool {
+    return left.weight == right.weight && left.guard == right.guard && left.effects == right.effects
+        && left.after == right.after;
+}
@@ -0,0 +1,263 @@
+//!
+//! ! State Graph
+//! The states of a Space as numbered nodes and its arrows as weighted edges,
+//! the form simulations and analyses work on. State ids are fully qualified.
+//!
+//! ----------------------------------------------------------------------------
+//! type: computer for Elea
+//! ----------------------------------------------------------------------------
+
+use std::collections::HashMap;
+use std::fmt;
+
+use crate::define::experience::representation::rust_minimal::{ArrowId, StateId};
+use crate::define::experience::representation::rust_pragmatic::{
+    Space, SpaceError
+};
+
+//------------------------------------------------------------------------------
+// GRAPH
+
+/// State Graph
+///
+/// Nodes are in `Space::states` order, edges in arrow order
+#[derive(Clone, Debug)]
+pub struct StateGraph {
+    pub states: Vec<StateId>,
+    pub edges: Vec<Vec<Edge>>,
+    index_by_id: HashMap<String, usize>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Edge {
+    pub arrow: ArrowId,
+    pub to: usize,
+    pub weight: f64,
+    pub guard: Option<String>,
+    pub effects: Vec<String>,
+    pub after: Option<String>,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum GraphError {
+    /// Graphs are only built from Spaces without structural problems
+    Space(Vec<SpaceError>),
+    /// Weights are finite and not negative
+    InvalidWeight { state: StateId, arrow: ArrowId, weight: f64 },
+    UnknownState(String),
+}
+
+impl fmt::Display for GraphError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            GraphError::Space(errors) => {
+                write!(f, "invalid space:")?;
+                for error in errors {
+                    write!(f, " {};", error)?;
+                }
+                return Ok(());
+            },
+            GraphError::InvalidWeight { state, arrow, weight } => {
+                write!(f, "arrow [{}] of [{}] has invalid weight {}", arrow.0, state.0, weight)
+            },
+            GraphError::UnknownState(state) => write!(f, "unknown state [{}]", state),
+        }
+    }
+}
+
+impl StateGraph {
+
+    pub fn new(space: &Space) -> Result<Self, GraphError> {
+        let errors = space.validate();
+        if !errors.is_empty() {
+            return Err(GraphError::Space(errors));
+        }
+        let states = space.states();
+        let index_by_id: HashMap<String, usize> = states.iter()
+            .enumerate()
+            .map(|(index, (id, _))| (id.to_string(), index))
+            .collect();
+
+        let mut edges = Vec::with_capacity(states.len());
+        for (id, state) in &states {
+            let mut state_edges = Vec::with_capacity(state.arrows.len());
+            for arrow in &state.arrows {
+                let weight = arrow.weight.unwrap_or(1.0);
+                if !weight.is_finite() || weight < 0.0 {
+                    return Err(GraphError::InvalidWeight {
+                        state: id.to_state_id(),
+                        arrow: arrow.id.clone(),
+                        weight,
+                    });
+                }
+                // validated above
+                let to = index_by_id[&arrow.next.0];
+                state_edges.push(Edge {
+                    arrow: arrow.id.clone(),
+                    to,
+                    weight,
+                    guard: arrow.guard.clone(),
+                    effects: arrow.effects.clone(),
+                    after: arrow.after.clone(),
+                });
+            }
+            edges.push(state_edges);
+        }
+
+        return Ok(Self {
+            states: states.iter().map(|(id, _)| id.to_state_id()).collect(),
+            edges,
+            index_by_id,
+        });
+    }
+
+    pub fn len(&self) -> usize {
+        return self.states.len();
+    }
+
+    pub fn is_empty(&self) -> bool {
+        return self.states.is_empty();
+    }
+
+    /// Index of a fully qualified state id
+    pub fn index_of(&self, id: &str) -> Result<usize, GraphError> {
+        return self.index_by_id.get(id).copied().ok_or_else(|| GraphError::UnknownState(id.to_string()));
+    }
+
+    /// Probability of each edge of a state, its weight over the state's
+    /// total weight. Every probability is 0 when the total is 0.
+    pub fn probabilities(&self, state: usize) -> Vec<f64> {
+        let total: f64 = self.edges[state].iter().map(|e| e.weight).sum();
+        return self.edges[state].iter()
+            .map(|e| if total > 0.0 { e.weight / total } else { 0.0 })
+            .collect();
+    }
+
+    /// Whether no arrow can be taken from a state: it has none, or their
+    /// weights are all 0
+    pub fn is_absorbing(&self, state: usize) -> bool {
+        return self.edges[state].iter().all(|e| e.weight == 0.0);
+    }
+
+    /// States reached by an arrow of weight above 0
+    pub fn successors(&self, state: usize) -> impl Iterator<Item = usize> + '_ {
+        return self.edges[state].iter().filter(|e| e.weight > 0.0).map(|e| e.to);
+    }
+
+    /// States that can reach one of `targets` through arrows of weight above
+    /// 0, the targets included, without passing through `avoid`
+    pub fn can_reach(&self, targets: &[usize], avoid: &[bool]) -> Vec<bool> {
+        let mut predecessors: Vec<Vec<usize>> = vec![Vec::new(); self.len()];
+        for state in 0..self.len() {
+            for next in self.successors(state) {
+                predecessors[next].push(state);
+            }
+        }
+        let mut reached = vec![false; self.len()];
+        let mut stack: Vec<usize> = targets.to_vec();
+        for target in targets {
+            reached[*target] = true;
+        }
+        while let Some(state) = stack.pop() {
+            for previous in &predecessors[state] {
+                if !reached[*previous] && !avoid[*previous] {
+                    reached[*previous] = true;
+                    stack.push(*previous);
+                }
+            }
+        }
+        return reached;
+    }
+
+    /// Strongly connected components over arrows of weight above 0, each in
+    /// state order, in reverse topological order
+    pub fn components(&self) -> Vec<Vec<usize>> {
+        let successors: Vec<Vec<usize>> = (0..self.len()).map(|state| self.successors(state).collect()).collect();
+        return components(&successors);
+    }
+}
+
+//------------------------------------------------------------------------------
+// GRAPH / Components
+
+/// Strongly connected components of the graph with the `successors` of
+/// each node, each in node order, in reverse topological order
+pub(crate) fn components(successors: &[Vec<usize>]) -> Vec<Vec<usize>> {
+    let mut tarjan = Tarjan {
+        successors,
+        index: 0,
+        indexes: vec![None; successors.len()],
+        low_links: vec![0; successors.len()],
+        stack: Vec::new(),
+        on_stack: vec![false; successors.len()],
+        components: Vec::new(),
+    };
+    for node in 0..successors.len() {
+        if tarjan.indexes[node].is_none() {
+            tarjan.visit(node);
+        }
+    }
+    return tarjan.components;
+}
+
+struct Tarjan<'a> {
+    successors: &'a [Vec<usize>],
+    index: usize,
+    indexes: Vec<Option<usize>>,
+    low_links: Vec<usize>,
+    stack: Vec<usize>,
+    on_stack: Vec<bool>,
+    components: Vec<Vec<usize>>,
+}
+
+impl Tarjan<'_> {
+
+    /// Depth first from `root`, with an explicit stack of the states being
+    /// visited and their next successor, so deep graphs do not overflow
+    fn visit(&mut self, root: usize) {
+        self.open(root);
+        let mut frames: Vec<(usize, Vec<usize>, usize)> = vec![(root, self.successors[root].clone(), 0)];
+        while let Some((state, successors, position)) = frames.last_mut() {
+            let state = *state;
+            if let Some(next) = successors.get(*position).copied() {
+                *position += 1;
+                match self.indexes[next] {
+                    None => {
+                        self.open(next);
+                        frames.push((next, self.successors[next].clone(), 0));
+                    },
+                    Some(next_index) if self.on_stack[next] => {
+                        self.low_links[state] = self.low_links[state].min(next_index);
+                    },
+                    Some(_) => {},
+                }
+                continue;
+            }
+
+            frames.pop();
+            if let Some((parent, _, _)) = frames.last() {
+                self.low_links[*parent] = self.low_links[*parent].min(self.low_links[state]);
+            }
+            if Some(self.low_links[state]) == self.indexes[state] {
+                let mut component = Vec::new();
+                while let Some(member) = self.stack.pop() {
+                    self.on_stack[member] = false;
+                    component.push(member);
+                    if member == state {
+                        break;
+                    }
+                }
+                component.sort();
+                self.components.push(component);
+            }
+        }
+    }
+
+    fn open(&mut self, state: usize) {
+        self.indexes[state] = Some(self.index);
+        self.low_links[state] = self.index;
+        self.index += 1;
+        self.stack.push(state);
+        self.on_stack[state] = true;
+    }
+}
@@ -0,0 +1,313 @@
+//!
+//! ! Markov
+//! Exact analysis of a Space as a Markov chain. The probability of an arrow
+//! is its weight over the weights of its state's arrows, so arrows without a
+//! weight are equally likely. A state without an arrow of weight above 0
+//! keeps the chain where it is, it is absorbing.
+//!
+//! Results are keyed by fully qualified state id. Probabilities and expected
+//! steps are found by solving linear systems, so they are exact up to
+//! floating point rounding. The systems are solved one strongly connected
+//! component at a time, from the components that are left last, so their
+//! cost grows with the size of the largest component rather than with the
+//! number of states.
+//!
+//! ----------------------------------------------------------------------------
+//! type: computer for Elea
+//! ----------------------------------------------------------------------------
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+use crate::define::experience::representation::rust_minimal::StateId;
+use crate::define::experience::representation::rust_pragmatic::Space;
+use crate::plan::graph::{GraphError, StateGraph};
+
+/// Probability of each state
+pub type Distribution = BTreeMap<StateId, f64>;
+
+//------------------------------------------------------------------------------
+// MARKOV
+
+/// Markov Chain
+///
+/// The transition probabilities of a Space
+pub struct MarkovChain {
+    graph: StateGraph,
+    /// Probability of each edge of each state
+    probabilities: Vec<Vec<f64>>,
+    /// Strongly connected components, in reverse topological order
+    components: Vec<Vec<usize>>,
+}
+
+/// A set of states the chain never leaves once in it, and how often it is
+/// in each of them in the long run
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct RecurrentClass {
+    pub states: Vec<StateId>,
+    pub stationary: Distribution,
+}
+
+impl MarkovChain {
+
+    pub fn new(space: &Space) -> Result<Self, GraphError> {
+        let graph = StateGraph::new(space)?;
+        let probabilities = (0..graph.len()).map(|state| graph.probabilities(state)).collect();
+        let components = graph.components();
+        return Ok(Self { graph, probabilities, components });
+    }
+
+    pub fn graph(&self) -> &StateGraph {
+        return &self.graph;
+    }
+
+    /// The recurrent classes, each with its stationary distribution. An
+    /// absorbing state is a class of its own.
+    pub fn recurrent_classes(&self) -> Vec<RecurrentClass> {
+        let mut component_of = vec![0; self.graph.len()];
+        let components = &self.components;
+        for (index, component) in components.iter().enumerate() {
+            for state in component {
+                component_of[*state] = index;
+            }
+        }
+
+        let mut classes: Vec<RecurrentClass> = components.iter()
+            .enumerate()
+            .filter(|(index, component)| {
+                component.iter().all(|state| self.graph.successors(*state).all(|next| component_of[next] == *index))
+            })
+            .map(|(_, component)| RecurrentClass {
+                states: component.iter().map(|state| self.graph.states[*state].clone()).collect(),
+                stationary: self.stationary(component),
+            })
+            .collect();
+        classes.sort_by(|c1, c2| c1.states.cmp(&c2.states));
+        return classes;
+    }
+
+    /// Probability, from each state, of ever reaching one of `targets`
+    pub fn hitting_probabilities(&self, targets: &[&str]) -> Result<Distribution, GraphError> {
+        let is_target = self.targets(targets)?;
+        let probabilities = self.hitting(&is_target);
+        return Ok(self.by_state(&probabilities));
+    }
+
+    /// Probability, from each state, of ending in each absorbing state.
+    /// Only probabilities above 0 are kept.
+    pub fn absorption_probabilities(&self) -> BTreeMap<StateId, Distribution> {
+        let n = self.graph.len();
+        let absorbing: Vec<usize> = (0..n).filter(|state| self.graph.is_absorbing(*state)).collect();
+        let mut column_of = vec![None; n];
+        for (column, state) in absorbing.iter().enumerate() {
+            column_of[*state] = Some(column);
+        }
+        let reaching = self.graph.can_reach(&absorbing, &vec![false; n]);
+        let is_unknown: Vec<bool> = (0..n).map(|s| reaching[s] && column_of[s].is_none()).collect();
+        let is_absorbing: Vec<bool> = column_of.iter().map(Option::is_some).collect();
+        // a(s, t) = sum p(s, s') a(s', t), with a(t, t) = 1, solved for every
+        // absorbing t at once
+        let solution = self.solve_over(&is_unknown, absorbing.len(), |state| {
+            let mut constant = vec![0.0; absorbing.len()];
+            for (edge, p) in self.graph.edges[state].iter().zip(&self.probabilities[state]) {
+                if let Some(column) = column_of[edge.to] {
+                    constant[column] += p;
+                }
+            }
+            return constant;
+        }, &is_absorbing);
+
+        let mut absorption: BTreeMap<StateId, Distribution> = BTreeMap::new();
+        for state in 0..n {
+            let distribution: Distribution = match column_of[state] {
+                Some(_) => BTreeMap::from([(self.graph.states[state].clone(), 1.0)]),
+                None => absorbing.iter()
+                    .zip(&solution[state])
+                    .filter(|(_, probability)| **probability > 0.0)
+                    .map(|(target, probability)| (self.graph.states[*target].clone(), *probability))
+                    .collect(),
+            };
+            if !distribution.is_empty() {
+                absorption.insert(self.graph.states[state].clone(), distribution);
+            }
+        }
+        return absorption;
+    }
+
+    /// Expected number of arrows taken, from each state, until one of
+    /// `targets` is reached. `None` where a target may never be reached.
+    pub fn expected_steps(&self, targets: &[&str]) -> Result<BTreeMap<StateId, Option<f64>>, GraphError> {
+        let is_target = self.targets(targets)?;
+        let n = self.graph.len();
+
+        // states that may never reach a target, and those that may get to one
+        // of them first
+        let target_indexes: Vec<usize> = (0..n).filter(|s| is_target[*s]).collect();
+        let reaching = self.graph.can_reach(&target_indexes, &vec![false; n]);
+        let stuck: Vec<usize> = (0..n).filter(|s| !reaching[*s]).collect();
+        let may_get_stuck = self.graph.can_reach(&stuck, &is_target);
+
+        let is_unknown: Vec<bool> = (0..n).map(|s| !is_target[s] && !may_get_stuck[s]).collect();
+        // k(s) = 1 + sum p(s, s') k(s'), with k = 0 at targets
+        let steps = self.solve_over(&is_unknown, 1, |_| vec![1.0], &is_target);
+
+        let mut expected = BTreeMap::new();
+        for state in 0..n {
+            let value = if is_target[state] {
+                Some(0.0)
+            } else if may_get_stuck[state] {
+                None
+            } else {
+                Some(steps[state][0])
+            };
+            expected.insert(self.graph.states[state].clone(), value);
+        }
+        return Ok(expected);
+    }
+
+    //--------------------------------------------------------------------------
+    // MARKOV / Helpers
+
+    fn targets(&self, targets: &[&str]) -> Result<Vec<bool>, GraphError> {
+        let mut is_target = vec![false; self.graph.len()];
+        for target in targets {
+            is_target[self.graph.index_of(target)?] = true;
+        }
+        return Ok(is_target);
+    }
+
+    fn hitting(&self, is_target: &[bool]) -> Vec<f64> {
+        let n = self.graph.len();
+        let target_indexes: Vec<usize> = (0..n).filter(|s| is_target[*s]).collect();
+        let reaching = self.graph.can_reach(&target_indexes, &vec![false; n]);
+        let is_unknown: Vec<bool> = (0..n).map(|s| reaching[s] && !is_target[s]).collect();
+        // h(s) = sum p(s, s') h(s'), with h = 1 at targets and 0 where no
+        // target can be reached
+        let solution = self.solve_over(&is_unknown, 1, |state| {
+            return vec![self.graph.edges[state].iter()
+                .zip(&self.probabilities[state])
+                .filter(|(edge, _)| is_target[edge.to])
+                .map(|(_, p)| p)
+                .sum()];
+        }, is_target);
+        return (0..n).map(|state| if is_target[state] { 1.0 } else { solution[state][0] }).collect();
+    }
+
+    /// Solve x(s) = constant(s) + sum p(s, s') x(s') for the unknown states,
+    /// for `columns` right hand sides at once. Other states count as 0,
+    /// except `known` ones which are left out of the sum as their part is in
+    /// `constant`.
+    ///
+    /// Components are solved in reverse topological order, so the values of
+    /// the states a component leads to are known by the time it is solved,
+    /// and only the unknowns of one component are solved together.
+    fn solve_over<F>(&self, is_unknown: &[bool], columns: usize, constant: F, known: &[bool]) -> Vec<Vec<f64>>
+    where
+        F: Fn(usize) -> Vec<f64>
+    {
+        let mut values = vec![vec![0.0; columns]; self.graph.len()];
+        let mut position = vec![None; self.graph.len()];
+        for component in &self.components {
+            let unknowns: Vec<usize> = component.iter().copied().filter(|s| is_unknown[*s]).collect();
+            if unknowns.is_empty() {
+                continue;
+            }
+            for (i, state) in unknowns.iter().enumerate() {
+                position[*state] = Some(i);
+            }
+            let m = unknowns.len();
+            let mut a = vec![vec![0.0; m]; m];
+            let mut b = Vec::with_capacity(m);
+            for (i, state) in unknowns.iter().enumerate() {
+                a[i][i] += 1.0;
+                let mut row = constant(*state);
+                for (edge, p) in self.graph.edges[*state].iter().zip(&self.probabilities[*state]) {
+                    if known[edge.to] || !is_unknown[edge.to] {
+                        continue;
+                    }
+                    match position[edge.to] {
+                        Some(j) => a[i][j] -= p,
+                        // a component solved before
+                        None => {
+                            for (value, next_value) in row.iter_mut().zip(&values[edge.to]) {
+                                *value += p * next_value;
+                            }
+                        },
+                    }
+                }
+                b.push(row);
+            }
+            for (state, solution) in unknowns.iter().zip(solve(a, b)) {
+                position[*state] = None;
+                values[*state] = solution;
+            }
+        }
+        return values;
+    }
+
+    /// Stationary distribution of a recurrent class: pi = pi P, summing to 1
+    fn stationary(&self, class: &[usize]) -> Distribution {
+        let m = class.len();
+        let position = |state: usize| class.iter().position(|s| *s == state);
+        // rows of (P^T - I), the last replaced by the normalization
+        let mut a = vec![vec![0.0; m]; m];
+        let mut b = vec![0.0; m];
+        for (j, state) in class.iter().enumerate() {
+            a[j][j] -= 1.0;
+            if self.graph.is_absorbing(*state) {
+                a[j][j] += 1.0;
+            }
+            for (edge, p) in self.graph.edges[*state].iter().zip(&self.probabilities[*state]) {
+                if let Some(i) = position(edge.to) {
+                    a[i][j] += p;
+                }
+            }
+        }
+        a[m - 1] = vec![1.0; m];
+        b[m - 1] = 1.0;
+        let solution = solve(a, b.into_iter().map(|value| vec![value]).collect());
+        return class.iter().zip(solution).map(|(state, p)| (self.graph.states[*state].clone(), p[0])).collect();
+    }
+
+    fn by_state(&self, values: &[f64]) -> Distribution {
+        return self.graph.states.iter().cloned().zip(values.iter().copied()).collect();
+    }
+}
+
+/// Solve `a x = b` by Gaussian elimination with partial pivoting, for every
+/// column of `b` at once. The systems built here are never singular.
+fn solve(mut a: Vec<Vec<f64>>, mut b: Vec<Vec<f64>>) -> Vec<Vec<f64>> {
+    let n = b.len();
+    for column in 0..n {
+        let pivot = (column..n)
+            .max_by(|r1, r2| a[*r1][column].abs().total_cmp(&a[*r2][column].abs()))
+            .unwrap_or(column);
+        a.swap(column, pivot);
+        b.swap(column, pivot);
+        for row in column + 1..n {
+            let factor = a[row][column] / a[column][column];
+            if factor == 0.0 {
+                continue;
+            }
+            let pivot_row = a[column].clone();
+            for (value, pivot_value) in a[row].iter_mut().zip(&pivot_row).skip(column) {
+                *value -= factor * pivot_value;
+            }
+            let pivot_b = b[column].clone();
+            for (value, pivot_value) in b[row].iter_mut().zip(&pivot_b) {
+                *value -= factor * pivot_value;
+            }
+        }
+    }
+    let mut x: Vec<Vec<f64>> = vec![Vec::new(); n];
+    for row in (0..n).rev() {
+        x[row] = b[row].iter()
+            .enumerate()
+            .map(|(k, value)| {
+                let sum: f64 = (row + 1..n).map(|j| a[row][j] * x[j][k]).sum();
+                return (value - sum) / a[row][row];
+            })
+            .collect();
+    }
+    return x;
+}
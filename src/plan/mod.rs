@@ -0,0 +1,6 @@
+pub mod check;
+pub mod equivalence;
+pub mod graph;
+pub mod markov;
+pub mod simulate;
+pub mod temporal;
@@ -0,0 +1,198 @@
+//!
+//! ! Simulate
+//! Seeded random walks over a Space. Each step takes one arrow of the current
+//! state, chosen in proportion to the arrows' weights. A walk ends in an
+//! absorbing state, one it cannot leave or one of the `stop_at` states, or
+//! after `max_steps` arrows. The same seed gives the same walks.
+//!
+//! ----------------------------------------------------------------------------
+//! type: computer for Elea
+//! ----------------------------------------------------------------------------
+
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashSet};
+use std::fmt;
+
+use crate::define::experience::representation::rust_minimal::StateId;
+use crate::define::experience::representation::rust_pragmatic::Space;
+use crate::plan::graph::{GraphError, StateGraph};
+
+//------------------------------------------------------------------------------
+// SIMULATE
+
+#[derive(Clone, Debug)]
+pub struct SimulationOptions {
+    pub seed: u64,
+    pub walks: usize,
+    pub max_steps: usize,
+    /// Fully qualified ids of states that end a walk
+    pub stop_at: Vec<String>,
+}
+
+impl Default for SimulationOptions {
+    fn default() -> Self {
+        return Self { seed: 0, walks: 1000, max_steps: 1000, stop_at: Vec::new() };
+    }
+}
+
+/// Statistics over every walk of a simulation. State ids are fully
+/// qualified.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct Simulation {
+    pub start: StateId,
+    pub walks: usize,
+    /// Visits of each visited state over all walks, the start included
+    pub visits: BTreeMap<StateId, u64>,
+    /// Number of walks of each length, in arrows taken
+    pub path_lengths: BTreeMap<usize, u64>,
+    /// Walks ending in each absorbing state
+    pub absorptions: BTreeMap<StateId, Absorption>,
+    /// Walks cut off at `max_steps`
+    pub truncated: u64,
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct Absorption {
+    pub walks: u64,
+    pub mean_steps: f64,
+}
+
+impl Simulation {
+
+    pub fn mean_path_length(&self) -> f64 {
+        if self.walks == 0 {
+            return 0.0;
+        }
+        let total: u64 = self.path_lengths.iter().map(|(length, walks)| *length as u64 * walks).sum();
+        return total as f64 / self.walks as f64;
+    }
+
+    /// Share of the walks ending in a state
+    pub fn absorption_rate(&self, state: &str) -> f64 {
+        return match self.absorptions.get(&StateId(state.to_string())) {
+            Some(absorption) if self.walks > 0 => absorption.walks as f64 / self.walks as f64,
+            _ => 0.0,
+        };
+    }
+}
+
+impl fmt::Display for Simulation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "{} walks from {}, mean length {:.2}", self.walks, self.start.0, self.mean_path_length())?;
+        writeln!(f, "visits")?;
+        for (state, visits) in &self.visits {
+            writeln!(f, "  {} {}", state.0, visits)?;
+        }
+        writeln!(f, "absorptions")?;
+        for (state, absorption) in &self.absorptions {
+            writeln!(f, "  {} {:.3} after {:.2} steps", state.0, self.absorption_rate(&state.0), absorption.mean_steps)?;
+        }
+        if self.truncated > 0 {
+            writeln!(f, "truncated {}", self.truncated)?;
+        }
+        return Ok(());
+    }
+}
+
+/// Walk a Space from `start`, a fully qualified state id
+pub fn simulate(space: &Space, start: &str, options: &SimulationOptions) -> Result<Simulation, GraphError> {
+    let graph = StateGraph::new(space)?;
+    let start_index = graph.index_of(start)?;
+    let mut stop_at = HashSet::new();
+    for state in &options.stop_at {
+        stop_at.insert(graph.index_of(state)?);
+    }
+
+    let mut rng = SplitMix64::new(options.seed);
+    let mut visits = vec![0u64; graph.len()];
+    let mut path_lengths: BTreeMap<usize, u64> = BTreeMap::new();
+    // walks and total steps of each absorbing state
+    let mut absorptions: BTreeMap<usize, (u64, u64)> = BTreeMap::new();
+    let mut truncated = 0;
+
+    for _ in 0..options.walks {
+        let mut state = start_index;
+        let mut steps = 0;
+        visits[state] += 1;
+        loop {
+            if stop_at.contains(&state) || graph.is_absorbing(state) {
+                let absorption = absorptions.entry(state).or_insert((0, 0));
+                absorption.0 += 1;
+                absorption.1 += steps as u64;
+                break;
+            }
+            if steps == options.max_steps {
+                truncated += 1;
+                break;
+            }
+            state = graph.edges[state][choose(&graph.probabilities(state), rng.next_f64())].to;
+            steps += 1;
+            visits[state] += 1;
+        }
+        *path_lengths.entry(steps).or_insert(0) += 1;
+    }
+
+    return Ok(Simulation {
+        start: graph.states[start_index].clone(),
+        walks: options.walks,
+        visits: visits.iter()
+            .enumerate()
+            .filter(|(_, visits)| **visits > 0)
+            .map(|(index, visits)| (graph.states[index].clone(), *visits))
+            .collect(),
+        path_lengths,
+        absorptions: absorptions.into_iter()
+            .map(|(index, (walks, steps))| {
+                (graph.states[index].clone(), Absorption { walks, mean_steps: steps as f64 / walks as f64 })
+            })
+            .collect(),
+        truncated,
+    });
+}
+
+//------------------------------------------------------------------------------
+// SIMULATE / Random
+
+/// Index picked by a uniform sample in [0, 1) from probabilities summing to
+/// 1, never one of probability 0
+fn choose(probabilities: &[f64], sample: f64) -> usize {
+    let mut cumulative = 0.0;
+    let mut last = 0;
+    for (index, probability) in probabilities.iter().enumerate() {
+        if *probability == 0.0 {
+            continue;
+        }
+        cumulative += probability;
+        last = index;
+        if sample < cumulative {
+            return index;
+        }
+    }
+    // rounding left the sample above the total
+    return last;
+}
+
+/// SplitMix64, a small generator whose output only depends on its seed
+struct SplitMix64 {
+    state: u64,
+}
+
+impl SplitMix64 {
+
+    fn new(seed: u64) -> Self {
+        return Self { state: seed };
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        return z ^ (z >> 31);
+    }
+
+    /// Uniform in [0, 1)
+    fn next_f64(&mut self) -> f64 {
+        return (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64;
+    }
+}
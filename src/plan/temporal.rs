@@ -0,0 +1,344 @@
+//!
+//! ! Temporal Logic
+//! Properties of the paths through a Space, written in CTL or LTL.
+//!
+//! Atoms are fully qualified state ids, or prefixes ending in `*` such as
+//! `broken/*`, and hold in the states they name. `true` and `false` hold
+//! everywhere and nowhere. Formulas combine them with `!`, `&`, `|` and `->`,
+//! and with temporal operators:
+//!
+//! - CTL: `AX`, `EX`, `AF`, `EF`, `AG`, `EG`, `A[f U g]` and `E[f U g]`
+//! - LTL: `X`, `F`, `G` and `f U g`, over every path
+//!
+//! ```text
+//! AG (broken/* -> EF default/on)
+//! G (default/off -> X !pattern/*)
+//! ```
+//!
+//! A formula is CTL or LTL, not both. Formulas of atoms alone are both.
+//!
+//! ----------------------------------------------------------------------------
+//! type: computer for Elea
+//! ----------------------------------------------------------------------------
+
+use std::fmt;
+use std::str::FromStr;
+
+//------------------------------------------------------------------------------
+// FORMULA
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Formula {
+    True,
+    False,
+    /// A fully qualified state id, or a prefix ending in `*`
+    State(String),
+    Not(Box<Formula>),
+    And(Box<Formula>, Box<Formula>),
+    Or(Box<Formula>, Box<Formula>),
+    Implies(Box<Formula>, Box<Formula>),
+    // CTL
+    AX(Box<Formula>),
+    EX(Box<Formula>),
+    AF(Box<Formula>),
+    EF(Box<Formula>),
+    AG(Box<Formula>),
+    EG(Box<Formula>),
+    AU(Box<Formula>, Box<Formula>),
+    EU(Box<Formula>, Box<Formula>),
+    // LTL
+    X(Box<Formula>),
+    F(Box<Formula>),
+    G(Box<Formula>),
+    U(Box<Formula>, Box<Formula>),
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Logic {
+    Ctl,
+    Ltl,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum FormulaError {
+    /// Byte offset in the formula, and what was expected there
+    Parse { position: usize, message: String },
+    /// CTL and LTL operators in one formula
+    MixedLogic,
+}
+
+impl fmt::Display for FormulaError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            FormulaError::Parse { position, message } => write!(f, "at {}: {}", position, message),
+            FormulaError::MixedLogic => write!(f, "formula mixes CTL and LTL operators"),
+        }
+    }
+}
+
+impl Formula {
+
+    pub fn parse(formula: &str) -> Result<Self, FormulaError> {
+        let mut parser = Parser { tokens: tokenize(formula)?, next: 0, end: formula.len(), in_until: false };
+        let parsed = parser.implies()?;
+        if let Some((position, token)) = parser.tokens.get(parser.next) {
+            return Err(FormulaError::Parse {
+                position: *position,
+                message: format!("unexpected [{}]", token),
+            });
+        }
+        parsed.logic()?;
+        return Ok(parsed);
+    }
+
+    /// The logic of the formula's temporal operators, CTL when it has none
+    pub fn logic(&self) -> Result<Logic, FormulaError> {
+        let (ctl, ltl) = self.operator_kinds();
+        return match (ctl, ltl) {
+            (true, true) => Err(FormulaError::MixedLogic),
+            (false, true) => Ok(Logic::Ltl),
+            _ => Ok(Logic::Ctl),
+        };
+    }
+
+    /// Every state pattern of the formula
+    pub fn patterns(&self) -> Vec<&str> {
+        let mut patterns = Vec::new();
+        self.visit(&mut |formula| {
+            if let Formula::State(pattern) = formula {
+                patterns.push(pattern.as_str());
+            }
+        });
+        return patterns;
+    }
+
+    /// Whether the pattern of a `State` atom names a state
+    pub fn matches(pattern: &str, state_id: &str) -> bool {
+        return match pattern.strip_suffix('*') {
+            Some(prefix) => state_id.starts_with(prefix),
+            None => pattern == state_id,
+        };
+    }
+
+    /// Whether the formula has CTL and LTL operators
+    fn operator_kinds(&self) -> (bool, bool) {
+        let (mut ctl, mut ltl) = (false, false);
+        self.visit(&mut |formula| match formula {
+            Formula::AX(_) | Formula::EX(_) | Formula::AF(_) | Formula::EF(_)
+            | Formula::AG(_) | Formula::EG(_) | Formula::AU(..) | Formula::EU(..) => ctl = true,
+            Formula::X(_) | Formula::F(_) | Formula::G(_) | Formula::U(..) => ltl = true,
+            _ => {},
+        });
+        return (ctl, ltl);
+    }
+
+    fn visit<'a, V: FnMut(&'a Formula)>(&'a self, visitor: &mut V) {
+        visitor(self);
+        match self {
+            Formula::True | Formula::False | Formula::State(_) => {},
+            Formula::Not(f) | Formula::AX(f) | Formula::EX(f) | Formula::AF(f) | Formula::EF(f)
+            | Formula::AG(f) | Formula::EG(f) | Formula::X(f) | Formula::F(f) | Formula::G(f) => f.visit(visitor),
+            Formula::And(f1, f2) | Formula::Or(f1, f2) | Formula::Implies(f1, f2)
+            | Formula::AU(f1, f2) | Formula::EU(f1, f2) | Formula::U(f1, f2) => {
+                f1.visit(visitor);
+                f2.visit(visitor);
+            },
+        }
+    }
+}
+
+impl FromStr for Formula {
+    type Err = FormulaError;
+
+    fn from_str(formula: &str) -> Result<Self, Self::Err> {
+        return Formula::parse(formula);
+    }
+}
+
+impl fmt::Display for Formula {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Formula::True => write!(f, "true"),
+            Formula::False => write!(f, "false"),
+            Formula::State(pattern) => write!(f, "{}", pattern),
+            Formula::Not(f1) => write!(f, "!{}", f1),
+            Formula::And(f1, f2) => write!(f, "({} & {})", f1, f2),
+            Formula::Or(f1, f2) => write!(f, "({} | {})", f1, f2),
+            Formula::Implies(f1, f2) => write!(f, "({} -> {})", f1, f2),
+            Formula::AX(f1) => write!(f, "AX {}", f1),
+            Formula::EX(f1) => write!(f, "EX {}", f1),
+            Formula::AF(f1) => write!(f, "AF {}", f1),
+            Formula::EF(f1) => write!(f, "EF {}", f1),
+            Formula::AG(f1) => write!(f, "AG {}", f1),
+            Formula::EG(f1) => write!(f, "EG {}", f1),
+            Formula::AU(f1, f2) => write!(f, "A[{} U {}]", f1, f2),
+            Formula::EU(f1, f2) => write!(f, "E[{} U {}]", f1, f2),
+            Formula::X(f1) => write!(f, "X {}", f1),
+            Formula::F(f1) => write!(f, "F {}", f1),
+            Formula::G(f1) => write!(f, "G {}", f1),
+            Formula::U(f1, f2) => write!(f, "({} U {})", f1, f2),
+        }
+    }
+}
+
+//------------------------------------------------------------------------------
+// FORMULA / Parser
+
+/// Tokens with their byte offset. Words are operators, `true`, `false` or
+/// state patterns; state patterns always have a `/`.
+fn tokenize(formula: &str) -> Result<Vec<(usize, String)>, FormulaError> {
+    let mut tokens = Vec::new();
+    let mut chars = formula.char_indices().peekable();
+    while let Some((position, c)) = chars.next() {
+        match c {
+            _ if c.is_whitespace() => {},
+            '!' | '&' | '|' | '(' | ')' | '[' | ']' => tokens.push((position, c.to_string())),
+            '-' if chars.peek().is_some_and(|(_, next)| *next == '>') => {
+                chars.next();
+                tokens.push((position, "->".to_string()));
+            },
+            _ if is_word_char(c) => {
+                let mut word = c.to_string();
+                while let Some((_, next)) = chars.peek() {
+                    // `->` ends a word
+                    if !is_word_char(*next) || (*next == '-' && formula[position + word.len()..].starts_with("->")) {
+                        break;
+                    }
+                    word.push(*next);
+                    chars.next();
+                }
+                tokens.push((position, word));
+            },
+            _ => {
+                return Err(FormulaError::Parse { position, message: format!("unexpected [{}]", c) });
+            },
+        }
+    }
+    return Ok(tokens);
+}
+
+fn is_word_char(c: char) -> bool {
+    return c.is_alphanumeric() || matches!(c, '_' | '-' | '.' | '/' | '*');
+}
+
+struct Parser {
+    tokens: Vec<(usize, String)>,
+    next: usize,
+    /// Offset reported for errors at the end of the formula
+    end: usize,
+    /// Whether a `U` ends the formula, as on the left side of `A[f U g]`
+    in_until: bool,
+}
+
+impl Parser {
+
+    fn peek(&self) -> Option<&str> {
+        return self.tokens.get(self.next).map(|(_, token)| token.as_str());
+    }
+
+    fn error(&self, message: &str) -> FormulaError {
+        let position = self.tokens.get(self.next).map(|(position, _)| *position).unwrap_or(self.end);
+        return FormulaError::Parse { position, message: message.to_string() };
+    }
+
+    fn expect(&mut self, token: &str) -> Result<(), FormulaError> {
+        if self.peek() != Some(token) {
+            return Err(self.error(&format!("expected [{}]", token)));
+        }
+        self.next += 1;
+        return Ok(());
+    }
+
+    /// `->` binds loosest and to the right
+    fn implies(&mut self) -> Result<Formula, FormulaError> {
+        let left = self.or()?;
+        if self.peek() == Some("->") {
+            self.next += 1;
+            let right = self.implies()?;
+            return Ok(Formula::Implies(Box::new(left), Box::new(right)));
+        }
+        return Ok(left);
+    }
+
+    fn or(&mut self) -> Result<Formula, FormulaError> {
+        let mut left = self.and()?;
+        while self.peek() == Some("|") {
+            self.next += 1;
+            left = Formula::Or(Box::new(left), Box::new(self.and()?));
+        }
+        return Ok(left);
+    }
+
+    fn and(&mut self) -> Result<Formula, FormulaError> {
+        let mut left = self.until()?;
+        while self.peek() == Some("&") {
+            self.next += 1;
+            left = Formula::And(Box::new(left), Box::new(self.until()?));
+        }
+        return Ok(left);
+    }
+
+    /// LTL `U` binds to the right
+    fn until(&mut self) -> Result<Formula, FormulaError> {
+        let left = self.unary()?;
+        if self.peek() == Some("U") && !self.in_until {
+            self.next += 1;
+            let right = self.until()?;
+            return Ok(Formula::U(Box::new(left), Box::new(right)));
+        }
+        return Ok(left);
+    }
+
+    fn unary(&mut self) -> Result<Formula, FormulaError> {
+        let token = match self.peek() {
+            Some(token) => token.to_string(),
+            None => return Err(self.error("expected a formula")),
+        };
+        self.next += 1;
+        let unary = |parser: &mut Self, op: fn(Box<Formula>) -> Formula| -> Result<Formula, FormulaError> {
+            return Ok(op(Box::new(parser.unary()?)));
+        };
+        return match token.as_str() {
+            "!" => unary(self, Formula::Not),
+            "AX" => unary(self, Formula::AX),
+            "EX" => unary(self, Formula::EX),
+            "AF" => unary(self, Formula::AF),
+            "EF" => unary(self, Formula::EF),
+            "AG" => unary(self, Formula::AG),
+            "EG" => unary(self, Formula::EG),
+            "X" => unary(self, Formula::X),
+            "F" => unary(self, Formula::F),
+            "G" => unary(self, Formula::G),
+            "A" | "E" => {
+                self.expect("[")?;
+                let in_until = std::mem::replace(&mut self.in_until, true);
+                let left = self.implies();
+                self.in_until = false;
+                let left = left?;
+                self.expect("U")?;
+                let right = self.implies()?;
+                self.expect("]")?;
+                self.in_until = in_until;
+                if token == "A" {
+                    Ok(Formula::AU(Box::new(left), Box::new(right)))
+                } else {
+                    Ok(Formula::EU(Box::new(left), Box::new(right)))
+                }
+            },
+            "(" => {
+                let in_until = std::mem::replace(&mut self.in_until, false);
+                let formula = self.implies()?;
+                self.expect(")")?;
+                self.in_until = in_until;
+                Ok(formula)
+            },
+            "true" => Ok(Formula::True),
+            "false" => Ok(Formula::False),
+            _ if token.contains('/') => Ok(Formula::State(token)),
+            _ => {
+                self.next -= 1;
+                Err(self.error(&format!("unknown operator or state [{}]", token)))
+            },
+        };
+    }
+}
@@ -0,0 +1,242 @@
+//!
+//! ! Journal
+//! An append-only record of the arrows a runtime took, one JSON entry per
+//! line, and the replay of such a record to rebuild the runtime.
+//!
+//! ```json
+//! {"timestamp":1760868000000,"agent":"technician","arrow":"fix","from":"broken/check","to":"broken/fix"}
+//! ```
+//!
+//! Timestamps are milliseconds by the clock of the runtime, since the Unix
+//! epoch for the system clock; timed arrows taken by `tick` are stamped with
+//! the time they came due. An entry is written before the runtime moves, so
+//! that the journal never misses an arrow taken.
+//!
+//! Replaying takes the arrows of the entries from a runtime, applying
+//! guards, effects and timers as taking them did: the runtime reads time
+//! from a virtual clock set to the timestamp of each entry, then gets its
+//! own clock back, still in the state it entered last. The state the
+//! journal starts in counts as entered long before. Entries that no longer
+//! hold for the Space, or that do not start where the runtime is, are
+//! reported and skipped.
+//!
+//! ----------------------------------------------------------------------------
+//! type: computer for Elea
+//! ----------------------------------------------------------------------------
+
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::define::experience::representation::qualified_id::QualifiedStateId;
+use crate::define::experience::representation::rust_minimal::{ArrowId, StateId};
+use crate::unite::runtime::{Runtime, RuntimeError, Transition};
+use crate::unite::timer::VirtualClock;
+
+//------------------------------------------------------------------------------
+// JOURNAL
+
+/// An arrow taken. State ids are fully qualified.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct Entry {
+    pub timestamp: u64,
+    /// Who took the arrow
+    pub agent: String,
+    pub arrow: ArrowId,
+    pub from: StateId,
+    pub to: StateId,
+}
+
+/// A journal file open for appending
+pub struct Journal {
+    path: PathBuf,
+    file: File,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum JournalError {
+    Io { file_path: String, error: String },
+    /// A line that is not an entry, numbered from 1
+    Parse { file_path: String, line: usize, error: String },
+    Runtime(RuntimeError),
+}
+
+impl fmt::Display for JournalError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            JournalError::Io { file_path, error } => write!(f, "{}: {}", file_path, error),
+            JournalError::Parse { file_path, line, error } => write!(f, "{}:{}: {}", file_path, line, error),
+            JournalError::Runtime(err) => write!(f, "{}", err),
+        }
+    }
+}
+
+impl Entry {
+
+    /// An entry for a transition taken at clock time `time`
+    pub fn at(time: Duration, agent: &str, transition: &Transition) -> Self {
+        return Self {
+            timestamp: time.as_millis() as u64,
+            agent: agent.to_string(),
+            arrow: transition.arrow.clone(),
+            from: transition.from.clone(),
+            to: transition.to.clone(),
+        };
+    }
+}
+
+impl Journal {
+
+    /// Open a journal, creating it if it does not exist. Entries are added
+    /// after those already there.
+    pub fn open(path: &Path) -> Result<Self, JournalError> {
+        let file = OpenOptions::new().create(true).append(true).open(path).map_err(|err| io_error(path, err))?;
+        return Ok(Self { path: path.to_path_buf(), file });
+    }
+
+    pub fn path(&self) -> &Path {
+        return &self.path;
+    }
+
+    /// Write an entry, on disk once this returns
+    pub fn append(&mut self, entry: &Entry) -> Result<(), JournalError> {
+        let mut line = serde_json::to_string(entry).map_err(|err| io_error(&self.path, err))?;
+        line.push('\n');
+        self.file.write_all(line.as_bytes()).map_err(|err| io_error(&self.path, err))?;
+        return self.file.sync_data().map_err(|err| io_error(&self.path, err));
+    }
+
+    /// Record an arrow of the runtime, then take it. Nothing is recorded
+    /// when the runtime cannot take the arrow, and the runtime does not move
+    /// when the entry cannot be written.
+    pub fn fire(&mut self, runtime: &mut Runtime, agent: &str, arrow: &str) -> Result<Entry, JournalError> {
+        let now = runtime.clock().now();
+        let pending = runtime.prepare(arrow, now).map_err(JournalError::Runtime)?;
+        let entry = Entry::at(now, agent, &pending.transition);
+        self.append(&entry)?;
+        runtime.commit(pending);
+        return Ok(entry);
+    }
+
+    /// Record and take the timed arrows of the runtime that came due, as
+    /// `Runtime::tick` does, each stamped with the time it came due. Stops
+    /// at the first error; the arrows recorded before it stay taken.
+    pub fn tick(&mut self, runtime: &mut Runtime, agent: &str) -> Result<Vec<Entry>, JournalError> {
+        let now = runtime.clock().now();
+        let mut entries = Vec::new();
+        while let Some((deadline, arrow)) = runtime.next_due(now).map_err(JournalError::Runtime)? {
+            let pending = runtime.prepare(&arrow, deadline).map_err(JournalError::Runtime)?;
+            let entry = Entry::at(deadline, agent, &pending.transition);
+            self.append(&entry)?;
+            runtime.commit(pending);
+            entries.push(entry);
+        }
+        return Ok(entries);
+    }
+
+    /// Every entry of a journal file, in order. Blank lines are skipped.
+    pub fn read(path: &Path) -> Result<Vec<Entry>, JournalError> {
+        let file = File::open(path).map_err(|err| io_error(path, err))?;
+        let mut entries = Vec::new();
+        for (index, line) in BufReader::new(file).lines().enumerate() {
+            let line = line.map_err(|err| io_error(path, err))?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let entry = serde_json::from_str(&line).map_err(|err| JournalError::Parse {
+                file_path: path.display().to_string(),
+                line: index + 1,
+                error: err.to_string(),
+            })?;
+            entries.push(entry);
+        }
+        return Ok(entries);
+    }
+}
+
+fn io_error(path: &Path, err: impl fmt::Display) -> JournalError {
+    return JournalError::Io { file_path: path.display().to_string(), error: err.to_string() };
+}
+
+//------------------------------------------------------------------------------
+// JOURNAL / Replay
+
+/// A rebuilt runtime, and the entries it could not take
+pub struct Replay {
+    pub runtime: Runtime,
+    pub invalid: Vec<InvalidEntry>,
+}
+
+/// `index` is the position of the entry in the journal
+#[derive(Clone, Debug, PartialEq)]
+pub struct InvalidEntry {
+    pub index: usize,
+    pub entry: Entry,
+    pub problem: ReplayProblem,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum ReplayProblem {
+    /// The entry does not start at the state the runtime is in
+    Discontinuous { current: StateId },
+    /// The arrow now leads to another state
+    ChangedArrow { found: StateId },
+    /// The runtime cannot take the arrow
+    Runtime(RuntimeError),
+}
+
+impl fmt::Display for InvalidEntry {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "entry {} ({} -{}-> {}): ", self.index, self.entry.from.0, self.entry.arrow.0, self.entry.to.0)?;
+        match &self.problem {
+            ReplayProblem::Discontinuous { current } => write!(f, "runtime is in [{}]", current.0),
+            ReplayProblem::ChangedArrow { found } => write!(f, "arrow now leads to [{}]", found.0),
+            ReplayProblem::Runtime(err) => write!(f, "{}", err),
+        }
+    }
+}
+
+/// Take the arrows of `entries` from `runtime`, which is where the journal
+/// starts, with the context it starts with
+pub fn replay(mut runtime: Runtime, entries: &[Entry]) -> Replay {
+    let clock = VirtualClock::new();
+    let (own_clock, own_entered_at) = runtime.replace_clock(Arc::new(clock.clone()), Duration::ZERO);
+    let taken = runtime.history().len();
+    let mut invalid = Vec::new();
+    for (index, entry) in entries.iter().enumerate() {
+        clock.set(Duration::from_millis(entry.timestamp));
+        let mut invalid_entry = |problem| invalid.push(InvalidEntry { index, entry: entry.clone(), problem });
+        if canonical(&entry.from) != *runtime.current() {
+            invalid_entry(ReplayProblem::Discontinuous { current: runtime.current().clone() });
+            continue;
+        }
+        let next = match runtime.check(&entry.arrow.0) {
+            Ok(arrow) => canonical(&arrow.next),
+            Err(err) => {
+                invalid_entry(ReplayProblem::Runtime(err));
+                continue;
+            },
+        };
+        if next != canonical(&entry.to) {
+            invalid_entry(ReplayProblem::ChangedArrow { found: next });
+            continue;
+        }
+        if let Err(err) = runtime.fire(&entry.arrow.0) {
+            invalid_entry(ReplayProblem::Runtime(err));
+        }
+    }
+    // timestamps are by the runtime's own clock
+    let entered_at = if runtime.history().len() > taken { runtime.entered_at() } else { own_entered_at };
+    runtime.replace_clock(own_clock, entered_at);
+    return Replay { runtime, invalid };
+}
+
+/// The canonical form of a fully qualified id, or the id itself if it does
+/// not parse
+fn canonical(state: &StateId) -> StateId {
+    return QualifiedStateId::parse(&state.0).map(|id| id.to_state_id()).unwrap_or_else(|_| state.clone());
+}
@@ -0,0 +1,6 @@
+pub mod journal;
+pub mod observer;
+pub mod runtime;
+pub mod session;
+pub mod snapshot;
+pub mod timer;
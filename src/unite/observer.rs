@@ -0,0 +1,173 @@
+//!
+//! ! Observer
+//! Hooks for applications embedding a runtime. An observer may veto a
+//! transition, and is told when the runtime leaves a state, takes an arrow
+//! and enters a state.
+//!
+//! Observers are registered globally, for the states of one machine, or for
+//! one state. A transition runs in two phases. Once its guard and effects
+//! succeeded, and before the runtime moves, `allow` asks every observer of
+//! either end of the arrow whether it may happen; the first veto stops the
+//! asking and leaves the runtime where it was. Since the transition may
+//! still be refused after it, `allow` should have no side effects. Once the
+//! runtime moved, the transition is final and the observers are told of it:
+//! every `on_exit`, then every `on_arrow`, then every `on_enter`.
+//!
+//! Within each hook, global observers run first, then machine observers,
+//! then state observers, each in the order they were registered. `on_exit`
+//! sees the context before the arrow's effects, `on_arrow` and `on_enter`
+//! see it after.
+//!
+//! ----------------------------------------------------------------------------
+//! type: computer for Elea
+//! ----------------------------------------------------------------------------
+
+use std::fmt;
+
+use crate::define::experience::language::context::Context;
+use crate::define::experience::representation::qualified_id::QualifiedStateId;
+use crate::define::experience::representation::rust_minimal::{MachineId, StateId};
+use crate::unite::runtime::Transition;
+
+//------------------------------------------------------------------------------
+// OBSERVER
+
+/// Hooks of a transition, doing nothing by default. State ids are fully
+/// qualified.
+pub trait Observer: Send {
+
+    /// Whether the transition may happen, with the context `before` and
+    /// `after` the arrow's effects. Allows it by default.
+    fn allow(&mut self, _transition: &Transition, _before: &Context, _after: &Context) -> Result<(), Veto> {
+        return Ok(());
+    }
+
+    fn on_exit(&mut self, _state: &StateId, _context: &Context) {}
+
+    fn on_arrow(&mut self, _transition: &Transition, _context: &Context) {}
+
+    fn on_enter(&mut self, _state: &StateId, _context: &Context) {}
+}
+
+/// States an observer is told about. `allow` and `on_arrow` are called when
+/// either end of the arrow is in scope.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Scope {
+    Global,
+    Machine(MachineId),
+    /// A fully qualified state, without the states below it
+    State(StateId),
+}
+
+/// A hook refusing a transition
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Veto {
+    pub reason: String,
+}
+
+/// Identifies a registered observer, to remove it
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct ObserverId(pub usize);
+
+/// Registered observers
+#[derive(Default)]
+pub struct Observers {
+    observers: Vec<(ObserverId, Scope, Box<dyn Observer>)>,
+    next_id: usize,
+}
+
+impl Veto {
+
+    pub fn new(reason: &str) -> Self {
+        return Self { reason: reason.to_string() };
+    }
+}
+
+impl fmt::Display for Veto {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        return write!(f, "{}", self.reason);
+    }
+}
+
+impl Scope {
+
+    /// Whether a fully qualified state is in scope
+    pub fn contains(&self, state: &StateId) -> bool {
+        return match self {
+            Scope::Global => true,
+            Scope::Machine(machine) => QualifiedStateId::parse(&state.0).is_ok_and(|id| id.machine() == machine),
+            Scope::State(scope_state) => scope_state == state,
+        };
+    }
+
+    /// Order of the scope's observers within a hook
+    fn rank(&self) -> usize {
+        return match self {
+            Scope::Global => 0,
+            Scope::Machine(_) => 1,
+            Scope::State(_) => 2,
+        };
+    }
+}
+
+impl Observers {
+
+    pub fn new() -> Self {
+        return Self::default();
+    }
+
+    pub fn register(&mut self, scope: Scope, observer: Box<dyn Observer>) -> ObserverId {
+        let id = ObserverId(self.next_id);
+        self.next_id += 1;
+        self.observers.push((id, scope, observer));
+        // stable, so registration order holds within a scope rank
+        self.observers.sort_by_key(|(_, scope, _)| scope.rank());
+        return id;
+    }
+
+    /// Remove an observer, returning whether it was registered
+    pub fn remove(&mut self, id: ObserverId) -> bool {
+        let count = self.observers.len();
+        self.observers.retain(|(observer_id, _, _)| *observer_id != id);
+        return self.observers.len() != count;
+    }
+
+    pub fn len(&self) -> usize {
+        return self.observers.len();
+    }
+
+    pub fn is_empty(&self) -> bool {
+        return self.observers.is_empty();
+    }
+
+    /// Ask the observers whether a transition may happen, `before` and
+    /// `after` its effects, stopping at the first veto
+    pub fn allow(&mut self, transition: &Transition, before: &Context, after: &Context) -> Result<(), Veto> {
+        for (_, scope, observer) in &mut self.observers {
+            if scope.contains(&transition.from) || scope.contains(&transition.to) {
+                observer.allow(transition, before, after)?;
+            }
+        }
+        return Ok(());
+    }
+
+    /// Tell the observers of a transition that happened, `before` and
+    /// `after` its effects
+    pub fn notify(&mut self, transition: &Transition, before: &Context, after: &Context) {
+        for (_, scope, observer) in &mut self.observers {
+            if scope.contains(&transition.from) {
+                observer.on_exit(&transition.from, before);
+            }
+        }
+        for (_, scope, observer) in &mut self.observers {
+            if scope.contains(&transition.from) || scope.contains(&transition.to) {
+                observer.on_arrow(transition, after);
+            }
+        }
+        for (_, scope, observer) in &mut self.observers {
+            if scope.contains(&transition.to) {
+                observer.on_enter(&transition.to, after);
+            }
+        }
+    }
+}
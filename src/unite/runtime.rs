@@ -0,0 +1,421 @@
+//!
+//! ! Runtime
+//! A running instance of a Space: its current state, the context guards
+//! read, and the history of arrows taken. The Space is shared, the runtime
+//! never changes it.
+//!
+//! The context starts with the defaults of the variables of every machine,
+//! which machines declaring a variable of the same name must declare alike.
+//! Taking an arrow of the current state checks its guard against the
+//! context. A false guard denies the arrow; a guard that does not parse or
+//! evaluate is an error. Either way the runtime stays where it is. The
+//! effects of an arrow taken then assign the variables of its machine, all
+//! of them or, on an error, none. Observers registered with the runtime
+//! may then veto the transition, and are told of it once it happened, see
+//! `unite::observer`.
+//!
+//! Timed arrows can only be taken once the runtime has been in their state
+//! for their duration, by the runtime's clock, see `unite::timer`. `tick`
+//! takes the arrows that came due, as if each was taken on time. A restored
+//! runtime, or one given a new clock, keeps the time it has been in its
+//! state.
+//!
+//! ----------------------------------------------------------------------------
+//! type: computer for Elea
+//! ----------------------------------------------------------------------------
+
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::define::experience::language::context::Context;
+use crate::define::experience::language::duration::{self, DurationError};
+use crate::define::experience::language::effect::{self, Effect, EffectError};
+use crate::define::experience::language::guard::{Guard, GuardError};
+use crate::define::experience::representation::qualified_id::QualifiedStateId;
+use crate::define::experience::representation::rust_minimal::{ArrowId, MachineId, StateId};
+use crate::define::experience::representation::rust_pragmatic::{
+    Arrow, Space, SpaceError, State
+};
+use crate::unite::observer::{Observer, ObserverId, Observers, Scope};
+use crate::unite::snapshot::{Snapshot, SnapshotError, SnapshotProblem};
+use crate::unite::timer::{Clock, SystemClock};
+
+//------------------------------------------------------------------------------
+// RUNTIME
+
+pub struct Runtime {
+    space: Arc<Space>,
+    /// Fully qualified
+    current: StateId,
+    context: Context,
+    history: Vec<Transition>,
+    observers: Observers,
+    clock: Arc<dyn Clock>,
+    /// Clock time the current state was entered at
+    entered_at: Duration,
+}
+
+/// An arrow about to be taken: checked, its effects applied to a copy of
+/// the context, and allowed by the observers. Committing it cannot fail.
+pub(crate) struct Pending {
+    pub(crate) transition: Transition,
+    context: Context,
+    entered_at: Duration,
+}
+
+/// An arrow taken, between fully qualified states
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct Transition {
+    pub from: StateId,
+    pub arrow: ArrowId,
+    pub to: StateId,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum RuntimeError {
+    UnknownState(String),
+    /// Two machines declare a variable of the same name differently
+    ConflictingVariable { name: String, first: MachineId, second: MachineId },
+    UnknownArrow { state: StateId, arrow: ArrowId },
+    /// The guard of the arrow is false
+    Denied { state: StateId, arrow: ArrowId, guard: String },
+    /// The guard of the arrow does not parse, or does not evaluate to a
+    /// boolean in the context
+    Guard { state: StateId, arrow: ArrowId, error: GuardError },
+    /// An effect of the arrow does not parse or apply
+    Effect { state: StateId, arrow: ArrowId, effect: String, error: EffectError },
+    /// The arrow is timed and the runtime has not been in its state long
+    /// enough
+    NotDue { state: StateId, arrow: ArrowId, remaining: Duration },
+    /// The duration of the arrow does not parse
+    Timer { state: StateId, arrow: ArrowId, error: DurationError },
+    /// An observer refused the transition
+    Vetoed { state: StateId, arrow: ArrowId, reason: String },
+}
+
+impl fmt::Display for RuntimeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            RuntimeError::UnknownState(state) => write!(f, "unknown state [{}]", state),
+            RuntimeError::ConflictingVariable { name, first, second } => {
+                write!(f, "variable [{}] is declared differently by [{}] and [{}]", name, first.0, second.0)
+            },
+            RuntimeError::UnknownArrow { state, arrow } => write!(f, "state [{}] has no arrow [{}]", state.0, arrow.0),
+            RuntimeError::Denied { state, arrow, guard } => {
+                write!(f, "arrow [{}] of [{}] is denied by its guard [{}]", arrow.0, state.0, guard)
+            },
+            RuntimeError::Guard { state, arrow, error } => {
+                write!(f, "guard of arrow [{}] of [{}]: {}", arrow.0, state.0, error)
+            },
+            RuntimeError::Effect { state, arrow, effect, error } => {
+                write!(f, "effect [{}] of arrow [{}] of [{}]: {}", effect, arrow.0, state.0, error)
+            },
+            RuntimeError::NotDue { state, arrow, remaining } => {
+                write!(f, "arrow [{}] of [{}] is due in {:?}", arrow.0, state.0, remaining)
+            },
+            RuntimeError::Timer { state, arrow, error } => {
+                write!(f, "duration of arrow [{}] of [{}]: {}", arrow.0, state.0, error)
+            },
+            RuntimeError::Vetoed { state, arrow, reason } => {
+                write!(f, "arrow [{}] of [{}] is vetoed: {}", arrow.0, state.0, reason)
+            },
+        }
+    }
+}
+
+impl fmt::Display for Transition {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        return write!(f, "{} -{}-> {}", self.from.0, self.arrow.0, self.to.0);
+    }
+}
+
+impl Runtime {
+
+    /// A runtime in the fully qualified state `start`, with the defaults of
+    /// the variables of every machine as context
+    pub fn new(space: Arc<Space>, start: &str) -> Result<Self, RuntimeError> {
+        let current = resolve(&space, start)?;
+        if let Some(SpaceError::ConflictingVariable { name, first, second }) = space.conflicting_variables().into_iter().next() {
+            return Err(RuntimeError::ConflictingVariable { name, first, second });
+        }
+        let mut context = Context::new();
+        for machine in &space.machines {
+            for (name, value) in effect::defaults(machine).iter() {
+                context.set(name, value.clone());
+            }
+        }
+        let clock: Arc<dyn Clock> = Arc::new(SystemClock::new());
+        let entered_at = clock.now();
+        return Ok(Self { space, current, context, history: Vec::new(), observers: Observers::new(), clock, entered_at });
+    }
+
+    /// Set the values of `context` over the variable defaults
+    pub fn with_context(mut self, context: Context) -> Self {
+        for (name, value) in context.iter() {
+            self.context.set(name, value.clone());
+        }
+        return self;
+    }
+
+    /// Read time from `clock`, keeping the time the runtime has been in its
+    /// current state
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        let elapsed = self.clock.now().saturating_sub(self.entered_at);
+        self.entered_at = clock.now().saturating_sub(elapsed);
+        self.clock = clock;
+        return self;
+    }
+
+    pub fn space(&self) -> &Arc<Space> {
+        return &self.space;
+    }
+
+    pub fn current(&self) -> &StateId {
+        return &self.current;
+    }
+
+    pub fn state(&self) -> Result<&State, RuntimeError> {
+        return self.space.resolve(&self.current.0).ok_or_else(|| RuntimeError::UnknownState(self.current.0.clone()));
+    }
+
+    pub fn clock(&self) -> &Arc<dyn Clock> {
+        return &self.clock;
+    }
+
+    /// Clock time the current state was entered at
+    pub fn entered_at(&self) -> Duration {
+        return self.entered_at;
+    }
+
+    pub fn context(&self) -> &Context {
+        return &self.context;
+    }
+
+    pub fn context_mut(&mut self) -> &mut Context {
+        return &mut self.context;
+    }
+
+    /// Arrows taken so far, oldest first
+    pub fn history(&self) -> &[Transition] {
+        return &self.history;
+    }
+
+    /// A runtime restored from a snapshot, once checked against the Space,
+    /// see `unite::snapshot`. It has no observers.
+    pub fn restore(space: Arc<Space>, snapshot: Snapshot) -> Result<Self, SnapshotError> {
+        let problems = snapshot.validate(&space);
+        if !problems.is_empty() {
+            return Err(SnapshotError::Invalid(problems));
+        }
+        let unknown = || SnapshotError::Invalid(vec![SnapshotProblem::UnknownCurrent(snapshot.current.clone())]);
+        let mut runtime = Self::new(space, &snapshot.current.0).map_err(|err| match err {
+            RuntimeError::UnknownState(_) => unknown(),
+            err => SnapshotError::Runtime(err),
+        })?;
+        for (name, value) in snapshot.context.iter() {
+            // defaults have their declared type, integers stored in a float
+            // variable become floats
+            let value = match runtime.context.get(name) {
+                Some(default) => value.clone().convert(default.type_of()).unwrap_or_else(|| value.clone()),
+                None => value.clone(),
+            };
+            runtime.context.set(name, value);
+        }
+        runtime.history = snapshot.history;
+        runtime.entered_at = runtime.clock.now().saturating_sub(Duration::from_millis(snapshot.elapsed));
+        return Ok(runtime);
+    }
+
+    /// The current state, the time spent in it, context and history of the
+    /// runtime
+    pub fn snapshot(&self) -> Snapshot {
+        return Snapshot {
+            current: self.current.clone(),
+            elapsed: self.clock.now().saturating_sub(self.entered_at).as_millis() as u64,
+            context: self.context.clone(),
+            history: self.history.clone(),
+        };
+    }
+
+    /// Register an observer of the transitions of states in `scope`
+    pub fn observe(&mut self, scope: Scope, observer: impl Observer + 'static) -> ObserverId {
+        return self.observers.register(scope, Box::new(observer));
+    }
+
+    /// Remove an observer, returning whether it was registered
+    pub fn unobserve(&mut self, id: ObserverId) -> bool {
+        return self.observers.remove(id);
+    }
+
+    /// Arrows of the current state that can be taken now, in the current
+    /// context
+    pub fn enabled_arrows(&self) -> Result<Vec<&Arrow>, RuntimeError> {
+        let mut enabled = Vec::new();
+        for arrow in &self.state()?.arrows {
+            match self.check(&arrow.id.0) {
+                Ok(_) => enabled.push(arrow),
+                Err(RuntimeError::Denied { .. } | RuntimeError::NotDue { .. }) => {},
+                Err(err) => return Err(err),
+            }
+        }
+        return Ok(enabled);
+    }
+
+    /// The arrow of the current state named `arrow`, if it can be taken
+    pub fn check(&self, arrow: &str) -> Result<&Arrow, RuntimeError> {
+        let arrow_id = ArrowId(arrow.to_string());
+        let arrow = self.state()?.arrow_by_id.get(&arrow_id).ok_or_else(|| RuntimeError::UnknownArrow {
+            state: self.current.clone(),
+            arrow: arrow_id.clone(),
+        })?;
+        if let Some(after) = self.duration(arrow)? {
+            let elapsed = self.clock.now().saturating_sub(self.entered_at);
+            if elapsed < after {
+                return Err(RuntimeError::NotDue { state: self.current.clone(), arrow: arrow_id, remaining: after - elapsed });
+            }
+        }
+        if let Some(source) = &arrow.guard {
+            let guard_error = |error| RuntimeError::Guard { state: self.current.clone(), arrow: arrow_id.clone(), error };
+            let guard = Guard::parse(source).map_err(guard_error)?;
+            if !guard.evaluate(&self.context).map_err(guard_error)? {
+                return Err(RuntimeError::Denied { state: self.current.clone(), arrow: arrow_id, guard: source.clone() });
+            }
+        }
+        return Ok(arrow);
+    }
+
+    /// Take an arrow of the current state, applying its effects once the
+    /// observers allow it
+    pub fn fire(&mut self, arrow: &str) -> Result<Transition, RuntimeError> {
+        let now = self.clock.now();
+        let pending = self.prepare(arrow, now)?;
+        return Ok(self.commit(pending));
+    }
+
+    /// Take the timed arrows that came due by the clock, in the order they
+    /// came due, each entering its state at the time it came due. Of arrows
+    /// due at once, the first by id whose guard allows it is taken. Stops at
+    /// the first error; the arrows taken before it stay taken.
+    pub fn tick(&mut self) -> Result<Vec<Transition>, RuntimeError> {
+        let now = self.clock.now();
+        let mut transitions = Vec::new();
+        while let Some((deadline, arrow)) = self.next_due(now)? {
+            let pending = self.prepare(&arrow, deadline)?;
+            transitions.push(self.commit(pending));
+        }
+        return Ok(transitions);
+    }
+
+    /// Clock time at which the first timed arrow of the current state comes
+    /// due, regardless of guards. Arrows whose duration does not parse are
+    /// left out.
+    pub fn next_deadline(&self) -> Option<Duration> {
+        return self.state().ok()?.arrows.iter()
+            .filter_map(|arrow| self.duration(arrow).ok().flatten())
+            .map(|after| self.entered_at + after)
+            .min();
+    }
+
+    /// Everything of taking an arrow that may fail, entering the next state
+    /// at `entered_at`. The runtime does not move until the arrow is
+    /// committed.
+    pub(crate) fn prepare(&mut self, arrow: &str, entered_at: Duration) -> Result<Pending, RuntimeError> {
+        let arrow = self.check(arrow)?;
+        let transition = Transition {
+            from: self.current.clone(),
+            arrow: arrow.id.clone(),
+            to: resolve(&self.space, &arrow.next.0)?,
+        };
+        let mut context = self.context.clone();
+        if !arrow.effects.is_empty() {
+            let machine_id = QualifiedStateId::parse(&self.current.0)
+                .map_err(|_| RuntimeError::UnknownState(self.current.0.clone()))?
+                .machine()
+                .clone();
+            let variables = &self.space.machine_by_id.get(&machine_id)
+                .ok_or_else(|| RuntimeError::UnknownState(self.current.0.clone()))?
+                .variables;
+            for source in &arrow.effects {
+                Effect::parse(source)
+                    .and_then(|effect| effect.apply(variables, &mut context))
+                    .map_err(|error| RuntimeError::Effect {
+                        state: self.current.clone(),
+                        arrow: arrow.id.clone(),
+                        effect: source.clone(),
+                        error,
+                    })?;
+            }
+        }
+        self.observers.allow(&transition, &self.context, &context).map_err(|veto| RuntimeError::Vetoed {
+            state: transition.from.clone(),
+            arrow: transition.arrow.clone(),
+            reason: veto.reason,
+        })?;
+        return Ok(Pending { transition, context, entered_at });
+    }
+
+    /// Read time from `clock`, in which the current state was entered at
+    /// `entered_at`, returning the clock and entry time replaced
+    pub(crate) fn replace_clock(&mut self, clock: Arc<dyn Clock>, entered_at: Duration) -> (Arc<dyn Clock>, Duration) {
+        let entered_at = std::mem::replace(&mut self.entered_at, entered_at);
+        return (std::mem::replace(&mut self.clock, clock), entered_at);
+    }
+
+    /// Move along a prepared arrow, then tell the observers
+    pub(crate) fn commit(&mut self, pending: Pending) -> Transition {
+        let Pending { transition, context, entered_at } = pending;
+        let before = std::mem::replace(&mut self.context, context);
+        self.current = transition.to.clone();
+        self.entered_at = entered_at;
+        self.history.push(transition.clone());
+        self.observers.notify(&transition, &before, &self.context);
+        return transition;
+    }
+
+    /// The earliest timed arrow of the current state due by `now` whose
+    /// guard allows it, with the time it came due
+    pub(crate) fn next_due(&self, now: Duration) -> Result<Option<(Duration, String)>, RuntimeError> {
+        let mut due = Vec::new();
+        for arrow in &self.state()?.arrows {
+            if let Some(after) = self.duration(arrow)? {
+                if self.entered_at + after <= now {
+                    due.push((self.entered_at + after, arrow));
+                }
+            }
+        }
+        // stable, so arrows due at once stay in id order
+        due.sort_by_key(|(deadline, _)| *deadline);
+        for (deadline, arrow) in due {
+            match self.check(&arrow.id.0) {
+                Ok(_) => return Ok(Some((deadline, arrow.id.0.clone()))),
+                Err(RuntimeError::Denied { .. }) => {},
+                Err(err) => return Err(err),
+            }
+        }
+        return Ok(None);
+    }
+
+    /// The duration of a timed arrow of the current state
+    fn duration(&self, arrow: &Arrow) -> Result<Option<Duration>, RuntimeError> {
+        return arrow.after.as_deref()
+            .map(|after| duration::parse_duration(after).map_err(|error| RuntimeError::Timer {
+                state: self.current.clone(),
+                arrow: arrow.id.clone(),
+                error,
+            }))
+            .transpose();
+    }
+}
+
+//------------------------------------------------------------------------------
+// RUNTIME / Helpers
+
+/// The canonical fully qualified id of a state of the Space
+fn resolve(space: &Space, state: &str) -> Result<StateId, RuntimeError> {
+    let unknown = || RuntimeError::UnknownState(state.to_string());
+    let id = QualifiedStateId::parse(state).map_err(|_| unknown())?;
+    space.resolve_id(&id).ok_or_else(unknown)?;
+    return Ok(id.to_state_id());
+}
@@ -0,0 +1,176 @@
+//!
+//! ! Session
+//! Many runtimes of one Space, by instance id, for services running many
+//! independent instances of a machine.
+//!
+//! The Space is shared by every runtime. Each runtime has its own lock, so
+//! transitions of different instances run concurrently; the map of instances
+//! is only locked to add, find or evict them, never while a runtime runs.
+//!
+//! A runtime whose lock was poisoned by a panicking thread is still used:
+//! runtimes only change once a transition fully succeeded.
+//!
+//! ----------------------------------------------------------------------------
+//! type: computer for Elea
+//! ----------------------------------------------------------------------------
+
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::{Arc, Mutex, MutexGuard, RwLock};
+
+use crate::define::experience::representation::qualified_id::QualifiedStateId;
+use crate::define::experience::representation::rust_pragmatic::Space;
+use crate::unite::runtime::{Runtime, RuntimeError, Transition};
+
+//------------------------------------------------------------------------------
+// SESSION
+
+pub type Session = Arc<Mutex<Runtime>>;
+
+pub struct SessionManager {
+    space: Arc<Space>,
+    sessions: RwLock<HashMap<String, Session>>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum SessionError {
+    /// An instance with this id already exists
+    Exists(String),
+    UnknownSession(String),
+    /// The runtime runs another Space than the manager's
+    ForeignSpace(String),
+    /// The filter is not a fully qualified state id
+    InvalidState(String),
+    Runtime(RuntimeError),
+}
+
+impl fmt::Display for SessionError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SessionError::Exists(id) => write!(f, "session [{}] already exists", id),
+            SessionError::UnknownSession(id) => write!(f, "unknown session [{}]", id),
+            SessionError::ForeignSpace(id) => write!(f, "session [{}] runs another space", id),
+            SessionError::InvalidState(state) => write!(f, "invalid state [{}]", state),
+            SessionError::Runtime(err) => write!(f, "{}", err),
+        }
+    }
+}
+
+impl SessionManager {
+
+    pub fn new(space: Arc<Space>) -> Self {
+        return Self { space, sessions: RwLock::new(HashMap::new()) };
+    }
+
+    pub fn space(&self) -> &Arc<Space> {
+        return &self.space;
+    }
+
+    /// Start an instance in the fully qualified state `start`
+    pub fn create(&self, id: &str, start: &str) -> Result<Session, SessionError> {
+        let runtime = Runtime::new(Arc::clone(&self.space), start).map_err(SessionError::Runtime)?;
+        return self.insert(id, runtime);
+    }
+
+    /// Add a runtime built elsewhere, such as a restored one. It must run
+    /// the manager's Space.
+    pub fn insert(&self, id: &str, runtime: Runtime) -> Result<Session, SessionError> {
+        if !Arc::ptr_eq(runtime.space(), &self.space) {
+            return Err(SessionError::ForeignSpace(id.to_string()));
+        }
+        let mut sessions = self.sessions.write().unwrap_or_else(|err| err.into_inner());
+        if sessions.contains_key(id) {
+            return Err(SessionError::Exists(id.to_string()));
+        }
+        let session = Arc::new(Mutex::new(runtime));
+        sessions.insert(id.to_string(), Arc::clone(&session));
+        return Ok(session);
+    }
+
+    pub fn get(&self, id: &str) -> Option<Session> {
+        return self.sessions.read().unwrap_or_else(|err| err.into_inner()).get(id).cloned();
+    }
+
+    /// Run `f` with the instance's runtime locked
+    pub fn with<R>(&self, id: &str, f: impl FnOnce(&mut Runtime) -> R) -> Result<R, SessionError> {
+        let session = self.get(id).ok_or_else(|| SessionError::UnknownSession(id.to_string()))?;
+        let mut runtime = lock(&session);
+        return Ok(f(&mut runtime));
+    }
+
+    /// Take an arrow of an instance
+    pub fn fire(&self, id: &str, arrow: &str) -> Result<Transition, SessionError> {
+        return self.with(id, |runtime| runtime.fire(arrow))?.map_err(SessionError::Runtime);
+    }
+
+    /// Instance ids, sorted
+    pub fn ids(&self) -> Vec<String> {
+        let mut ids: Vec<String> = self.sessions.read().unwrap_or_else(|err| err.into_inner()).keys().cloned().collect();
+        ids.sort();
+        return ids;
+    }
+
+    pub fn len(&self) -> usize {
+        return self.sessions.read().unwrap_or_else(|err| err.into_inner()).len();
+    }
+
+    pub fn is_empty(&self) -> bool {
+        return self.len() == 0;
+    }
+
+    /// Ids of the instances whose runtime matches `predicate`, sorted. Each
+    /// runtime is locked in turn while it is tested.
+    pub fn filter(&self, predicate: impl Fn(&Runtime) -> bool) -> Vec<String> {
+        let mut ids: Vec<String> = self.matching(predicate).into_iter().map(|(id, _)| id).collect();
+        ids.sort();
+        return ids;
+    }
+
+    /// Ids of the instances in a state or in a state below it, sorted. A
+    /// machine id matches every state of the machine.
+    pub fn in_state(&self, state: &str) -> Result<Vec<String>, SessionError> {
+        let state = QualifiedStateId::parse(state)
+            .or_else(|_| QualifiedStateId::machine_root(state))
+            .map_err(|_| SessionError::InvalidState(state.to_string()))?;
+        return Ok(self.filter(|runtime| {
+            QualifiedStateId::parse(&runtime.current().0).is_ok_and(|current| state.contains(&current))
+        }));
+    }
+
+    /// Remove an instance. Holders of its session may keep using it, apart
+    /// from the manager.
+    pub fn evict(&self, id: &str) -> Option<Session> {
+        return self.sessions.write().unwrap_or_else(|err| err.into_inner()).remove(id);
+    }
+
+    /// Remove the instances whose runtime matches `predicate`, returning
+    /// their ids, sorted. An instance replaced while it was tested stays.
+    pub fn evict_where(&self, predicate: impl Fn(&Runtime) -> bool) -> Vec<String> {
+        let matching = self.matching(predicate);
+        let mut sessions = self.sessions.write().unwrap_or_else(|err| err.into_inner());
+        let mut ids = Vec::new();
+        for (id, session) in matching {
+            if sessions.get(&id).is_some_and(|current| Arc::ptr_eq(current, &session)) {
+                sessions.remove(&id);
+                ids.push(id);
+            }
+        }
+        ids.sort();
+        return ids;
+    }
+
+    /// Instances whose runtime matches `predicate`. Runtimes are tested
+    /// without the map locked, so that a test waiting on a runtime never
+    /// holds up the other instances.
+    fn matching(&self, predicate: impl Fn(&Runtime) -> bool) -> Vec<(String, Session)> {
+        let sessions: Vec<(String, Session)> = self.sessions.read().unwrap_or_else(|err| err.into_inner())
+            .iter()
+            .map(|(id, session)| (id.clone(), Arc::clone(session)))
+            .collect();
+        return sessions.into_iter().filter(|(_, session)| predicate(&lock(session))).collect();
+    }
+}
+
+fn lock(session: &Session) -> MutexGuard<'_, Runtime> {
+    return session.lock().unwrap_or_else(|err| err.into_inner());
+}
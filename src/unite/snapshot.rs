@@ -0,0 +1,187 @@
+//!
+//! ! Snapshot
+//! The state of a runtime, its current state and the time spent in it, its
+//! context and history, as JSON or YAML, to restore it in another process.
+//! Observers are not part of it.
+//!
+//! The Space may have changed in between, so a snapshot is checked before
+//! it is restored: its states must still exist, each arrow of its history
+//! must still lead where it led, the history must end at the current state,
+//! and the variables of its context must keep their declared types. Variables
+//! declared since the snapshot was taken start with their defaults.
+//!
+//! ----------------------------------------------------------------------------
+//! type: computer for Elea
+//! ----------------------------------------------------------------------------
+
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+use crate::define::experience::language::context::{Context, Type};
+use crate::define::experience::representation::qualified_id::QualifiedStateId;
+use crate::define::experience::representation::rust_minimal::{ArrowId, StateId};
+use crate::define::experience::representation::rust_pragmatic::Space;
+use crate::unite::runtime::{RuntimeError, Transition};
+
+//------------------------------------------------------------------------------
+// SNAPSHOT
+
+/// State ids are fully qualified
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct Snapshot {
+    pub current: StateId,
+    /// Milliseconds the runtime has been in its current state, by its clock
+    #[serde(default)]
+    pub elapsed: u64,
+    pub context: Context,
+    pub history: Vec<Transition>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum SnapshotError {
+    /// The snapshot could not be read or written
+    Format(String),
+    /// The snapshot does not hold for the Space
+    Invalid(Vec<SnapshotProblem>),
+    /// The Space cannot be run
+    Runtime(RuntimeError),
+}
+
+/// A part of a snapshot the Space no longer agrees with. `index` is the
+/// position of the transition in the history.
+#[derive(Clone, Debug, PartialEq)]
+pub enum SnapshotProblem {
+    UnknownCurrent(StateId),
+    UnknownState { index: usize, state: StateId },
+    UnknownArrow { index: usize, state: StateId, arrow: ArrowId },
+    /// The arrow now leads to another state
+    ChangedArrow { index: usize, arrow: ArrowId, recorded: StateId, found: StateId },
+    /// The transition does not start where the previous one ended, or, at
+    /// the end of the history, the current state is not where it ended
+    Discontinuous { index: usize },
+    /// A declared variable holds a value of another type
+    VariableType { variable: String, expected: Type, found: Type },
+}
+
+impl fmt::Display for SnapshotError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SnapshotError::Format(err) => write!(f, "invalid snapshot format: {}", err),
+            SnapshotError::Invalid(problems) => {
+                let problems: Vec<String> = problems.iter().map(|problem| problem.to_string()).collect();
+                write!(f, "snapshot does not match the space: {}", problems.join(", "))
+            },
+            SnapshotError::Runtime(err) => write!(f, "{}", err),
+        }
+    }
+}
+
+impl fmt::Display for SnapshotProblem {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SnapshotProblem::UnknownCurrent(state) => write!(f, "unknown current state [{}]", state.0),
+            SnapshotProblem::UnknownState { index, state } => {
+                write!(f, "transition {}: unknown state [{}]", index, state.0)
+            },
+            SnapshotProblem::UnknownArrow { index, state, arrow } => {
+                write!(f, "transition {}: state [{}] has no arrow [{}]", index, state.0, arrow.0)
+            },
+            SnapshotProblem::ChangedArrow { index, arrow, recorded, found } => {
+                write!(f, "transition {}: arrow [{}] leads to [{}], not [{}]", index, arrow.0, found.0, recorded.0)
+            },
+            SnapshotProblem::Discontinuous { index } => {
+                write!(f, "transition {}: does not follow the previous state", index)
+            },
+            SnapshotProblem::VariableType { variable, expected, found } => {
+                write!(f, "variable [{}] is {}, not {}", variable, found, expected)
+            },
+        }
+    }
+}
+
+impl Snapshot {
+
+    pub fn to_json(&self) -> Result<String, SnapshotError> {
+        return serde_json::to_string_pretty(self).map_err(|err| SnapshotError::Format(err.to_string()));
+    }
+
+    pub fn to_yaml(&self) -> Result<String, SnapshotError> {
+        return serde_yaml::to_string(self).map_err(|err| SnapshotError::Format(err.to_string()));
+    }
+
+    pub fn from_json(json: &str) -> Result<Self, SnapshotError> {
+        return serde_json::from_str(json).map_err(|err| SnapshotError::Format(err.to_string()));
+    }
+
+    pub fn from_yaml(yaml: &str) -> Result<Self, SnapshotError> {
+        return serde_yaml::from_str(yaml).map_err(|err| SnapshotError::Format(err.to_string()));
+    }
+
+    /// Parts of the snapshot the Space no longer agrees with, in history
+    /// order, then variables in name order
+    pub fn validate(&self, space: &Space) -> Vec<SnapshotProblem> {
+        let mut problems = Vec::new();
+        let canonical = |state: &StateId| {
+            let id = QualifiedStateId::parse(&state.0).ok()?;
+            space.resolve_id(&id)?;
+            return Some(id.to_state_id());
+        };
+
+        let mut previous: Option<StateId> = None;
+        for (index, transition) in self.history.iter().enumerate() {
+            let from = canonical(&transition.from);
+            if previous.is_some() && from.is_some() && previous != from {
+                problems.push(SnapshotProblem::Discontinuous { index });
+            }
+            previous = canonical(&transition.to);
+            let from = match from {
+                Some(from) => from,
+                None => {
+                    problems.push(SnapshotProblem::UnknownState { index, state: transition.from.clone() });
+                    continue;
+                },
+            };
+            let arrow = space.resolve(&from.0).and_then(|state| state.arrow_by_id.get(&transition.arrow));
+            let arrow = match arrow {
+                Some(arrow) => arrow,
+                None => {
+                    problems.push(SnapshotProblem::UnknownArrow { index, state: from, arrow: transition.arrow.clone() });
+                    continue;
+                },
+            };
+            let found = canonical(&arrow.next).unwrap_or_else(|| arrow.next.clone());
+            if previous.as_ref() != Some(&found) {
+                problems.push(SnapshotProblem::ChangedArrow {
+                    index,
+                    arrow: transition.arrow.clone(),
+                    recorded: transition.to.clone(),
+                    found,
+                });
+            }
+        }
+
+        match canonical(&self.current) {
+            Some(current) => {
+                if previous.is_some() && previous != Some(current) {
+                    problems.push(SnapshotProblem::Discontinuous { index: self.history.len() });
+                }
+            },
+            None => problems.push(SnapshotProblem::UnknownCurrent(self.current.clone())),
+        }
+
+        for (name, value) in self.context.iter() {
+            // machines declaring it declare it alike, see `Runtime::new`
+            let declared = space.machines.iter().find_map(|machine| machine.variables.get(name));
+            if let Some(variable) = declared {
+                if !variable.type_.accepts(value.type_of()) {
+                    problems.push(SnapshotProblem::VariableType {
+                        variable: name.clone(),
+                        expected: variable.type_,
+                        found: value.type_of(),
+                    });
+                }
+            }
+        }
+        return problems;
+    }
+}
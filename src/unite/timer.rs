@@ -0,0 +1,81 @@
+//!
+//! ! Timer
+//! Arrows taken once a runtime has been in their state for some time, and
+//! the clocks runtimes read that time from.
+//!
+//! ```yaml
+//! arrows:
+//!   - id: calm
+//!     next: default/on
+//!     after: 10s
+//! ```
+//!
+//! Durations are parsed by `define::experience::language::duration`. A timed
+//! arrow cannot be taken before its time. Once it is due, `Runtime::tick` takes it,
+//! or the runtime's application may; its guard still applies.
+//!
+//! The system clock follows real time. The virtual clock only moves when it
+//! is told to, so that tests advance through timeouts without sleeping.
+//!
+//! ----------------------------------------------------------------------------
+//! type: computer for Elea
+//! ----------------------------------------------------------------------------
+
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+//------------------------------------------------------------------------------
+// TIMER
+
+/// A source of time, as the time elapsed since a start of its choosing
+pub trait Clock: Send + Sync {
+    fn now(&self) -> Duration;
+}
+
+/// Real time, since the Unix epoch
+#[derive(Clone, Debug, Default)]
+pub struct SystemClock;
+
+/// Time that only moves when advanced. Clones share their time, so a test
+/// keeps a clone to advance the clock of a runtime.
+#[derive(Clone, Debug, Default)]
+pub struct VirtualClock {
+    now: Arc<Mutex<Duration>>,
+}
+
+impl SystemClock {
+
+    pub fn new() -> Self {
+        return Self;
+    }
+}
+
+impl Clock for SystemClock {
+    fn now(&self) -> Duration {
+        return SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default();
+    }
+}
+
+impl VirtualClock {
+
+    pub fn new() -> Self {
+        return Self::default();
+    }
+
+    pub fn advance(&self, duration: Duration) {
+        let mut now = self.now.lock().unwrap_or_else(|err| err.into_inner());
+        *now += duration;
+    }
+
+    /// Move to `now`, which may not be before the current time
+    pub fn set(&self, now: Duration) {
+        let mut current = self.now.lock().unwrap_or_else(|err| err.into_inner());
+        *current = now.max(*current);
+    }
+}
+
+impl Clock for VirtualClock {
+    fn now(&self) -> Duration {
+        return *self.now.lock().unwrap_or_else(|err| err.into_inner());
+    }
+}
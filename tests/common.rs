@@ -3,7 +3,13 @@
 //!
 
 use std::env;
-use std::path::PathBuf;
+use std::fs;
+use std::path::{Path, PathBuf};
+use walkdir::WalkDir;
+
+use elea::define::experience::representation::filesystem;
+use elea::define::experience::representation::rust_minimal::{ArrowId, StateId};
+use elea::define::experience::representation::rust_pragmatic::Space;
 
 
 pub fn asset_path(asset_path: &str) -> PathBuf {
@@ -16,3 +22,61 @@ pub fn asset_path(asset_path: &str) -> PathBuf {
     path.push(asset_path);
     return path.clone();
 }
+
+/// Copy a test asset directory to a fresh temporary directory, for tests
+/// that change files
+pub fn temp_asset_copy(asset_path: &str, test_name: &str) -> PathBuf {
+    let source = self::asset_path(asset_path);
+    let mut target = env::temp_dir();
+    target.push(format!("elea-test-{}-{}", test_name, std::process::id()));
+    if target.exists() {
+        fs::remove_dir_all(&target).unwrap();
+    }
+    for entry in WalkDir::new(&source).into_iter().filter_map(|e| e.ok()) {
+        let relative = entry.path().strip_prefix(&source).unwrap();
+        let path = target.join(relative);
+        if entry.file_type().is_dir() {
+            fs::create_dir_all(&path).unwrap();
+        } else {
+            fs::copy(entry.path(), &path).unwrap();
+        }
+    }
+    return target;
+}
+
+/// The Space of a test asset directory of nested machines
+pub fn load_space(asset_path: &str) -> Space {
+    return load_space_at(&self::asset_path(asset_path));
+}
+
+/// The Space of a directory of nested machines
+pub fn load_space_at(machines_path: &Path) -> Space {
+    return Space::new(filesystem::to_machines(machines_path, filesystem::Format::NestedMachines).unwrap());
+}
+
+/// A lightswitch that can break and be repaired, and be unplugged when off
+pub fn lightswitch() -> Space {
+    return elea::space! {
+        default {
+            on { off -> default/off, break -> broken/fix }
+            off { on -> default/on, unplug -> default/unplugged }
+            unplugged {}
+        }
+        broken {
+            fix { repair -> default/on }
+        }
+    };
+}
+
+/// The lightswitch with weighted arrows of the plan tests
+pub fn weighted_lightswitch() -> Space {
+    return load_space("plan/weighted-lightswitch");
+}
+
+pub fn state_id(id: &str) -> StateId {
+    return StateId(id.to_string());
+}
+
+pub fn arrow_id(id: &str) -> ArrowId {
+    return ArrowId(id.to_string());
+}
@@ -0,0 +1,130 @@
+//!
+//! Test | Compose
+//!
+
+use crate::common::state_id;
+use elea::define::change::compose::{self, ComposeError, ComposeOptions};
+use elea::define::experience::language::context::{Type, Value};
+use elea::define::experience::representation::rust_minimal::{ArrowId, MachineId};
+use elea::define::experience::representation::rust_pragmatic::{
+    Arrow, Machine, Space, State, Variable
+};
+
+
+fn lightswitch() -> Space {
+    return elea::space! {
+        power {
+            on { off -> power/off, blink -> power/on }
+            off { on -> power/on }
+        }
+        pattern {
+            steady { blink -> pattern/flash }
+            flash { blink -> pattern/steady, calm -> pattern/steady }
+            dark {}
+        }
+    };
+}
+
+#[test]
+fn test_compose_interleaves_and_synchronizes() {
+    let space = lightswitch();
+    let product = compose::compose_space(&space, "lightswitch", &["power", "pattern"], &ComposeOptions::default()).unwrap();
+
+    assert_eq!(product.id, MachineId("lightswitch".to_string()));
+    assert_eq!(product.states.len(), 6);
+    // `blink` needs both machines, the other arrows move one
+    assert_eq!(product.state_by_id[&state_id("on/steady")], State::new("on/steady", vec![
+        Arrow::new("blink", "lightswitch/on/flash"),
+        Arrow::new("off", "lightswitch/off/steady"),
+    ]));
+    assert_eq!(product.state_by_id[&state_id("off/steady")], State::new("off/steady", vec![
+        Arrow::new("on", "lightswitch/on/steady"),
+    ]));
+    assert_eq!(product.state_by_id[&state_id("on/flash")], State::new("on/flash", vec![
+        Arrow::new("blink", "lightswitch/on/steady"),
+        Arrow::new("calm", "lightswitch/on/steady"),
+        Arrow::new("off", "lightswitch/off/flash"),
+    ]));
+    assert_eq!(product.state_by_id[&state_id("on/dark")], State::new("on/dark", vec![
+        Arrow::new("off", "lightswitch/off/dark"),
+    ]));
+    assert!(Space::new(vec![product]).validate().is_empty());
+}
+
+#[test]
+fn test_compose_reachable_from_start() {
+    let space = lightswitch();
+    let options = ComposeOptions { start: Some(vec!["power/off".to_string(), "pattern/steady".to_string()]) };
+    let product = compose::compose_space(&space, "lightswitch", &["power", "pattern"], &options).unwrap();
+    let ids: Vec<&str> = product.states.iter().map(|state| state.id.0.as_str()).collect();
+    assert_eq!(ids, vec!["off/flash", "off/steady", "on/flash", "on/steady"]);
+}
+
+#[test]
+fn test_compose_nested_states_and_weights() {
+    let mut fade = State::new("fade/levels", vec![Arrow::new("step", "pattern/fade")]);
+    fade.arrows[0].weight = Some(0.5);
+    let pattern = Machine::new("pattern", vec![fade, State::new("fade", vec![Arrow::new("step", "pattern/fade/levels")])]);
+    let mut tick = Arrow::new("step", "clock/tick");
+    tick.weight = Some(0.5);
+    let clock = Machine::new("clock", vec![State::new("tick", vec![tick])]);
+
+    let product = compose::compose("sync", &[&pattern, &clock], &ComposeOptions::default()).unwrap();
+    let state = &product.state_by_id[&compose::product_state_id(&[&state_id("fade/levels"), &state_id("tick")])];
+    assert_eq!(state.id, state_id("fade.levels/tick"));
+    assert_eq!(state.arrows[0].next, state_id("sync/fade/tick"));
+    assert_eq!(state.arrows[0].weight, Some(0.25));
+    assert_eq!(product.state_by_id[&state_id("fade/tick")].arrows[0].weight, Some(0.5));
+}
+
+#[test]
+fn test_compose_errors() {
+    let space = lightswitch();
+    let compose = |ids: &[&str], options: &ComposeOptions| compose::compose_space(&space, "product", ids, options);
+    assert_eq!(compose(&[], &ComposeOptions::default()), Err(ComposeError::NoMachines));
+    assert_eq!(
+        compose(&["power", "power"], &ComposeOptions::default()),
+        Err(ComposeError::DuplicateMachine(MachineId("power".to_string()))),
+    );
+    assert_eq!(
+        compose(&["power", "bulb"], &ComposeOptions::default()),
+        Err(ComposeError::UnknownMachine(MachineId("bulb".to_string()))),
+    );
+    assert_eq!(
+        compose(&["power", "pattern"], &ComposeOptions { start: Some(vec!["power/on".to_string()]) }),
+        Err(ComposeError::StartLength { expected: 2, found: 1 }),
+    );
+    assert_eq!(
+        compose(&["power"], &ComposeOptions { start: Some(vec!["pattern/steady".to_string()]) }),
+        Err(ComposeError::UnknownState(state_id("pattern/steady"))),
+    );
+
+    let leaving = Machine::new("power", vec![State::new("on", vec![Arrow::new("break", "broken/fix")])]);
+    assert_eq!(
+        compose::compose("product", &[&leaving], &ComposeOptions::default()),
+        Err(ComposeError::ExternalArrow {
+            state: state_id("power/on"),
+            arrow: ArrowId("break".to_string()),
+            next: state_id("broken/fix"),
+        }),
+    );
+    let twice = Machine::new("power", vec![State::new("on", vec![Arrow::new("blink", "power/on"), Arrow::new("blink", "power/off")])]);
+    assert_eq!(
+        compose::compose("product", &[&twice], &ComposeOptions::default()),
+        Err(ComposeError::DuplicateArrow { state: state_id("power/on"), arrow: ArrowId("blink".to_string()) }),
+    );
+    let mut power = space.machine_by_id[&MachineId("power".to_string())].clone();
+    let mut pattern = space.machine_by_id[&MachineId("pattern".to_string())].clone();
+    power.variables.insert("level".to_string(), Variable { type_: Type::Int, default: Value::Int(100) });
+    pattern.variables.insert("level".to_string(), Variable { type_: Type::Int, default: Value::Int(100) });
+    assert!(compose::compose("product", &[&power, &pattern], &ComposeOptions::default()).is_ok());
+    pattern.variables.insert("level".to_string(), Variable { type_: Type::Text, default: Value::from("full") });
+    assert_eq!(
+        compose::compose("product", &[&power, &pattern], &ComposeOptions::default()),
+        Err(ComposeError::ConflictingVariable {
+            name: "level".to_string(),
+            first: MachineId("power".to_string()),
+            second: MachineId("pattern".to_string()),
+        }),
+    );
+}
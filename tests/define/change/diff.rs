@@ -0,0 +1,142 @@
+//!
+//! Test | Diff
+//!
+
+use crate::common::state_id;
+use elea::define::change::diff::{
+    self, ArrowChange, Attribute, AttributeChange, DescriptionChange, Rename, Retarget
+};
+use elea::define::experience::representation::rust_minimal::{ArrowId, MachineId};
+use elea::define::experience::representation::rust_pragmatic::{
+    Arrow, Machine, Space, State
+};
+
+
+fn lightswitch(pattern_machine: &str, pattern_state: &str) -> Space {
+    let pattern_target = format!("default/{}", pattern_state);
+    let pattern_levels = format!("{}/fade/levels", pattern_machine);
+    return Space::new(vec![
+        Machine::new("default", vec![
+            State::new("on", vec![
+                Arrow::new("off", "default/off"),
+                Arrow::new("pattern", &pattern_target),
+            ]),
+            State::new("off", vec![
+                Arrow::new("on", "default/on"),
+            ]),
+            State::new(pattern_state, vec![
+                Arrow::new("fade", &pattern_levels),
+            ]),
+        ]),
+        Machine::new(pattern_machine, vec![
+            State::new("fade/levels", vec![
+                Arrow::new("20_percent", "default/on"),
+                Arrow::new("40_percent", "default/on"),
+            ]),
+        ]),
+    ]);
+}
+
+#[test]
+fn test_diff_of_equal_spaces_is_empty() {
+    let space_diff = diff::diff(&lightswitch("pattern", "pattern"), &lightswitch("pattern", "pattern"));
+    assert!(space_diff.is_empty());
+    assert_eq!(space_diff.to_string(), "");
+}
+
+#[test]
+fn test_diff_detects_renames() {
+    let space_diff = diff::diff(&lightswitch("pattern", "pattern"), &lightswitch("effects", "patterns"));
+
+    assert_eq!(space_diff.renamed_machines, vec![Rename {
+        from: MachineId("pattern".to_string()),
+        to: MachineId("effects".to_string()),
+    }]);
+    assert_eq!(space_diff.renamed_states, vec![Rename {
+        from: state_id("default/pattern"),
+        to: state_id("default/patterns"),
+    }]);
+    // references that only follow the renames are not changes
+    assert!(space_diff.retargeted_arrows.is_empty());
+    assert!(space_diff.added_states.is_empty() && space_diff.removed_states.is_empty());
+    assert!(space_diff.added_machines.is_empty() && space_diff.removed_machines.is_empty());
+}
+
+#[test]
+fn test_diff_detects_arrow_and_description_changes() {
+    let old = lightswitch("pattern", "pattern");
+    let mut new_default = old.machine_by_id[&MachineId("default".to_string())].clone();
+    let mut on = State::new("on", vec![
+        Arrow::new("off", "default/pattern"),
+        Arrow::new("dim", "default/dim"),
+    ]);
+    on.description = Some("Light is on".to_string());
+    new_default.states.retain(|s| s.id.0 != "on");
+    new_default.states.push(on);
+    new_default.states.push(State::new("dim", vec![Arrow::new("on", "default/on")]));
+    let new_default = Machine::new("default", new_default.states);
+    let new = Space::new(vec![new_default, old.machine_by_id[&MachineId("pattern".to_string())].clone()]);
+
+    let space_diff = diff::diff(&old, &new);
+    assert_eq!(space_diff.added_states, vec![state_id("default/dim")]);
+    assert_eq!(space_diff.added_arrows, vec![ArrowChange {
+        state: state_id("default/on"),
+        arrow: ArrowId("dim".to_string()),
+        next: state_id("default/dim"),
+    }]);
+    assert_eq!(space_diff.removed_arrows, vec![ArrowChange {
+        state: state_id("default/on"),
+        arrow: ArrowId("pattern".to_string()),
+        next: state_id("default/pattern"),
+    }]);
+    assert_eq!(space_diff.retargeted_arrows, vec![Retarget {
+        state: state_id("default/on"),
+        arrow: ArrowId("off".to_string()),
+        from: state_id("default/off"),
+        to: state_id("default/pattern"),
+    }]);
+    assert_eq!(space_diff.changed_descriptions, vec![DescriptionChange {
+        state: state_id("default/on"),
+        arrow: None,
+        from: None,
+        to: Some("Light is on".to_string()),
+    }]);
+
+    let text = space_diff.to_string();
+    assert!(text.contains("state added        default/dim\n"));
+    assert!(text.contains("arrow retargeted   default/on --off--> default/pattern (was default/off)\n"));
+    let json: serde_json::Value = serde_json::to_value(&space_diff).unwrap();
+    assert_eq!(json["added_states"][0], "default/dim");
+}
+
+#[test]
+fn test_diff_detects_arrow_attribute_changes() {
+    let old = lightswitch("pattern", "pattern");
+    let mut machines = old.machines.clone();
+    let default = machines.iter_mut().find(|m| m.id.0 == "default").unwrap();
+    let on = default.states.iter_mut().find(|s| s.id.0 == "on").unwrap();
+    on.arrows[0].weight = Some(0.5);
+    on.arrows[0].guard = Some("brightness > 0".to_string());
+    on.arrows[1].effects = vec!["set brightness = 20".to_string(), "set mode = \"fade\"".to_string()];
+    on.arrows[1].after = Some("10s".to_string());
+    let new = Space::new(machines);
+
+    let space_diff = diff::diff(&old, &new);
+    let change = |arrow: &str, attribute: Attribute, to: &str| AttributeChange {
+        state: state_id("default/on"),
+        arrow: ArrowId(arrow.to_string()),
+        attribute,
+        from: None,
+        to: Some(to.to_string()),
+    };
+    assert_eq!(space_diff.changed_arrows, vec![
+        change("off", Attribute::Weight, "0.5"),
+        change("off", Attribute::Guard, "brightness > 0"),
+        change("pattern", Attribute::Effects, "set brightness = 20; set mode = \"fade\""),
+        change("pattern", Attribute::After, "10s"),
+    ]);
+    let text = space_diff.to_string();
+    assert!(text.contains("arrow changed      default/on --off--> weight: (none) -> 0.5\n"));
+    assert!(text.contains("arrow changed      default/on --pattern--> after: (none) -> 10s\n"));
+    assert!(diff::diff(&new, &new).is_empty());
+}
@@ -0,0 +1,130 @@
+//!
+//! Test | Minimize
+//!
+
+use crate::common::state_id;
+use elea::define::change::minimize::{self, MinimizeError, MinimizeOptions};
+use elea::define::experience::language::context::{Type, Value};
+use elea::define::experience::representation::rust_minimal::{ArrowId, MachineId};
+use elea::define::experience::representation::rust_pragmatic::{
+    Arrow, Machine, Space, SpaceError, State, Variable
+};
+
+
+fn fading_lightswitch() -> Space {
+    return elea::space! {
+        default {
+            on { off -> default/off }
+            off { on -> default/on, fade -> default/fade }
+            fade { level20 -> default/fade/level20, level40 -> default/fade/level40 }
+            fade/level20 { back -> default/on }
+            fade/level40 { back -> default/on }
+            unplugged {}
+        }
+        broken {
+            fix {}
+        }
+    };
+}
+
+#[test]
+fn test_minimize_merges_equivalent_states() {
+    let minimization = minimize::minimize(&fading_lightswitch(), MinimizeOptions::default()).unwrap();
+
+    assert_eq!(minimization.merged(), vec![vec![state_id("default/fade/level20"), state_id("default/fade/level40")]]);
+    assert_eq!(minimization.removed_count(), 1);
+    assert_eq!(minimization.mapping[&state_id("default/fade/level40")], state_id("default/fade/level20"));
+    assert_eq!(minimization.mapping[&state_id("default/on")], state_id("default/on"));
+    // machines keep their states apart by default
+    assert_eq!(minimization.mapping[&state_id("broken/fix")], state_id("broken/fix"));
+
+    let space = &minimization.space;
+    assert!(space.resolve("default/fade/level40").is_none());
+    assert_eq!(space.resolve("default/fade").unwrap(), &State::new("fade", vec![
+        Arrow::new("level20", "default/fade/level20"),
+        Arrow::new("level40", "default/fade/level20"),
+    ]));
+    assert!(space.validate().is_empty());
+    assert_eq!(minimization.to_string(), "default/fade/level20 <- default/fade/level40\n1 states removed\n");
+}
+
+#[test]
+fn test_minimize_across_machines() {
+    let options = MinimizeOptions { across_machines: true };
+    let minimization = minimize::minimize(&fading_lightswitch(), options).unwrap();
+    assert_eq!(minimization.mapping[&state_id("default/unplugged")], state_id("broken/fix"));
+    assert!(minimization.space.machine_by_id.values().all(|machine| !machine.states.is_empty()));
+    assert_eq!(minimization.removed_count(), 2);
+}
+
+#[test]
+fn test_minimize_keeps_variables() {
+    let mut space = fading_lightswitch();
+    let variable = Variable { type_: Type::Int, default: Value::Int(100) };
+    space.machines.iter_mut().find(|m| m.id.0 == "default").unwrap().variables.insert("brightness".to_string(), variable.clone());
+    let space = Space::new(space.machines);
+
+    let minimization = minimize::minimize(&space, MinimizeOptions::default()).unwrap();
+    let default = &minimization.space.machine_by_id[&MachineId("default".to_string())];
+    assert_eq!(default.variables.get("brightness"), Some(&variable));
+}
+
+#[test]
+fn test_minimize_refines_by_next_states() {
+    // three steps to the end are not two steps to the end
+    let chain = Machine::new("chain", vec![
+        State::new("a", vec![Arrow::new("next", "chain/b")]),
+        State::new("b", vec![Arrow::new("next", "chain/c")]),
+        State::new("c", vec![]),
+        State::new("x", vec![Arrow::new("next", "chain/y")]),
+        State::new("y", vec![]),
+    ]);
+    let minimization = minimize::minimize(&Space::new(vec![chain]), MinimizeOptions::default()).unwrap();
+    assert_eq!(minimization.merged(), vec![
+        vec![state_id("chain/b"), state_id("chain/x")],
+        vec![state_id("chain/c"), state_id("chain/y")],
+    ]);
+    assert_eq!(minimization.space.resolve("chain/a").unwrap().arrows[0].next, state_id("chain/b"));
+
+    // weights tell arrows apart, a missing weight is 1
+    let mut heavy = Arrow::new("next", "weights/end");
+    heavy.weight = Some(2.0);
+    let mut one = Arrow::new("next", "weights/end");
+    one.weight = Some(1.0);
+    let weights = Machine::new("weights", vec![
+        State::new("heavy", vec![heavy]),
+        State::new("one", vec![one]),
+        State::new("plain", vec![Arrow::new("next", "weights/end")]),
+        State::new("end", vec![]),
+    ]);
+    let minimization = minimize::minimize(&Space::new(vec![weights]), MinimizeOptions::default()).unwrap();
+    assert_eq!(minimization.merged(), vec![vec![state_id("weights/one"), state_id("weights/plain")]]);
+    // so do guards, effects and durations
+    let mut guarded = Arrow::new("next", "behavior/end");
+    guarded.guard = Some("brightness > 20".to_string());
+    let mut dimming = Arrow::new("next", "behavior/end");
+    dimming.effects = vec!["set brightness = 20".to_string()];
+    let mut waiting = Arrow::new("next", "behavior/end");
+    waiting.after = Some("10s".to_string());
+    let behavior = Machine::new("behavior", vec![
+        State::new("guarded", vec![guarded]),
+        State::new("dimming", vec![dimming]),
+        State::new("waiting", vec![waiting]),
+        State::new("plain", vec![Arrow::new("next", "behavior/end")]),
+        State::new("end", vec![]),
+    ]);
+    let minimization = minimize::minimize(&Space::new(vec![behavior]), MinimizeOptions::default()).unwrap();
+    assert!(minimization.merged().is_empty());
+}
+
+#[test]
+fn test_minimize_invalid_space() {
+    let space = Space::new(vec![Machine::new("default", vec![State::new("on", vec![Arrow::new("off", "default/off")])])]);
+    assert_eq!(minimize::minimize(&space, MinimizeOptions::default()).err(), Some(MinimizeError::Space(vec![
+        SpaceError::UnresolvedArrow {
+            state: state_id("default/on"),
+            arrow: ArrowId("off".to_string()),
+            next: state_id("default/off"),
+        },
+    ])));
+}
@@ -0,0 +1,4 @@
+mod compose;
+mod diff;
+mod minimize;
+mod rename;
@@ -0,0 +1,117 @@
+//!
+//! Test | Rename
+//!
+
+use std::fs;
+
+use crate::common;
+use elea::define::change::rename::{self, RenameError, RenameOptions};
+
+
+const LIGHTSWITCH: &str = "representation/filesystem/machines/nested-machines-lightswitch";
+
+#[test]
+fn test_rename_state_moves_file_and_rewrites_arrows() {
+    let machines_path = common::temp_asset_copy(LIGHTSWITCH, "rename-move");
+
+    let report = rename::rename_state(&machines_path, "default/pattern", "pattern/menu", RenameOptions::default()).unwrap();
+    assert_eq!(report.file_from, machines_path.join("pattern.yaml"));
+    assert_eq!(report.file_to, machines_path.join("pattern/menu.yaml"));
+    assert_eq!(report.rewritten_files, vec![machines_path.join("on.yaml")]);
+    assert_eq!(report.touched_files().len(), 3);
+
+    assert!(!machines_path.join("pattern.yaml").exists());
+    let space = common::load_space_at(&machines_path);
+    assert!(space.resolve("default/pattern").is_none());
+    let menu = space.resolve("pattern/menu").unwrap();
+    assert_eq!(menu.arrows.len(), 2);
+    let on = space.resolve("default/on").unwrap();
+    assert!(on.arrows.iter().any(|a| a.next.0 == "pattern/menu"));
+    assert!(space.validate().is_empty());
+
+    fs::remove_dir_all(&machines_path).unwrap();
+}
+
+#[test]
+fn test_rename_state_removes_emptied_directories() {
+    let machines_path = common::temp_asset_copy(LIGHTSWITCH, "rename-empty-dirs");
+
+    let report = rename::rename_state(&machines_path, "pattern/fade/levels", "pattern/levels", RenameOptions::default()).unwrap();
+    assert_eq!(report.file_to, machines_path.join("pattern/levels.yaml"));
+    assert!(!machines_path.join("pattern/fade").exists());
+    assert!(machines_path.join("pattern/strobe").exists());
+    assert!(common::load_space_at(&machines_path).validate().is_empty());
+
+    fs::remove_dir_all(&machines_path).unwrap();
+}
+
+#[test]
+fn test_rename_state_keeps_comments_and_layout() {
+    let machines_path = common::temp_asset_copy(LIGHTSWITCH, "rename-in-place");
+    fs::write(machines_path.join("on.yaml"), concat!(
+        "# the light is on\n",
+        "id: on\n",
+        "arrows:\n",
+        "  - id: self\n",
+        "    next: default/on\n",
+        "  - {id: off, next: 'default/off'} # switch it off\n",
+        "  - id: pattern\n",
+        "    next: default/pattern\n",
+    )).unwrap();
+
+    rename::rename_state(&machines_path, "default/off", "default/dark", RenameOptions::default()).unwrap();
+    assert_eq!(fs::read_to_string(machines_path.join("on.yaml")).unwrap(), concat!(
+        "# the light is on\n",
+        "id: on\n",
+        "arrows:\n",
+        "  - id: self\n",
+        "    next: default/on\n",
+        "  - {id: off, next: 'default/dark'} # switch it off\n",
+        "  - id: pattern\n",
+        "    next: default/pattern\n",
+    ));
+    let dark = fs::read_to_string(machines_path.join("dark.yaml")).unwrap();
+    let off = fs::read_to_string(common::asset_path(LIGHTSWITCH).join("off.yaml")).unwrap();
+    assert_eq!(dark, off.replace("id: off", "id: dark").replace("next: default/off", "next: default/dark"));
+    // no staged file is left behind
+    assert!(fs::read_dir(&machines_path).unwrap().all(|entry| {
+        !entry.unwrap().file_name().to_string_lossy().ends_with(".elea-rename")
+    }));
+
+    fs::remove_dir_all(&machines_path).unwrap();
+}
+
+#[test]
+fn test_rename_state_dry_run_writes_nothing() {
+    let machines_path = common::temp_asset_copy(LIGHTSWITCH, "rename-dry-run");
+    let before = fs::read_to_string(machines_path.join("on.yaml")).unwrap();
+
+    let report = rename::rename_state(&machines_path, "default/off", "default/dark", RenameOptions { dry_run: true }).unwrap();
+    assert_eq!(report.file_to, machines_path.join("dark.yaml"));
+    assert_eq!(report.rewritten_files, vec![machines_path.join("dark.yaml"), machines_path.join("on.yaml")]);
+    assert!(machines_path.join("off.yaml").exists());
+    assert!(!machines_path.join("dark.yaml").exists());
+    assert_eq!(fs::read_to_string(machines_path.join("on.yaml")).unwrap(), before);
+
+    fs::remove_dir_all(&machines_path).unwrap();
+}
+
+#[test]
+fn test_rename_state_errors() {
+    let machines_path = common::asset_path(LIGHTSWITCH);
+    let options = RenameOptions { dry_run: true };
+
+    match rename::rename_state(&machines_path, "default/missing", "default/other", options) {
+        Err(RenameError::UnknownState(state)) => assert_eq!(state.0, "default/missing"),
+        other => panic!("expected an unknown state, got {:?}", other),
+    }
+    match rename::rename_state(&machines_path, "default/on", "default/off", options) {
+        Err(RenameError::TargetExists(target)) => assert_eq!(target, "default/off"),
+        other => panic!("expected an existing target, got {:?}", other),
+    }
+    // states of Tree files take their ids from the tree
+    match rename::rename_state(&machines_path, "broken/fix", "broken/repair", options) {
+        Err(RenameError::Unsupported { .. }) => {},
+        other => panic!("expected an unsupported state, got {:?}", other),
+    }
+}
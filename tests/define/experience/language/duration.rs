@@ -0,0 +1,23 @@
+//!
+//! Test | Duration
+//!
+
+use std::time::Duration;
+
+use elea::define::experience::language::duration::{self, DurationError};
+
+
+#[test]
+fn test_parse_duration() {
+    assert_eq!(duration::parse_duration("10s"), Ok(Duration::from_secs(10)));
+    assert_eq!(duration::parse_duration("1m 30s"), Ok(Duration::from_secs(90)));
+    assert_eq!(duration::parse_duration("1h250ms"), Ok(Duration::from_millis(3_600_250)));
+    assert_eq!(duration::parse_duration("0s"), Err(DurationError::Zero));
+    assert_eq!(duration::parse_duration(" "), Err(DurationError::Parse { position: 1, message: "expected an amount".to_string() }));
+    assert_eq!(duration::parse_duration("10"), Err(DurationError::Parse {
+        position: 2,
+        message: "expected a unit, [ms], [s], [m] or [h]".to_string(),
+    }));
+    assert_eq!(duration::parse_duration("2 days"), Err(DurationError::Parse { position: 1, message: "expected a unit, [ms], [s], [m] or [h]".to_string() }));
+    assert_eq!(duration::parse_duration("2days"), Err(DurationError::Parse { position: 1, message: "unknown unit [days]".to_string() }));
+}
@@ -0,0 +1,145 @@
+//!
+//! Test | Effect
+//!
+
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+use crate::common;
+use elea::define::experience::language::context::{Context, Type, Value};
+use elea::define::experience::language::effect::{self, CheckError, Effect, EffectError};
+use elea::define::experience::language::guard::GuardError;
+use elea::define::experience::lint::{self, LintConfig};
+use elea::define::experience::representation::rust_minimal::{ArrowId, MachineId, StateId};
+use elea::define::experience::representation::rust_pragmatic::{
+    Arrow, Machine, Space, State, Variable
+};
+use elea::unite::runtime::{Runtime, RuntimeError};
+
+
+fn variable_lightswitch() -> Space {
+    return common::load_space("unite/variable-lightswitch");
+}
+
+fn variables() -> BTreeMap<String, Variable> {
+    return BTreeMap::from([
+        ("brightness".to_string(), Variable { type_: Type::Int, default: Value::Int(100) }),
+        ("temperature".to_string(), Variable { type_: Type::Float, default: Value::Float(20.0) }),
+        ("pattern".to_string(), Variable { type_: Type::Text, default: Value::from("fade") }),
+    ]);
+}
+
+#[test]
+fn test_parse() {
+    let effect = Effect::parse("set brightness = brightness / 2").unwrap();
+    assert_eq!(effect.variable, "brightness");
+    assert_eq!(effect.expr.to_string(), "(brightness / 2)");
+
+    let parse_error = |effect: &str| match Effect::parse(effect) {
+        Err(EffectError::Parse { position, message }) => (position, message),
+        other => panic!("{:?}", other),
+    };
+    assert_eq!(parse_error("brightness = 40"), (0, "expected [set]".to_string()));
+    assert_eq!(parse_error("set = 40"), (4, "expected a variable".to_string()));
+    assert_eq!(parse_error("set brightness == 40"), (15, "expected [=]".to_string()));
+    assert_eq!(parse_error("set brightness = 40 +"), (21, "expected a value".to_string()));
+}
+
+#[test]
+fn test_check_and_apply() {
+    let variables = variables();
+    let check = |effect: &str| Effect::parse(effect).unwrap().check(&variables);
+    assert_eq!(check("set temperature = temperature + brightness"), Ok(()));
+    assert_eq!(check("set temperature = 21"), Ok(()));
+    assert_eq!(check("set level = 3"), Err(EffectError::UndeclaredVariable("level".to_string())));
+    assert_eq!(check("set brightness = level"), Err(EffectError::UndeclaredVariable("level".to_string())));
+    assert_eq!(check("set brightness = temperature"), Err(EffectError::TypeMismatch {
+        variable: "brightness".to_string(),
+        expected: Type::Int,
+        found: Type::Float,
+    }));
+    assert_eq!(check("set pattern = pattern * 2"), Err(EffectError::Expr(GuardError::TypeMismatch {
+        operator: "*".to_string(),
+        types: vec![Type::Text, Type::Int],
+    })));
+
+    let mut context = Context::from_iter([("brightness", Value::Int(40)), ("temperature", Value::Float(20.0))]);
+    Effect::parse("set temperature = brightness").unwrap().apply(&variables, &mut context).unwrap();
+    assert_eq!(context.get("temperature"), Some(&Value::Float(40.0)));
+    assert_eq!(
+        Effect::parse("set brightness = brightness / 0").unwrap().apply(&variables, &mut context),
+        Err(EffectError::Expr(GuardError::DivisionByZero))
+    );
+}
+
+#[test]
+fn test_load_variables() {
+    let space = variable_lightswitch();
+    let machine = &space.machine_by_id[&MachineId("default".to_string())];
+    assert_eq!(machine.variables["brightness"], Variable { type_: Type::Int, default: Value::Int(100) });
+    assert_eq!(machine.variables["temperature"].default, Value::Int(20));
+    assert_eq!(effect::defaults(machine).get("temperature"), Some(&Value::Float(20.0)));
+    assert!(effect::check(machine).is_empty());
+    assert!(lint::lint(&space, &LintConfig::default()).iter().all(|finding| finding.rule != "invalid_effect"));
+}
+
+#[test]
+fn test_runtime_applies_effects() {
+    let mut runtime = Runtime::new(Arc::new(variable_lightswitch()), "default/on").unwrap();
+    assert_eq!(runtime.context().get("brightness"), Some(&Value::Int(100)));
+
+    runtime.fire("dim").unwrap();
+    runtime.fire("dim").unwrap();
+    assert_eq!(runtime.context().get("brightness"), Some(&Value::Int(25)));
+    assert_eq!(runtime.context().get("dimmed"), Some(&Value::Bool(true)));
+    runtime.fire("off").unwrap();
+    runtime.fire("on").unwrap();
+    assert_eq!(runtime.context().get("brightness"), Some(&Value::Int(100)));
+    assert_eq!(runtime.context().get("dimmed"), Some(&Value::Bool(false)));
+    assert_eq!(runtime.context().get("temperature"), Some(&Value::Float(21.0)));
+}
+
+#[test]
+fn test_failed_effects_change_nothing() {
+    let mut arrow = Arrow::new("dim", "default/dim");
+    arrow.effects = vec!["set brightness = 50".to_string(), "set brightness = brightness / level".to_string()];
+    let mut machine = Machine::new("default", vec![State::new("on", vec![arrow]), State::new("dim", Vec::new())]);
+    machine.variables = variables();
+    let space = Space::new(vec![machine]);
+
+    let context = Context::from_iter([("level", 0)]);
+    let mut runtime = Runtime::new(Arc::new(space), "default/on").unwrap().with_context(context);
+    assert_eq!(runtime.fire("dim"), Err(RuntimeError::Effect {
+        state: StateId("default/on".to_string()),
+        arrow: ArrowId("dim".to_string()),
+        effect: "set brightness = brightness / level".to_string(),
+        error: EffectError::Expr(GuardError::DivisionByZero),
+    }));
+    assert_eq!(runtime.current(), &StateId("default/on".to_string()));
+    assert_eq!(runtime.context().get("brightness"), Some(&Value::Int(100)));
+    assert!(runtime.history().is_empty());
+}
+
+#[test]
+fn test_check_machine() {
+    let mut arrow = Arrow::new("dim", "default/on");
+    arrow.effects = vec!["set brightness = 'low'".to_string(), "set level = 1".to_string()];
+    let mut machine = Machine::new("default", vec![State::new("on", vec![arrow])]);
+    machine.variables = variables();
+    machine.variables.insert("dimmed".to_string(), Variable { type_: Type::Bool, default: Value::Int(0) });
+
+    let errors = effect::check(&machine);
+    assert_eq!(errors[0], CheckError::Default { variable: "dimmed".to_string(), expected: Type::Bool, found: Type::Int });
+    assert_eq!(errors.len(), 3);
+
+    let findings = lint::lint(&Space::new(vec![machine]), &LintConfig::default());
+    let messages: Vec<(&str, &str)> = findings.iter()
+        .filter(|finding| finding.rule == "invalid_effect")
+        .map(|finding| (finding.state.0.as_str(), finding.message.as_str()))
+        .collect();
+    assert_eq!(messages, vec![
+        ("default", "default of variable [dimmed] is int, not bool"),
+        ("default/on", "effect [set brightness = 'low'] of arrow [dim] is invalid: variable [brightness] is int, not text"),
+        ("default/on", "effect [set level = 1] of arrow [dim] is invalid: undeclared variable [level]"),
+    ]);
+}
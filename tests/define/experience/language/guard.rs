@@ -0,0 +1,69 @@
+//!
+//! Test | Guard
+//!
+
+use elea::define::experience::language::context::{Context, Type, Value};
+use elea::define::experience::language::guard::{Guard, GuardError};
+
+
+fn context() -> Context {
+    return Context::from_iter([
+        ("battery_level", Value::Int(42)),
+        ("temperature", Value::Float(20.5)),
+        ("power_saving", Value::Bool(false)),
+        ("pattern", Value::from("fade")),
+    ]);
+}
+
+fn evaluate(guard: &str) -> Result<bool, GuardError> {
+    return Guard::parse(guard)?.evaluate(&context());
+}
+
+#[test]
+fn test_evaluate() {
+    assert_eq!(evaluate("battery_level < 20"), Ok(false));
+    assert_eq!(evaluate("battery_level >= 20 && !power_saving"), Ok(true));
+    assert_eq!(evaluate("not power_saving and (temperature > 30 or pattern == 'fade')"), Ok(true));
+    // integers and floats mix
+    assert_eq!(evaluate("battery_level / 2 + 0.5 == 21.5"), Ok(true));
+    assert_eq!(evaluate("battery_level % 5 == 2 && -battery_level < 0"), Ok(true));
+    assert_eq!(evaluate("pattern + \"-in\" == 'fade-in'"), Ok(true));
+    assert_eq!(evaluate("true || missing > 1"), Ok(true));
+}
+
+#[test]
+fn test_evaluation_errors() {
+    assert_eq!(evaluate("missing > 1"), Err(GuardError::UnknownVariable("missing".to_string())));
+    assert_eq!(evaluate("pattern > 20"), Err(GuardError::TypeMismatch {
+        operator: ">".to_string(),
+        types: vec![Type::Text, Type::Int],
+    }));
+    assert_eq!(evaluate("battery_level"), Err(GuardError::NotBoolean(Type::Int)));
+    assert_eq!(evaluate("power_saving || battery_level"), Err(GuardError::TypeMismatch {
+        operator: "||".to_string(),
+        types: vec![Type::Int],
+    }));
+    assert_eq!(evaluate("battery_level / 0 > 1"), Err(GuardError::DivisionByZero));
+    assert_eq!(evaluate("power_saving < true"), Err(GuardError::TypeMismatch {
+        operator: "<".to_string(),
+        types: vec![Type::Bool, Type::Bool],
+    }));
+}
+
+#[test]
+fn test_parse() {
+    let guard = Guard::parse("a + b * c < 3 || !(d == 'x')").unwrap();
+    assert_eq!(guard.expr.to_string(), "(((a + (b * c)) < 3) || !(d == \"x\"))");
+    assert_eq!(guard.variables(), vec!["a", "b", "c", "d"]);
+    assert_eq!(guard.to_string(), "a + b * c < 3 || !(d == 'x')");
+
+    let parse_error = |guard: &str| match Guard::parse(guard) {
+        Err(GuardError::Parse { position, message }) => (position, message),
+        other => panic!("{:?}", other),
+    };
+    assert_eq!(parse_error("level = 20"), (6, "unexpected [=], comparisons use [==]".to_string()));
+    assert_eq!(parse_error("a < b < c"), (6, "unexpected [<]".to_string()));
+    assert_eq!(parse_error("(a > 1"), (6, "expected [)]".to_string()));
+    assert_eq!(parse_error("a == 'open"), (5, "unterminated text".to_string()));
+    assert_eq!(parse_error("a >"), (3, "expected a value".to_string()));
+}
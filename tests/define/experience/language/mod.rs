@@ -0,0 +1,3 @@
+mod duration;
+mod effect;
+mod guard;
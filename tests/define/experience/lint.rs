@@ -0,0 +1,120 @@
+//!
+//! Test | Lint
+//!
+
+use elea::define::experience::language::context::{Type, Value};
+use elea::define::experience::lint::{self, Finding, LintConfig, Severity};
+use elea::define::experience::representation::rust_minimal::{ArrowId, StateId};
+use elea::define::experience::representation::rust_pragmatic::{
+    Arrow, Machine, Space, State, Variable
+};
+
+use crate::common;
+
+
+fn summary(findings: &[Finding]) -> Vec<(String, Severity, String, Option<String>)> {
+    return findings.iter().map(|f| {
+        (f.rule.clone(), f.severity, f.state.0.clone(), f.arrow.as_ref().map(|a| a.0.clone()))
+    }).collect();
+}
+
+#[test]
+fn test_lint_dir_with_config() {
+    let machines_path = common::asset_path("lint/lightswitch");
+    let findings = lint::lint_dir(&machines_path).unwrap();
+
+    let rule = |rule: &str, severity, state: &str, arrow: Option<&str>| {
+        (rule.to_string(), severity, state.to_string(), arrow.map(|a| a.to_string()))
+    };
+    assert_eq!(summary(&findings), vec![
+        rule("missing_description", Severity::Warning, "default/off", None),
+        rule("id_casing", Severity::Warning, "default/on", Some("goDim")),
+        rule("fan_out", Severity::Error, "default/on", None),
+        rule("unresolved_arrow", Severity::Error, "default/on", Some("goDim")),
+        rule("self_arrow", Severity::Error, "default/on", Some("self")),
+    ]);
+
+    // findings point at the file defining the state
+    assert_eq!(findings[0].file, Some(machines_path.join("off.yaml")));
+    assert_eq!(findings[1].file, Some(machines_path.join("on.yaml")));
+    assert!(findings[4].to_string().ends_with("on.yaml: error [self_arrow] default/on: arrow [self] points at [default/off]"));
+}
+
+#[test]
+fn test_lint_defaults_and_disabled_rules() {
+    let machines_path = common::asset_path("representation/filesystem/machines/nested-machines-lightswitch");
+    let findings = lint::lint_dir(&machines_path).unwrap();
+    // the fixture has no descriptions, which is only informational by default
+    assert!(findings.iter().all(|f| f.rule == "missing_description" && f.severity == Severity::Info));
+    assert_eq!(findings.len(), 11);
+
+    let mut config = LintConfig::default();
+    config.rules.missing_description.severity = Severity::Off;
+    config.rules.self_arrow.allow = vec!["self".to_string()];
+    let space = common::load_space_at(&machines_path);
+    assert!(lint::lint(&space, &config).is_empty());
+
+    config.rules.fan_out.max = 2;
+    let findings = lint::lint(&space, &config);
+    assert!(findings.iter().all(|f| f.rule == "fan_out"));
+    assert!(findings.iter().any(|f| f.state == StateId("broken/fix".to_string()) && f.arrow.is_none()));
+    assert!(!findings.iter().any(|f| f.arrow == Some(ArrowId("self".to_string()))));
+}
+
+#[test]
+fn test_lint_invalid_guards() {
+    let mut arrow = Arrow::new("strobe", "default/on");
+    arrow.guard = Some("battery_level => 20".to_string());
+    let space = Space::new(vec![Machine::new("default", vec![State::new("on", vec![arrow])])]);
+    let findings = lint::lint(&space, &LintConfig::default());
+    let finding = findings.iter().find(|finding| finding.rule == "invalid_guard").unwrap();
+    assert_eq!(finding.arrow, Some(ArrowId("strobe".to_string())));
+    assert_eq!(finding.message, "guard of arrow [strobe] is invalid: at 14: unexpected [=], comparisons use [==]");
+
+    let space = common::load_space("unite/guarded-lightswitch");
+    assert!(lint::lint(&space, &LintConfig::default()).iter().all(|finding| finding.rule != "invalid_guard"));
+}
+
+#[test]
+fn test_lint_unindexed_space() {
+    // a deserialized Space has no machine index
+    let mut arrow = Arrow::new("dim", "default/on");
+    arrow.effects = vec!["set brightness = 50".to_string()];
+    let mut space = Space::new(vec![Machine::new("default", vec![State::new("on", vec![arrow])])]);
+    space.machine_by_id.clear();
+    let findings = lint::lint(&space, &LintConfig::default());
+    let finding = findings.iter().find(|finding| finding.rule == "invalid_effect").unwrap();
+    assert_eq!(finding.message, "effects of arrow [dim] cannot be checked, machine [default] is not indexed");
+}
+
+#[test]
+fn test_lint_conflicting_variables() {
+    let mut counter = Machine::new("counter", vec![State::new("idle", Vec::new())]);
+    counter.variables.insert("level".to_string(), Variable { type_: Type::Int, default: Value::Int(0) });
+    let mut switch = Machine::new("switch", vec![State::new("off", Vec::new())]);
+    switch.variables.insert("level".to_string(), Variable { type_: Type::Bool, default: Value::Bool(false) });
+    let findings = lint::lint(&Space::new(vec![counter, switch]), &LintConfig::default());
+    let conflicts: Vec<(&str, &str)> = findings.iter()
+        .filter(|finding| finding.rule == "invalid_effect")
+        .map(|finding| (finding.state.0.as_str(), finding.message.as_str()))
+        .collect();
+    assert_eq!(conflicts, vec![("switch", "variable [level] is declared differently by [counter] and [switch]")]);
+}
+
+#[test]
+fn test_lint_id_casing_once_per_id() {
+    let space = Space::new(vec![Machine::new("Lightswitch", vec![
+        State::new("on", Vec::new()),
+        State::new("Broken/fix", Vec::new()),
+        State::new("Broken/diagnose", Vec::new()),
+    ])]);
+    let findings = lint::lint(&space, &LintConfig::default());
+    let casing: Vec<(&str, &str)> = findings.iter()
+        .filter(|finding| finding.rule == "id_casing")
+        .map(|finding| (finding.state.0.as_str(), finding.message.as_str()))
+        .collect();
+    assert_eq!(casing, vec![
+        ("Lightswitch", "id [Lightswitch] is not snake_case"),
+        ("Lightswitch/Broken/diagnose", "id [Broken] is not snake_case"),
+    ]);
+}
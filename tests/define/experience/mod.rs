@@ -1 +1,3 @@
+mod language;
+mod lint;
 mod representation;
@@ -0,0 +1,66 @@
+//!
+//! Test | Canonical Representation
+//!
+
+use elea::define::experience::language::context::{Type, Value};
+use elea::define::experience::representation::canonical::Canonical;
+use elea::define::experience::representation::filesystem;
+use elea::define::experience::representation::rust_pragmatic::{
+    Arrow, Machine, Space, State, Variable
+};
+
+use crate::common;
+
+
+fn lightswitch() -> Space {
+    return common::load_space("representation/filesystem/machines/nested-machines-lightswitch");
+}
+
+#[test]
+fn test_machines_are_loaded_in_id_order() {
+    let machines_path = common::asset_path("representation/filesystem/machines/nested-machines-lightswitch");
+    let machines = filesystem::to_machines(machines_path.as_path(), filesystem::Format::NestedMachines).unwrap();
+    let ids: Vec<&str> = machines.iter().map(|m| m.id.0.as_str()).collect();
+    assert_eq!(ids, vec!["broken", "default", "pattern"]);
+}
+
+#[test]
+fn test_content_hash_is_stable() {
+    let space = lightswitch();
+    assert_eq!(space.canonical(), lightswitch().canonical());
+    assert_eq!(space.content_hash().len(), 64);
+    assert_eq!(
+        space.content_hash(),
+        "99fdca713e4267a45eee7b95e89ab4c172466911e7ca8677440158e237290dac"
+    );
+    let machine_ids: Vec<String> = space.machine_hashes().into_iter().map(|(id, _)| id).collect();
+    assert_eq!(machine_ids, vec!["broken", "default", "pattern"]);
+}
+
+#[test]
+fn test_content_hash_ignores_order_but_not_content() {
+    let machine = Machine::new("default", vec![
+       State::new("on", vec![Arrow::new("off", "default/off")]),
+       State::new("off", vec![Arrow::new("on", "default/on"), Arrow::new("self", "default/off")]),
+    ]);
+
+    let mut reordered = machine.clone();
+    reordered.states.reverse();
+    reordered.states[0].arrows.reverse();
+    assert_eq!(machine.content_hash(), reordered.content_hash());
+
+    let mut retargeted = machine.clone();
+    retargeted.states[0].arrows[0].next.0 = "default/pattern".to_string();
+    assert_ne!(machine.content_hash(), retargeted.content_hash());
+
+    let mut described = machine.clone();
+    described.states[1].description = Some("Light is on".to_string());
+    assert_ne!(machine.content_hash(), described.content_hash());
+    assert_ne!(machine.states[1].content_hash(), described.states[1].content_hash());
+    let mut dimmable = machine.clone();
+    dimmable.variables.insert("brightness".to_string(), Variable { type_: Type::Int, default: Value::Int(100) });
+    assert_ne!(machine.content_hash(), dimmable.content_hash());
+    let mut dimmed = dimmable.clone();
+    dimmed.variables.insert("brightness".to_string(), Variable { type_: Type::Int, default: Value::Int(40) });
+    assert_ne!(dimmable.content_hash(), dimmed.content_hash());
+}
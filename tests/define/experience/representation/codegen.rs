@@ -0,0 +1,6 @@
+//!
+//! Test | Code Generation
+//!
+
+mod rust;
+mod typescript;
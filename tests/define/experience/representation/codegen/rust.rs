@@ -0,0 +1,92 @@
+//!
+//! Test | Code Generation | Rust
+//!
+
+use std::fs;
+
+use crate::common;
+use elea::define::experience::representation::codegen::{self, CodegenError};
+use elea::define::experience::representation::rust_pragmatic::{
+    Arrow, Machine, Space, State
+};
+
+#[allow(dead_code)]
+mod lightswitch {
+    include!("../../../../../assets/test/codegen/lightswitch.rs");
+}
+
+use lightswitch::{default, pattern, SpaceState};
+
+#[allow(dead_code)]
+mod prelude_names {
+    include!("../../../../../assets/test/codegen/prelude-names.rs");
+}
+
+
+const LIGHTSWITCH: &str = "representation/filesystem/machines/nested-machines-lightswitch";
+
+#[test]
+fn test_generate_matches_checked_in_code() {
+    let space = codegen::load_space(&common::asset_path(LIGHTSWITCH)).unwrap();
+    let code = codegen::rust::generate(&space).unwrap();
+    let expected = fs::read_to_string(common::asset_path("codegen/lightswitch.rs")).unwrap();
+    assert_eq!(code, expected);
+}
+
+#[test]
+fn test_generated_transitions() {
+    let next = default::On.transition(default::OnArrow::Pattern);
+    assert_eq!(next, SpaceState::Default(default::State::Pattern));
+    assert_eq!(next.id(), "default/pattern");
+
+    let next = default::Pattern.transition(default::PatternArrow::Fade);
+    assert_eq!(next, SpaceState::Pattern(pattern::State::FadeLevels));
+    assert_eq!(pattern::FadeLevels.transition(pattern::FadeLevelsArrow::_20Percent).id(), "default/on");
+    assert_eq!(default::OnArrow::Self_.id(), "self");
+    assert_eq!(default::State::from(default::Off), default::Off::STATE);
+}
+
+fn prelude_names() -> Space {
+    return Space::new(vec![
+        Machine::new("convert", vec![
+            State::new("from", vec![Arrow::new("into", "convert/into")]),
+            State::new("into", vec![Arrow::new("from", "convert/from")]),
+        ]),
+    ]);
+}
+
+#[test]
+fn test_generate_states_named_after_prelude_traits() {
+    let code = codegen::rust::generate(&prelude_names()).unwrap();
+    let expected = fs::read_to_string(common::asset_path("codegen/prelude-names.rs")).unwrap();
+    assert_eq!(code, expected);
+
+    let next = prelude_names::convert::From.transition(prelude_names::convert::FromArrow::Into);
+    assert_eq!(next, prelude_names::SpaceState::Convert(prelude_names::convert::State::Into));
+    assert_eq!(prelude_names::convert::State::from(prelude_names::convert::Into), prelude_names::convert::Into::STATE);
+}
+
+#[test]
+fn test_generate_rejects_colliding_names() {
+    let space = Space::new(vec![
+        Machine::new("default", vec![
+            State::new("fade_levels", vec![]),
+            State::new("fade/levels", vec![Arrow::new("back", "default/fade_levels")]),
+        ]),
+    ]);
+    match codegen::rust::generate(&space) {
+        Err(CodegenError::NameCollision { name, .. }) => assert_eq!(name, "FadeLevels"),
+        other => panic!("expected a name collision, got {:?}", other.map(|_| ())),
+    }
+}
+
+#[test]
+fn test_generate_rejects_invalid_spaces() {
+    let space = Space::new(vec![
+        Machine::new("default", vec![State::new("on", vec![Arrow::new("off", "default/off")])]),
+    ]);
+    match codegen::rust::generate(&space) {
+        Err(CodegenError::Space(errors)) => assert_eq!(errors.len(), 1),
+        other => panic!("expected an invalid space, got {:?}", other.map(|_| ())),
+    }
+}
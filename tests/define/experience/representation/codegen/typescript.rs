@@ -0,0 +1,43 @@
+//!
+//! Test | Code Generation | TypeScript
+//!
+
+use std::fs;
+
+use crate::common;
+use elea::define::experience::representation::codegen::{self, CodegenError};
+use elea::define::experience::representation::rust_pragmatic::{
+    Arrow, Machine, Space, State
+};
+
+
+const LIGHTSWITCH: &str = "representation/filesystem/machines/nested-machines-lightswitch";
+
+#[test]
+fn test_generate_matches_checked_in_code() {
+    let space = codegen::load_space(&common::asset_path(LIGHTSWITCH)).unwrap();
+    let code = codegen::typescript::generate(&space).unwrap();
+    let expected = fs::read_to_string(common::asset_path("codegen/lightswitch.ts")).unwrap();
+    assert_eq!(code, expected);
+}
+
+#[test]
+fn test_generate_escapes_ids_and_rejects_invalid_spaces() {
+    let space = Space::new(vec![
+        Machine::new("default", vec![
+            State::new("say\"hi\"", vec![Arrow::new("done", "default/idle")]),
+            State::new("idle", vec![]),
+        ]),
+    ]);
+    let code = codegen::typescript::generate(&space).unwrap();
+    assert!(code.contains(r#""default/say\"hi\"": {"#));
+    assert!(code.contains(r#""default/idle": never;"#));
+
+    let space = Space::new(vec![
+        Machine::new("default", vec![State::new("on", vec![Arrow::new("off", "default/off")])]),
+    ]);
+    match codegen::typescript::generate(&space) {
+        Err(CodegenError::Space(errors)) => assert_eq!(errors.len(), 1),
+        other => panic!("expected an invalid space, got {:?}", other.map(|_| ())),
+    }
+}
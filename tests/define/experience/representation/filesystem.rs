@@ -8,71 +8,46 @@ use elea::define::experience::representation::rust_pragmatic::{
     Arrow, Machine, State, Space
 };
 use elea::define::experience::representation::filesystem;
+use elea::define::experience::representation::filesystem::{
+    FormatNestedMachinesError, IncludeError, MachinesError
+};
 
 use crate::common;
 
+mod cache;
+mod formatter;
+mod watch;
+
 
 #[test]
 fn test_format_nested_machines() {
 
-    let machine_default = Machine::new("default", vec![
-       State::new("on", vec![
-          Arrow::new("self", "default/on"),
-          Arrow::new("off", "default/off"),
-          Arrow::new("pattern", "default/pattern"),
-       ]),
-       State::new("off", vec![
-          Arrow::new("on", "default/on"),
-          Arrow::new("self", "default/off"),
-       ]),
-       State::new("pattern", vec![
-          Arrow::new("fade", "pattern/fade/levels"),
-          Arrow::new("strobe", "pattern/strobe/speeds"),
-       ]),
-    ]);
-    let machine_pattern =  Machine::new("pattern", vec![
-       State::new("fade/levels", vec![
-          Arrow::new("20_percent", "default/on"),
-          Arrow::new("40_percent", "default/on"),
-          Arrow::new("60_percent", "default/on"),
-          Arrow::new("80_percent", "default/on"),
-          Arrow::new("100_percent", "default/on"),
-       ]),
-       State::new("strobe/speeds", vec![
-          Arrow::new("slow", "default/on"),
-          Arrow::new("medium", "default/on"),
-          Arrow::new("fast", "default/on"),
-       ]),
-    ]);
-    let machine_broken = Machine::new("broken", vec![
-       State::new("fix", vec![
-          Arrow::new("power", "broken/fix/power"),
-          Arrow::new("light", "broken/fix/light"),
-          Arrow::new("structure", "broken/fix/structure"),
-       ]),
-       State::new("fix/power", vec![
-          Arrow::new("battery", "broken/fix/power/battery"),
-          Arrow::new("miscellaneous", "broken/fix/power/miscellaneous"),
-       ]),
-       State::new("fix/power/battery", vec![
-          Arrow::new("replace", "broken/fix"),
-          Arrow::new("charge", "broken/fix"),
-          Arrow::new("plug_in", "broken/fix"),
-       ]),
-       State::new("fix/power/miscellaneous", vec![
-          Arrow::new("check_outlet_power", "broken/fix"),
-          Arrow::new("check_cord", "broken/fix"),
-       ]),
-       State::new("fix/light", vec![
-          Arrow::new("change_bulb", "broken/fix"),
-       ]),
-       State::new("fix/structure", vec![
-          Arrow::new("cord", "broken/fix"),
-          Arrow::new("casing", "broken/fix"),
-       ]),
-    ]);
-    let mut target_machines = vec![machine_default, machine_broken, machine_pattern]; 
-    target_machines.sort_by(|m1,m2| m1.id.cmp(&m2.id));
+    let target_space = elea::space! {
+        default {
+            on { self -> default/on, off -> default/off, pattern -> default/pattern }
+            off { on -> default/on, self -> default/off }
+            pattern { fade -> pattern/fade/levels, strobe -> pattern/strobe/speeds }
+        }
+        pattern {
+            fade/levels {
+                20_percent -> default/on,
+                40_percent -> default/on,
+                60_percent -> default/on,
+                80_percent -> default/on,
+                100_percent -> default/on,
+            }
+            strobe/speeds { slow -> default/on, medium -> default/on, fast -> default/on }
+        }
+        broken {
+            fix { power -> broken/fix/power, light -> broken/fix/light, structure -> broken/fix/structure }
+            fix/power { battery -> broken/fix/power/battery, miscellaneous -> broken/fix/power/miscellaneous }
+            fix/power/battery { replace -> broken/fix, charge -> broken/fix, plug_in -> broken/fix }
+            fix/power/miscellaneous { check_outlet_power -> broken/fix, check_cord -> broken/fix }
+            fix/light { change_bulb -> broken/fix }
+            fix/structure { cord -> broken/fix, casing -> broken/fix }
+        }
+    };
+    let target_machines = target_space.machines;
 
     // todo verify state paths
 
@@ -80,8 +55,7 @@ fn test_format_nested_machines() {
     let actual_machines_res = filesystem::to_machines(machines_path.as_path(), filesystem::Format::NestedMachines);
 
     if actual_machines_res.is_err() {
-        println!("{:?}", actual_machines_res);
-        assert!(false);
+        panic!("{:?}", actual_machines_res);
     }
     let mut actual_machines = actual_machines_res.unwrap();
     actual_machines.sort_by(|m1,m2| m1.id.cmp(&m2.id));
@@ -93,15 +67,18 @@ fn test_format_nested_machines() {
 }
 
 /// Useful for debugging failed tests
+#[allow(dead_code)]
 fn write_machine_files(space1: &Space, space2: &Space) {
     let f_space1 = std::fs::OpenOptions::new()
         .write(true)
         .create(true)
+        .truncate(true)
         .open("space1.yml")
         .expect("Couldn't open file");
     let f_space2 = std::fs::OpenOptions::new()
         .write(true)
         .create(true)
+        .truncate(true)
         .open("space2.yml")
         .expect("Couldn't open file");
     serde_yaml::to_writer(f_space1, &space1).unwrap();
@@ -109,3 +86,92 @@ fn write_machine_files(space1: &Space, space2: &Space) {
 }
 
 
+
+/// The troubleshooting tree of `broken/fixes.yaml`, placed in `machine_id`
+fn troubleshooting_machine(machine_id: &str) -> Machine {
+    let id = |state: &str| machine_id.to_string() + "/" + state;
+    return Machine::new(machine_id, vec![
+       State::new("fix", vec![
+          Arrow::new("power", &id("fix/power")),
+          Arrow::new("light", &id("fix/light")),
+          Arrow::new("structure", &id("fix/structure")),
+       ]),
+       State::new("fix/power", vec![
+          Arrow::new("battery", &id("fix/power/battery")),
+          Arrow::new("miscellaneous", &id("fix/power/miscellaneous")),
+       ]),
+       State::new("fix/power/battery", vec![
+          Arrow::new("replace", &id("fix")),
+          Arrow::new("charge", &id("fix")),
+          Arrow::new("plug_in", &id("fix")),
+       ]),
+       State::new("fix/power/miscellaneous", vec![
+          Arrow::new("check_outlet_power", &id("fix")),
+          Arrow::new("check_cord", &id("fix")),
+       ]),
+       State::new("fix/light", vec![
+          Arrow::new("change_bulb", &id("fix")),
+       ]),
+       State::new("fix/structure", vec![
+          Arrow::new("cord", &id("fix")),
+          Arrow::new("casing", &id("fix")),
+       ]),
+    ]);
+}
+
+#[test]
+fn test_format_nested_machines_includes() {
+
+    let machine_default = Machine::new("default", vec![
+       State::new("on", vec![
+          Arrow::new("off", "default/off"),
+          Arrow::new("break", "broken/fix"),
+       ]),
+       State::new("off", vec![
+          Arrow::new("on", "default/on"),
+       ]),
+    ]);
+    // included directory, mounted at the including file's machine
+    let machine_broken = troubleshooting_machine("broken");
+    // included file, mounted in the `repair` namespace
+    let machine_repair = troubleshooting_machine("repair");
+    let mut target_machines = vec![machine_default, machine_broken, machine_repair];
+    target_machines.sort_by(|m1,m2| m1.id.cmp(&m2.id));
+
+    let machines_path: PathBuf = common::asset_path("representation/filesystem/includes/lightswitch");
+    let mut actual_machines = filesystem::to_machines(machines_path.as_path(), filesystem::Format::NestedMachines).unwrap();
+    actual_machines.sort_by(|m1,m2| m1.id.cmp(&m2.id));
+
+    assert_eq!(target_machines, actual_machines);
+}
+
+#[test]
+fn test_format_nested_machines_include_cycle() {
+    let machines_path: PathBuf = common::asset_path("representation/filesystem/includes/cycle");
+    let result = filesystem::to_machines(machines_path.as_path(), filesystem::Format::NestedMachines);
+
+    match result {
+        Err(MachinesError::NestedMachines(FormatNestedMachinesError::Include(IncludeError::Cycle(chain)))) => {
+            assert!(chain.len() >= 2);
+            assert!(chain[..chain.len() - 1].contains(chain.last().unwrap()));
+        },
+        other => panic!("expected an include cycle, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_format_nested_machines_include_target_outside_mount() {
+    // `switch/on` was written for the default machine, so its `off` arrow
+    // would lead out of the `switch` namespace it is included in
+    let machines_path: PathBuf = common::asset_path("representation/filesystem/includes/misplaced");
+    let result = filesystem::to_machines(machines_path.as_path(), filesystem::Format::NestedMachines);
+
+    match result {
+        Err(MachinesError::NestedMachines(FormatNestedMachinesError::Include(IncludeError::TargetOutsideMount {
+            state, arrow, next, mount, ..
+        }))) => {
+            assert_eq!((state.as_str(), arrow.as_str(), next.as_str(), mount.as_str()), ("switch/on", "off", "default/off", "switch"));
+        },
+        other => panic!("expected a target outside the mount, got {:?}", other),
+    }
+}
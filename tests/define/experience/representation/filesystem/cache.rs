@@ -0,0 +1,51 @@
+//!
+//! Test | Filesystem Representations | Cache
+//!
+
+use std::fs;
+
+use elea::define::experience::representation::filesystem;
+use elea::define::experience::representation::filesystem::LoadOptions;
+
+use crate::common;
+
+
+#[test]
+fn test_parallel_and_cached_loads_match() {
+    let machines_path = common::temp_asset_copy(
+        "representation/filesystem/machines/nested-machines-lightswitch",
+        "cache",
+    );
+    let cache_dir = machines_path.with_extension("cache");
+    let _ = fs::remove_dir_all(&cache_dir);
+
+    let load = |options: &LoadOptions| {
+        let mut machines = filesystem::to_machines_with(&machines_path, filesystem::Format::NestedMachines, options).unwrap();
+        machines.sort_by(|m1, m2| m1.id.cmp(&m2.id));
+        machines
+    };
+    let sequential = load(&LoadOptions { threads: Some(1), cache_dir: None });
+    assert_eq!(sequential, load(&LoadOptions { threads: Some(4), cache_dir: None }));
+
+    let cached = LoadOptions { threads: None, cache_dir: Some(cache_dir.clone()) };
+    assert_eq!(sequential, load(&cached));
+    // one entry per state file
+    assert_eq!(fs::read_dir(&cache_dir).unwrap().count(), 6);
+    assert_eq!(sequential, load(&cached));
+    assert_eq!(fs::read_dir(&cache_dir).unwrap().count(), 6);
+
+    // a changed file gets a new entry and its new states
+    fs::write(machines_path.join("off.yaml"), "\
+id: off
+arrows:
+  - id: on
+    next: default/on
+").unwrap();
+    let changed = load(&cached);
+    assert_eq!(fs::read_dir(&cache_dir).unwrap().count(), 7);
+    let default = changed.iter().find(|m| m.id.0 == "default").unwrap();
+    assert_eq!(default.states.iter().find(|s| s.id.0 == "off").unwrap().arrows.len(), 1);
+
+    fs::remove_dir_all(&machines_path).unwrap();
+    fs::remove_dir_all(&cache_dir).unwrap();
+}
@@ -0,0 +1,99 @@
+//!
+//! Test | Filesystem Representations | Formatter
+//!
+
+use std::fs;
+
+use elea::define::experience::representation::filesystem::formatter::{
+    self, FormatMode, FormatterError, Preserved
+};
+
+use crate::common;
+
+
+#[test]
+fn test_format_dir_check_and_write() {
+    let machines_path = common::temp_asset_copy("formatter/unformatted", "formatter");
+    let formatted_path = common::asset_path("formatter/formatted");
+    let files = ["on.yaml", "list.yaml", "broken/fixes.yaml"];
+
+    let original = fs::read_to_string(machines_path.join("on.yaml")).unwrap();
+    let commented = fs::read_to_string(machines_path.join("commented.yaml")).unwrap();
+    let described = fs::read_to_string(machines_path.join("described.yaml")).unwrap();
+
+    // check mode reports without changing anything
+    let report = formatter::format_dir(&machines_path, FormatMode::Check).unwrap();
+    assert_eq!(report.unformatted, vec![
+        machines_path.join("broken/fixes.yaml"),
+        machines_path.join("list.yaml"),
+        machines_path.join("on.yaml"),
+    ]);
+    // files whose comments or block scalars would be lost are skipped
+    assert_eq!(report.skipped, vec![
+        (machines_path.join("commented.yaml"), Preserved::Comment { line: 2 }),
+        (machines_path.join("described.yaml"), Preserved::BlockScalar { line: 2 }),
+    ]);
+    assert_eq!(fs::read_to_string(machines_path.join("on.yaml")).unwrap(), original);
+
+    let rewritten = formatter::format_dir(&machines_path, FormatMode::Write).unwrap();
+    assert_eq!(rewritten, report);
+    assert_eq!(fs::read_to_string(machines_path.join("commented.yaml")).unwrap(), commented);
+    assert_eq!(fs::read_to_string(machines_path.join("described.yaml")).unwrap(), described);
+    for file in files {
+        assert_eq!(
+            fs::read_to_string(machines_path.join(file)).unwrap(),
+            fs::read_to_string(formatted_path.join(file)).unwrap(),
+            "{}", file
+        );
+    }
+    // hidden files are left alone
+    assert_eq!(fs::read_to_string(machines_path.join(".hidden/skip.yaml")).unwrap(), "b:   1\n");
+
+    assert!(formatter::format_dir(&machines_path, FormatMode::Check).unwrap().unformatted.is_empty());
+    fs::remove_dir_all(&machines_path).unwrap();
+}
+
+#[test]
+fn test_format_yaml_is_idempotent_and_keeps_values() {
+    let machines_path = common::asset_path("representation/filesystem/machines/nested-machines-lightswitch");
+    for file in ["on.yaml", "pattern/fade/levels.yaml", "broken/fixes.yaml"] {
+        let content = fs::read_to_string(machines_path.join(file)).unwrap();
+        let formatted = formatter::format_yaml(&content).unwrap();
+        assert_eq!(formatter::format_yaml(&formatted).unwrap(), formatted);
+        let before: serde_yaml::Value = serde_yaml::from_str(&content).unwrap();
+        let after: serde_yaml::Value = serde_yaml::from_str(&formatted).unwrap();
+        assert_eq!(before, after);
+    }
+}
+
+#[test]
+fn test_format_yaml_refuses_comments() {
+    assert!(matches!(
+        formatter::format_yaml("id: on\narrows: [] # none yet\n"),
+        Err(FormatterError::Preserved { preserved: Preserved::Comment { line: 2 }, .. })
+    ));
+    assert!(matches!(
+        formatter::format_yaml("id: on\ndescription: >-\n  Light\n  is on\n"),
+        Err(FormatterError::Preserved { preserved: Preserved::BlockScalar { line: 2 }, .. })
+    ));
+    // a `#` within quotes or a word is text
+    let formatted = formatter::format_yaml("id: on\ndescription: 'Light #1 is on'\nnext: a#b\n").unwrap();
+    assert_eq!(formatted, "id: on\nnext: a#b\ndescription: 'Light #1 is on'\n");
+}
+
+#[test]
+fn test_format_yaml_refuses_anchors_tags_and_document_markers() {
+    let preserved = |content: &str| match formatter::format_yaml(content) {
+        Err(FormatterError::Preserved { preserved, .. }) => Some(preserved),
+        _ => None,
+    };
+    assert_eq!(preserved("id: on\nnext: &on default/on\nafter: *on\n"), Some(Preserved::Anchor { line: 2 }));
+    assert_eq!(preserved("id: on\nnext: [&on a, *on]\n"), Some(Preserved::Anchor { line: 2 }));
+    assert_eq!(preserved("id: on\nweight: !!float 1\n"), Some(Preserved::Tag { line: 2 }));
+    assert_eq!(preserved("- !state on\n"), Some(Preserved::Tag { line: 1 }));
+    assert_eq!(preserved("---\nid: on\n"), Some(Preserved::DocumentMarker { line: 1 }));
+    assert_eq!(preserved("id: on\n...\n"), Some(Preserved::DocumentMarker { line: 2 }));
+    // within a value or quotes they are text
+    let formatted = formatter::format_yaml("id: on\nguard: a * b != c & d\ndescription: '&on *on !on'\n").unwrap();
+    assert_eq!(formatted, "id: on\ndescription: '&on *on !on'\nguard: a * b != c & d\n");
+}
@@ -0,0 +1,100 @@
+//!
+//! Test | Filesystem Representations | Watch
+//!
+
+use std::fs;
+
+use elea::define::experience::representation::filesystem::watch::Watcher;
+use elea::define::experience::representation::rust_minimal::{ArrowId, StateId};
+use elea::define::experience::representation::rust_pragmatic::SpaceError;
+
+use crate::common;
+
+
+#[test]
+fn test_watcher_reloads_changed_files() {
+    let machines_path = common::temp_asset_copy(
+        "representation/filesystem/machines/nested-machines-lightswitch",
+        "watch",
+    );
+    let mut watcher = Watcher::new(&machines_path).unwrap();
+    assert!(watcher.poll().unwrap().is_none());
+
+    // modified file: only its state changes
+    fs::write(machines_path.join("off.yaml"), "\
+id: off
+arrows:
+  - id: self
+    next: default/off
+  - id: on
+    next: default/on
+  - id: break
+    next: broken/fix
+").unwrap();
+    let report = watcher.poll().unwrap().unwrap();
+    assert!(!report.full_reload);
+    assert_eq!(report.modified_files, vec![machines_path.join("off.yaml")]);
+    assert_eq!(report.changed_states, vec![StateId("default/off".to_string())]);
+    assert!(report.added_states.is_empty() && report.removed_states.is_empty());
+    assert!(report.errors.is_empty());
+    assert_eq!(watcher.space().resolve("default/off").unwrap().arrows.len(), 3);
+
+    // new file with an arrow to a missing state
+    fs::write(machines_path.join("pattern").join("pulse.yaml"), "\
+id: pulse
+arrows:
+  - id: stop
+    next: default/dim
+").unwrap();
+    let report = watcher.poll().unwrap().unwrap();
+    assert_eq!(report.added_states, vec![StateId("pattern/pulse".to_string())]);
+    assert_eq!(report.errors, vec![SpaceError::UnresolvedArrow {
+        state: StateId("pattern/pulse".to_string()),
+        arrow: ArrowId("stop".to_string()),
+        next: StateId("default/dim".to_string()),
+    }]);
+
+    // removed file: its whole tree is gone, and the arrow to it is reported
+    fs::remove_file(machines_path.join("broken").join("fixes.yaml")).unwrap();
+    let report = watcher.poll().unwrap().unwrap();
+    assert_eq!(report.removed_states.len(), 6);
+    assert!(watcher.space().resolve("broken/fix").is_none());
+    assert!(report.errors.iter().any(|e| matches!(e, SpaceError::UnresolvedArrow { next, .. } if next.0 == "broken/fix")));
+
+    fs::remove_dir_all(&machines_path).unwrap();
+}
+
+#[test]
+fn test_watcher_reloads_everything_for_include_files() {
+    let root = common::temp_asset_copy("representation/filesystem/includes", "watch-includes");
+    let machines_path = root.join("lightswitch");
+    let mut watcher = Watcher::new(&machines_path).unwrap();
+
+    // files of included directories are watched too
+    let shared_fixes = root.join("shared").join("troubleshooting").join("fixes.yaml");
+    fs::write(&shared_fixes, "\
+id: fix
+tree:
+  - id: light
+    tree:
+      - id: change_bulb
+").unwrap();
+    let report = watcher.poll().unwrap().unwrap();
+    assert!(!report.full_reload);
+    assert!(watcher.space().resolve("broken/fix/power").is_none());
+    assert!(watcher.space().resolve("broken/fix/light").is_some());
+
+    // an include file change reloads the whole directory
+    fs::write(machines_path.join("repair.yaml"), "\
+include:
+  - path: ../shared/troubleshooting/fixes.yaml
+    as: mend
+").unwrap();
+    let report = watcher.poll().unwrap().unwrap();
+    assert!(report.full_reload);
+    assert!(report.added_states.contains(&StateId("mend/fix".to_string())));
+    assert!(report.removed_states.contains(&StateId("repair/fix".to_string())));
+    assert!(watcher.space().resolve("repair/fix").is_none());
+
+    fs::remove_dir_all(&root).unwrap();
+}
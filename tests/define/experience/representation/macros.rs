@@ -0,0 +1,44 @@
+//!
+//! Test | Macros
+//!
+
+use elea::define::experience::representation::rust_pragmatic::{
+    Arrow, Machine, Space, State
+};
+
+
+#[test]
+fn test_space_macro_matches_constructors() {
+    let space = elea::space! {
+        default {
+            on { off -> default/off, self -> default/on }
+            off { on -> default/on }
+        }
+        pattern {
+            fade/levels { 20_percent -> default/on }
+        }
+    };
+    let expected = Space::new(vec![
+        Machine::new("default", vec![
+            State::new("on", vec![Arrow::new("off", "default/off"), Arrow::new("self", "default/on")]),
+            State::new("off", vec![Arrow::new("on", "default/on")]),
+        ]),
+        Machine::new("pattern", vec![
+            State::new("fade/levels", vec![Arrow::new("20_percent", "default/on")]),
+        ]),
+    ]);
+    assert_eq!(space.machines, expected.machines);
+    assert!(space.validate().is_empty());
+}
+
+#[test]
+fn test_space_macro_empty_machines() {
+    let space = elea::space! {
+        default {}
+        idle { waiting {} }
+    };
+    assert_eq!(space.machines.len(), 2);
+    assert!(space.machines[0].states.is_empty());
+    assert!(space.resolve("idle/waiting").unwrap().arrows.is_empty());
+    assert!(elea::space! {}.machines.is_empty());
+}
@@ -1 +1,5 @@
+mod canonical;
+mod codegen;
 mod filesystem;
+mod macros;
+mod qualified_id;
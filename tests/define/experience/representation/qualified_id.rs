@@ -0,0 +1,104 @@
+//!
+//! Test | Qualified State Ids
+//!
+
+use elea::define::experience::representation::filesystem;
+use elea::define::experience::representation::qualified_id::{
+    QualifiedStateId, QualifiedStateIdError
+};
+use elea::define::experience::representation::rust_minimal::{ArrowId, MachineId, StateId};
+use elea::define::experience::representation::rust_pragmatic::{
+    Arrow, Machine, Space, SpaceError, State
+};
+
+use crate::common;
+
+
+#[test]
+fn test_parse_and_path_operations() {
+    let id = QualifiedStateId::parse("broken/fix/power/battery").unwrap();
+
+    assert_eq!(id.machine().0, "broken");
+    assert_eq!(id.path(), &["fix", "power", "battery"]);
+    assert_eq!(id.name(), Some("battery"));
+    assert_eq!(id.local_id().0, "fix/power/battery");
+    assert_eq!(id.to_string(), "broken/fix/power/battery");
+
+    let parent = id.parent().unwrap();
+    assert_eq!(parent.to_string(), "broken/fix/power");
+    assert!(parent.is_parent_of(&id));
+    assert_eq!(parent.child("miscellaneous").unwrap().to_string(), "broken/fix/power/miscellaneous");
+
+    let root = QualifiedStateId::machine_root("broken").unwrap();
+    assert!(root.is_machine_root());
+    assert_eq!(root.join("fix/power/battery").unwrap(), id);
+    assert!(root.contains(&id));
+    assert!(!id.contains(&root));
+}
+
+#[test]
+fn test_parse_rejects_invalid_ids() {
+    assert_eq!(QualifiedStateId::parse(""), Err(QualifiedStateIdError::Empty));
+    assert_eq!(
+        QualifiedStateId::parse("broken"),
+        Err(QualifiedStateIdError::MissingStatePath("broken".to_string()))
+    );
+    assert_eq!(QualifiedStateId::parse("broken//power"), Err(QualifiedStateIdError::EmptySegment));
+    assert_eq!(QualifiedStateId::parse("broken/fix/"), Err(QualifiedStateIdError::EmptySegment));
+    assert_eq!(
+        QualifiedStateId::parse("broken/../on"),
+        Err(QualifiedStateIdError::IllegalSegment("..".to_string()))
+    );
+    assert_eq!(
+        QualifiedStateId::parse("broken/fix power"),
+        Err(QualifiedStateIdError::IllegalSegment("fix power".to_string()))
+    );
+    let root = QualifiedStateId::machine_root("broken").unwrap();
+    assert!(root.child("fix/power").is_err());
+}
+
+#[test]
+fn test_space_resolve() {
+    let machines_path = common::asset_path("representation/filesystem/machines/nested-machines-lightswitch");
+    let machines = filesystem::to_machines(machines_path.as_path(), filesystem::Format::NestedMachines).unwrap();
+    let space = Space::new(machines);
+
+    let battery = space.resolve("broken/fix/power/battery").unwrap();
+    assert_eq!(battery.id.0, "fix/power/battery");
+    assert_eq!(space.resolve("default/on").unwrap().id.0, "on");
+    assert_eq!(space.resolve("pattern/fade/levels").unwrap().id.0, "fade/levels");
+    assert!(space.resolve("broken/fix/missing").is_none());
+    assert!(space.resolve("broken").is_none());
+
+    // every arrow of the fixture points at a state that resolves
+    for (_, state) in space.states() {
+        for arrow in &state.arrows {
+            assert!(space.resolve(&arrow.next.0).is_some(), "unresolved {}", arrow.next.0);
+        }
+    }
+
+    let power = QualifiedStateId::parse("broken/fix/power").unwrap();
+    let mut children: Vec<&str> = space.children(&power).iter().map(|s| s.id.0.as_str()).collect();
+    children.sort();
+    assert_eq!(children, vec!["fix/power/battery", "fix/power/miscellaneous"]);
+}
+
+#[test]
+fn test_space_validate_state_ids() {
+    let space = Space::new(vec![Machine::new("default", vec![
+        State::new("fix/power", vec![Arrow::new("off", "default/fix//power")]),
+        State::new("fix//power", Vec::new()),
+    ])]);
+    assert_eq!(space.validate(), vec![
+        SpaceError::InvalidStateId {
+            machine: MachineId("default".to_string()),
+            state: StateId("fix//power".to_string()),
+            error: QualifiedStateIdError::EmptySegment,
+        },
+        SpaceError::UnresolvedArrow {
+            state: StateId("default/fix/power".to_string()),
+            arrow: ArrowId("off".to_string()),
+            next: StateId("default/fix//power".to_string()),
+        },
+    ]);
+}
@@ -0,0 +1,143 @@
+//!
+//! Test | Check
+//!
+
+use crate::common::{self, state_id};
+use elea::define::experience::representation::rust_minimal::{ArrowId, StateId};
+use elea::define::experience::representation::rust_pragmatic::Space;
+use elea::plan::check::{check_str, CheckError, Counterexample};
+use elea::plan::graph::GraphError;
+
+
+fn counterexample(space: &Space, formula: &str, initial: &[&str]) -> Counterexample {
+    let result = check_str(space, formula, initial).unwrap();
+    assert!(!result.holds, "{} holds", formula);
+    return result.counterexample.unwrap();
+}
+
+fn arrows(ids: &[&str]) -> Vec<ArrowId> {
+    return ids.iter().map(|id| ArrowId(id.to_string())).collect();
+}
+
+fn states(ids: &[&str]) -> Vec<StateId> {
+    return ids.iter().map(|id| StateId(id.to_string())).collect();
+}
+
+#[test]
+fn test_ctl_properties_that_hold() {
+    let space = common::lightswitch();
+    for formula in [
+        "AG (broken/* -> AF default/on)",
+        "AG (default/off -> AX !broken/*)",
+        "EF default/unplugged",
+        "A[!default/unplugged U (default/off | broken/*)]",
+        "EG !default/unplugged",
+    ] {
+        let result = check_str(&space, formula, &["default/on"]).unwrap();
+        assert!(result.holds, "{} does not hold", formula);
+        assert_eq!(result.counterexample, None);
+    }
+}
+
+#[test]
+fn test_ctl_counterexamples() {
+    let space = common::lightswitch();
+
+    // the shortest path to a state breaking AG
+    let path = counterexample(&space, "AG !broken/fix", &["default/off"]);
+    assert_eq!(path.prefix, arrows(&["on", "break"]));
+    assert_eq!(path.cycle, None);
+    assert_eq!(path.states, states(&["default/off", "default/on", "broken/fix"]));
+    assert_eq!(path.to_string(), "default/off -on-> default/on -break-> broken/fix");
+
+    // a cycle never reaching what AF waits for
+    let path = counterexample(&space, "AF broken/fix", &["default/on"]);
+    assert_eq!(path.prefix, arrows(&[]));
+    assert_eq!(path.cycle, Some(arrows(&["off", "on"])));
+    assert_eq!(path.to_string(), "default/on (repeat -off-> default/off -on-> default/on)");
+
+    // staying forever in a state without arrows
+    let path = counterexample(&space, "AG AF default/on", &[]);
+    assert_eq!(path.states[0], state_id("broken/fix"));
+    assert_eq!(path.prefix, arrows(&["repair", "off", "unplug"]));
+    assert_eq!(path.cycle, Some(arrows(&[])));
+    let path = counterexample(&space, "AF default/on", &["default/off"]);
+    assert_eq!(path.prefix, arrows(&["unplug"]));
+    assert_eq!(path.cycle, Some(arrows(&[])));
+    assert_eq!(path.to_string(), "default/off -unplug-> default/unplugged (stays forever)");
+
+    // existential formulas fail at the initial state
+    let path = counterexample(&space, "EF broken/*", &["default/unplugged"]);
+    assert_eq!(path.arrows(), arrows(&[]));
+    assert_eq!(path.states, states(&["default/unplugged"]));
+}
+
+#[test]
+fn test_ltl_properties() {
+    let space = common::lightswitch();
+    for formula in [
+        "G (default/off -> X !broken/*)",
+        "G (broken/fix -> X default/on)",
+        "default/on U (default/off | broken/*)",
+        "F default/off | F broken/*",
+    ] {
+        let result = check_str(&space, formula, &["default/on"]).unwrap();
+        assert!(result.holds, "{} does not hold", formula);
+    }
+
+    let path = counterexample(&space, "G (default/on -> X default/off)", &["default/on"]);
+    assert_eq!(path.to_string(), "default/on (repeat -break-> broken/fix -repair-> default/on)");
+
+    let path = counterexample(&space, "G F default/on", &["default/on"]);
+    assert_eq!(path.states.last(), Some(&state_id("default/unplugged")));
+    assert_eq!(path.cycle, Some(arrows(&[])));
+
+    // a path through off forever avoids the repair
+    let path = counterexample(&space, "G F broken/fix", &["default/on"]);
+    assert_eq!(path.prefix, arrows(&[]));
+    assert_eq!(path.cycle, Some(arrows(&["off", "on"])));
+    assert_eq!(path.states, states(&["default/on", "default/off", "default/on"]));
+}
+
+#[test]
+fn test_ltl_properties_with_many_temporal_subformulas() {
+    let space = common::lightswitch();
+    // 11 temporal subformulas, few of their truth values agree with a path
+    let formula = [
+        "G (default/off -> X !broken/*)",
+        "G (broken/fix -> X default/on)",
+        "G (default/on -> X !default/unplugged)",
+        "G (default/unplugged -> X default/unplugged)",
+        "G (default/unplugged -> X X default/unplugged)",
+    ].join(" & ");
+    let result = check_str(&space, &formula, &["default/on"]).unwrap();
+    assert!(result.holds, "{} does not hold", formula);
+
+    let path = counterexample(&space, &format!("{} & G F default/on", formula), &["default/on"]);
+    assert_eq!(path.states.last(), Some(&state_id("default/unplugged")));
+}
+
+#[test]
+fn test_check_machines_directory() {
+    let space = common::load_space("plan/weighted-lightswitch");
+
+    // zero weights still count as arrows
+    let result = check_str(&space, "EF default/unplugged", &["broken/fix"]).unwrap();
+    assert!(result.holds);
+    let path = counterexample(&space, "G !default/unplugged", &["default/on"]);
+    assert_eq!(path.to_string(), "default/on -break-> broken/fix -give_up-> default/unplugged (stays forever)");
+}
+
+#[test]
+fn test_check_errors() {
+    let space = common::lightswitch();
+    assert_eq!(
+        check_str(&space, "AG broken/fixed", &[]),
+        Err(CheckError::UnmatchedPattern("broken/fixed".to_string())),
+    );
+    assert_eq!(
+        check_str(&space, "AG default/on", &["default/missing"]),
+        Err(CheckError::Graph(GraphError::UnknownState("default/missing".to_string()))),
+    );
+    assert!(matches!(check_str(&space, "AG", &[]), Err(CheckError::Formula(_))));
+}
@@ -0,0 +1,148 @@
+//!
+//! Test | Equivalence
+//!
+
+use crate::common::{arrow_id, lightswitch, state_id};
+use elea::define::experience::representation::rust_pragmatic::{Arrow, Space};
+use elea::plan::equivalence::{self, Difference, Equivalence, EquivalenceOptions, Side};
+
+
+#[test]
+fn test_equivalent_up_to_state_renaming() {
+    let renamed = elea::space! {
+        power {
+            lit { off -> power/dark, break -> power/broken }
+            dark { on -> power/lit, unplug -> power/unplugged }
+            unplugged {}
+            broken { repair -> power/lit }
+        }
+    };
+    let equivalence = equivalence::equivalence(&lightswitch(), &renamed, EquivalenceOptions::default()).unwrap();
+    let isomorphism = match &equivalence {
+        Equivalence::Isomorphic(isomorphism) => isomorphism,
+        Equivalence::Different(difference) => panic!("{:?}", difference),
+    };
+    assert_eq!(isomorphism.states[&state_id("default/on")], state_id("power/lit"));
+    assert_eq!(isomorphism.states[&state_id("broken/fix")], state_id("power/broken"));
+    assert_eq!(isomorphism.states.len(), 4);
+    assert!(isomorphism.arrows.iter().all(|(left, right)| left == right));
+    assert!(equivalence.to_string().starts_with("equivalent\nstate broken/fix -> power/broken\n"));
+
+    let same = equivalence::equivalence(&lightswitch(), &lightswitch(), EquivalenceOptions::default()).unwrap();
+    assert!(same.is_isomorphic());
+}
+
+#[test]
+fn test_equivalent_up_to_arrow_renaming() {
+    let renamed = elea::space! {
+        default {
+            on { switch_off -> default/off, break -> broken/fix }
+            off { switch_on -> default/on, unplug -> default/unplugged }
+            unplugged {}
+        }
+        broken {
+            fix { repair -> default/on }
+        }
+    };
+    let strict = equivalence::equivalence(&lightswitch(), &renamed, EquivalenceOptions::default()).unwrap();
+    assert!(!strict.is_isomorphic());
+
+    let options = EquivalenceOptions { rename_arrows: true };
+    match equivalence::equivalence(&lightswitch(), &renamed, options).unwrap() {
+        Equivalence::Isomorphic(isomorphism) => {
+            assert_eq!(isomorphism.arrows[&arrow_id("off")], arrow_id("switch_off"));
+            assert_eq!(isomorphism.arrows[&arrow_id("on")], arrow_id("switch_on"));
+            assert_eq!(isomorphism.arrows[&arrow_id("repair")], arrow_id("repair"));
+            assert_eq!(isomorphism.states[&state_id("default/on")], state_id("default/on"));
+        },
+        Equivalence::Different(difference) => panic!("{:?}", difference),
+    }
+}
+
+#[test]
+fn test_distinguishing_sequence() {
+    // the repair leads to off instead of on
+    let changed = elea::space! {
+        default {
+            on { off -> default/off, break -> broken/fix }
+            off { on -> default/on, unplug -> default/unplugged }
+            unplugged {}
+        }
+        broken {
+            fix { repair -> default/off }
+        }
+    };
+    let equivalence = equivalence::equivalence(&lightswitch(), &changed, EquivalenceOptions::default()).unwrap();
+    assert_eq!(equivalence, Equivalence::Different(Difference::Distinguished {
+        side: Side::Left,
+        state: state_id("broken/fix"),
+        closest: state_id("broken/fix"),
+        sequence: vec![arrow_id("repair"), arrow_id("break")],
+    }));
+    assert_eq!(
+        equivalence.to_string(),
+        "not equivalent\nleft state broken/fix has no counterpart\nclosest is broken/fix, told apart by [repair, break]\n",
+    );
+}
+
+#[test]
+fn test_guards_effects_and_durations_tell_arrows_apart() {
+    // the repair of a lightswitch changed by `change`
+    let changed = |change: &dyn Fn(&mut Arrow)| {
+        let mut machines = lightswitch().machines;
+        let broken = machines.iter_mut().find(|m| m.id.0 == "broken").unwrap();
+        change(&mut broken.states[0].arrows[0]);
+        return Space::new(machines);
+    };
+    let guarded = changed(&|arrow| arrow.guard = Some("attempts < 3".to_string()));
+    let repairing = changed(&|arrow| arrow.effects = vec!["set attempts = 0".to_string()]);
+    let waiting = changed(&|arrow| arrow.after = Some("1h".to_string()));
+
+    for (changed, rename_arrows) in [(guarded, false), (repairing, false), (waiting, true)] {
+        let options = EquivalenceOptions { rename_arrows };
+        let equivalence = equivalence::equivalence(&lightswitch(), &changed, options).unwrap();
+        assert!(!equivalence.is_isomorphic(), "{}", equivalence);
+        assert!(equivalence::equivalence(&changed, &changed, options).unwrap().is_isomorphic());
+    }
+}
+
+#[test]
+fn test_unmatched_states() {
+    let doubled = elea::space! {
+        default {
+            on { off -> default/off, break -> broken/fix }
+            off { on -> default/on, unplug -> default/unplugged }
+            unplugged {}
+            gone {}
+        }
+        broken {
+            fix { repair -> default/on }
+        }
+    };
+    let equivalence = equivalence::equivalence(&lightswitch(), &doubled, EquivalenceOptions::default()).unwrap();
+    assert_eq!(equivalence, Equivalence::Different(Difference::Unmatched {
+        side: Side::Right,
+        states: vec![state_id("default/gone"), state_id("default/unplugged")],
+        counterparts: vec![state_id("default/unplugged")],
+    }));
+}
+
+#[test]
+fn test_symmetric_spaces_need_a_search() {
+    let left = elea::space! {
+        ring { a { next -> ring/b } b { next -> ring/a } c { next -> ring/d } d { next -> ring/c } }
+    };
+    let right = elea::space! {
+        loop { w { next -> loop/z } x { next -> loop/y } y { next -> loop/x } z { next -> loop/w } }
+    };
+    match equivalence::equivalence(&left, &right, EquivalenceOptions::default()).unwrap() {
+        Equivalence::Isomorphic(isomorphism) => {
+            for (l, r) in &isomorphism.states {
+                let next_l = left.resolve(&l.0).unwrap().arrows[0].next.0.clone();
+                let next_r = right.resolve(&r.0).unwrap().arrows[0].next.0.clone();
+                assert_eq!(isomorphism.states[&state_id(&next_l)], state_id(&next_r));
+            }
+        },
+        Equivalence::Different(difference) => panic!("{:?}", difference),
+    }
+}
@@ -0,0 +1,119 @@
+//!
+//! Test | Markov
+//!
+
+use crate::common::{self, state_id};
+use elea::define::experience::representation::rust_pragmatic::{
+    Arrow, Machine, Space, State
+};
+use elea::plan::graph::GraphError;
+use elea::plan::markov::MarkovChain;
+
+
+fn weighted(id: &str, next: &str, weight: f64) -> Arrow {
+    let mut arrow = Arrow::new(id, next);
+    arrow.weight = Some(weight);
+    return arrow;
+}
+
+fn assert_close(actual: f64, expected: f64) {
+    assert!((actual - expected).abs() < 1e-9, "{} is not {}", actual, expected);
+}
+
+#[test]
+fn test_expected_steps_and_hitting_probabilities() {
+    let chain = MarkovChain::new(&common::weighted_lightswitch()).unwrap();
+
+    let steps = chain.expected_steps(&["default/unplugged"]).unwrap();
+    assert_close(steps[&state_id("default/on")].unwrap(), 80.0);
+    assert_close(steps[&state_id("default/off")].unwrap(), 81.0);
+    assert_close(steps[&state_id("broken/fix")].unwrap(), 73.0);
+    // a separate recurrent class never gets there
+    assert_eq!(steps[&state_id("broken/diagnose")], None);
+
+    // from a repair, giving up may come first
+    let steps = chain.expected_steps(&["default/on"]).unwrap();
+    assert_eq!(steps[&state_id("broken/fix")], None);
+    assert_close(steps[&state_id("default/off")].unwrap(), 1.0);
+    let hitting = chain.hitting_probabilities(&["default/on"]).unwrap();
+    assert_close(hitting[&state_id("broken/fix")], 0.9);
+    assert_close(hitting[&state_id("default/unplugged")], 0.0);
+
+    assert_eq!(chain.expected_steps(&["default/missing"]), Err(GraphError::UnknownState("default/missing".to_string())));
+}
+
+#[test]
+fn test_absorption_probabilities() {
+    let space = Space::new(vec![
+        Machine::new("default", vec![
+            State::new("start", vec![weighted("left", "default/left", 1.0), weighted("right", "default/middle", 3.0)]),
+            State::new("middle", vec![weighted("back", "default/start", 1.0), weighted("right", "default/right", 1.0)]),
+            State::new("left", vec![]),
+            State::new("right", vec![]),
+        ]),
+    ]);
+    let absorption = MarkovChain::new(&space).unwrap().absorption_probabilities();
+    // l = 1/4 + 3/4 * 1/2 * l
+    let start = &absorption[&state_id("default/start")];
+    assert_close(start[&state_id("default/left")], 0.4);
+    assert_close(start[&state_id("default/right")], 0.6);
+    assert_eq!(absorption[&state_id("default/left")].len(), 1);
+}
+
+#[test]
+fn test_absorption_probabilities_of_a_long_chain() {
+    // one absorbing state per step, each leaving the chain half of the time
+    let length = 400;
+    let mut states: Vec<State> = Vec::new();
+    for index in 0..length {
+        states.push(State::new(&format!("t{}", index), vec![
+            Arrow::new("exit", &format!("default/e{}", index)),
+            Arrow::new("next", &format!("default/t{}", index + 1)),
+        ]));
+        states.push(State::new(&format!("e{}", index), vec![]));
+    }
+    states.push(State::new(&format!("t{}", length), vec![]));
+    let absorption = MarkovChain::new(&Space::new(vec![Machine::new("default", states)])).unwrap().absorption_probabilities();
+
+    let start = &absorption[&state_id("default/t0")];
+    assert_close(start[&state_id("default/e0")], 0.5);
+    assert_close(start[&state_id("default/e1")], 0.25);
+    assert_close(start.values().sum(), 1.0);
+    let last = &absorption[&state_id(&format!("default/t{}", length - 1))];
+    assert_eq!(last.len(), 2);
+    assert_close(last[&state_id(&format!("default/t{}", length))], 0.5);
+}
+
+#[test]
+fn test_recurrent_classes() {
+    let space = Space::new(vec![
+        Machine::new("default", vec![
+            State::new("a", vec![Arrow::new("next", "default/b")]),
+            State::new("b", vec![weighted("back", "default/a", 1.0), weighted("stay", "default/b", 1.0)]),
+            State::new("start", vec![Arrow::new("go", "default/a"), weighted("never", "default/end", 0.0)]),
+            State::new("end", vec![]),
+        ]),
+    ]);
+    let classes = MarkovChain::new(&space).unwrap().recurrent_classes();
+    assert_eq!(classes.len(), 2);
+    assert_eq!(classes[0].states, vec![state_id("default/a"), state_id("default/b")]);
+    assert_close(classes[0].stationary[&state_id("default/a")], 1.0 / 3.0);
+    assert_close(classes[0].stationary[&state_id("default/b")], 2.0 / 3.0);
+    assert_eq!(classes[1].states, vec![state_id("default/end")]);
+    assert_close(classes[1].stationary[&state_id("default/end")], 1.0);
+}
+
+#[test]
+fn test_recurrent_classes_of_a_long_chain() {
+    // deep enough to overflow the stack of a recursive search
+    let length = 100_000;
+    let mut states: Vec<State> = (0..length)
+        .map(|index| State::new(&format!("s{}", index), vec![Arrow::new("next", &format!("default/s{}", index + 1))]))
+        .collect();
+    states.push(State::new(&format!("s{}", length), vec![]));
+    let chain = MarkovChain::new(&Space::new(vec![Machine::new("default", states)])).unwrap();
+    assert_eq!(chain.graph().components().len(), length + 1);
+    let classes = chain.recurrent_classes();
+    assert_eq!(classes.len(), 1);
+    assert_eq!(classes[0].states, vec![state_id(&format!("default/s{}", length))]);
+}
@@ -0,0 +1,5 @@
+mod check;
+mod equivalence;
+mod markov;
+mod simulate;
+mod temporal;
@@ -0,0 +1,95 @@
+//!
+//! Test | Simulate
+//!
+
+use std::fs;
+
+use crate::common::{self, state_id};
+use elea::define::experience::representation::filesystem;
+use elea::define::experience::representation::rust_pragmatic::{Arrow, Space};
+use elea::plan::graph::GraphError;
+use elea::plan::simulate::{self, SimulationOptions};
+
+
+#[test]
+fn test_weights_are_loaded() {
+    let space = common::weighted_lightswitch();
+    let on = space.resolve("default/on").unwrap();
+    assert_eq!(on.arrow_by_id.values().map(|a| a.weight).filter(Option::is_some).count(), 1);
+    let fix = space.resolve("broken/fix").unwrap();
+    assert_eq!(fix.arrows[0].weight, Some(0.1));
+    // Tree nodes weigh the arrow leading to them
+    let diagnose = space.resolve("broken/diagnose").unwrap();
+    assert_eq!(diagnose.arrows.iter().map(|a| a.weight).collect::<Vec<_>>(), vec![None, Some(2.0)]);
+}
+
+#[test]
+fn test_invalid_weights_are_rejected_when_loaded() {
+    let arrow: Arrow = serde_yaml::from_str("{id: on, next: default/on, weight: 0.5}").unwrap();
+    assert_eq!(arrow.weight, Some(0.5));
+    for weight in ["-1", ".nan", ".inf"] {
+        let yaml = format!("{{id: on, next: default/on, weight: {}}}", weight);
+        let error = serde_yaml::from_str::<Arrow>(&yaml).unwrap_err();
+        assert!(error.to_string().contains("must be finite and not negative"), "{}", error);
+    }
+}
+
+#[test]
+fn test_invalid_tree_weights_are_rejected_when_loaded() {
+    for weight in ["-5", ".nan"] {
+        let machines_path = common::temp_asset_copy("plan/weighted-lightswitch", "tree-weight");
+        let tree = format!("id: diagnose\ntree:\n  - id: reboot\n    weight: {}\n", weight);
+        fs::write(machines_path.join("broken/diagnose.yaml"), tree).unwrap();
+        let error = format!("{:?}", filesystem::to_machines(&machines_path, filesystem::Format::NestedMachines).unwrap_err());
+        assert!(error.contains("must be finite and not negative"), "{}", error);
+        fs::remove_dir_all(&machines_path).unwrap();
+    }
+}
+
+#[test]
+fn test_simulation_is_reproducible_by_seed() {
+    let space = common::weighted_lightswitch();
+    let options = SimulationOptions { seed: 7, walks: 200, ..SimulationOptions::default() };
+    let simulation = simulate::simulate(&space, "default/on", &options).unwrap();
+    assert_eq!(simulation, simulate::simulate(&space, "default/on", &options).unwrap());
+
+    let other_seed = SimulationOptions { seed: 8, ..options };
+    assert_ne!(simulation, simulate::simulate(&space, "default/on", &other_seed).unwrap());
+}
+
+#[test]
+fn test_simulation_statistics() {
+    let space = common::weighted_lightswitch();
+    let options = SimulationOptions { seed: 1, walks: 2000, ..SimulationOptions::default() };
+    let simulation = simulate::simulate(&space, "default/on", &options).unwrap();
+
+    // the only way out is giving up a repair
+    assert_eq!(simulation.absorption_rate("default/unplugged"), 1.0);
+    assert_eq!(simulation.truncated, 0);
+    assert_eq!(simulation.path_lengths.values().sum::<u64>(), 2000);
+    assert!(!simulation.visits.contains_key(&state_id("broken/diagnose")));
+    // on -> off -> on loops three times out of four, a repair nine times out
+    // of ten, so a walk takes 80 arrows on average
+    let mean = simulation.mean_path_length();
+    assert!(mean > 70.0 && mean < 90.0, "mean path length {}", mean);
+
+    let stopped = SimulationOptions { stop_at: vec!["broken/fix".to_string()], ..options };
+    let simulation = simulate::simulate(&space, "default/on", &stopped).unwrap();
+    assert_eq!(simulation.absorption_rate("broken/fix"), 1.0);
+}
+
+#[test]
+fn test_simulation_errors() {
+    let space = common::weighted_lightswitch();
+    let options = SimulationOptions::default();
+    assert_eq!(
+        simulate::simulate(&space, "default/missing", &options).unwrap_err(),
+        GraphError::UnknownState("default/missing".to_string()),
+    );
+
+    let mut machines = space.machines.clone();
+    let on = machines[1].states.iter_mut().find(|s| s.id.0 == "on").unwrap();
+    on.arrows[0].weight = Some(-1.0);
+    let invalid = Space::new(machines);
+    assert!(matches!(simulate::simulate(&invalid, "default/on", &options), Err(GraphError::InvalidWeight { .. })));
+}
@@ -0,0 +1,41 @@
+//!
+//! Test | Temporal
+//!
+
+use elea::plan::temporal::{Formula, FormulaError, Logic};
+
+
+#[test]
+fn test_parse_precedence_and_display() {
+    let formula = Formula::parse("AG (broken/* -> EF default/on)").unwrap();
+    assert_eq!(formula.to_string(), "AG (broken/* -> EF default/on)");
+    assert_eq!(formula.logic(), Ok(Logic::Ctl));
+    assert_eq!(formula.patterns(), vec!["broken/*", "default/on"]);
+
+    // `!` binds tighter than `&`, `&` than `|`, `|` than `->`
+    let formula = Formula::parse("!a/b & a/c | a/d -> a/e").unwrap();
+    assert_eq!(formula.to_string(), "(((!a/b & a/c) | a/d) -> a/e)");
+
+    let formula: Formula = "G (default/off->X !pattern/*)".parse().unwrap();
+    assert_eq!(formula.to_string(), "G (default/off -> X !pattern/*)");
+    assert_eq!(formula.logic(), Ok(Logic::Ltl));
+
+    let formula = Formula::parse("A[!a/b U a/c & E[true U a/d]]").unwrap();
+    assert_eq!(formula.to_string(), "A[!a/b U (a/c & E[true U a/d])]");
+    assert_eq!(Formula::parse(&formula.to_string()), Ok(formula));
+}
+
+#[test]
+fn test_parse_errors() {
+    assert_eq!(Formula::parse("AG on"), Err(FormulaError::Parse {
+        position: 3,
+        message: "unknown operator or state [on]".to_string(),
+    }));
+    assert_eq!(Formula::parse("(a/b"), Err(FormulaError::Parse {
+        position: 4,
+        message: "expected [)]".to_string(),
+    }));
+    assert_eq!(Formula::parse("AG F a/b"), Err(FormulaError::MixedLogic));
+    assert!(Formula::matches("broken/*", "broken/fix"));
+    assert!(!Formula::matches("broken/fix", "broken/fix/more"));
+}
@@ -1,3 +1,5 @@
 
 mod common;
 mod define;
+mod plan;
+mod unite;
@@ -0,0 +1,148 @@
+//!
+//! Test | Journal
+//!
+
+use std::env;
+use std::fs;
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::common::{self, arrow_id, state_id};
+use elea::define::experience::language::context::{Context, Value};
+use elea::unite::journal::{self, Journal, JournalError, ReplayProblem};
+use elea::unite::runtime::{Runtime, RuntimeError};
+use elea::unite::snapshot::Snapshot;
+use elea::unite::timer::VirtualClock;
+
+
+fn runtime() -> Runtime {
+    let space = common::load_space("unite/variable-lightswitch");
+    return Runtime::new(Arc::new(space), "default/on").unwrap();
+}
+
+#[test]
+fn test_record_and_replay() {
+    let path = env::temp_dir().join(format!("elea-test-journal-{}.jsonl", std::process::id()));
+    let _ = fs::remove_file(&path);
+
+    let mut runtime = runtime();
+    let mut journal = Journal::open(&path).unwrap();
+    journal.fire(&mut runtime, "alice", "dim").unwrap();
+    journal.fire(&mut runtime, "alice", "off").unwrap();
+    assert!(matches!(journal.fire(&mut runtime, "bob", "off"), Err(JournalError::Runtime(RuntimeError::UnknownArrow { .. }))));
+    drop(journal);
+    // reopening appends
+    let entry = Journal::open(&path).unwrap().fire(&mut runtime, "bob", "on").unwrap();
+    assert_eq!(entry.agent, "bob");
+    assert_eq!(entry.from, state_id("default/off"));
+
+    let entries = Journal::read(&path).unwrap();
+    let arrows: Vec<&str> = entries.iter().map(|entry| entry.arrow.0.as_str()).collect();
+    assert_eq!(arrows, vec!["dim", "off", "on"]);
+    assert!(entries.windows(2).all(|pair| pair[0].timestamp <= pair[1].timestamp));
+
+    let replay = journal::replay(self::runtime(), &entries);
+    assert!(replay.invalid.is_empty());
+    // the replay entered the last state at its timestamp, in whole
+    // milliseconds
+    assert_eq!(Snapshot { elapsed: 0, ..replay.runtime.snapshot() }, Snapshot { elapsed: 0, ..runtime.snapshot() });
+    fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn test_entries_are_written_before_the_runtime_moves() {
+    let clock = VirtualClock::new();
+    let mut runtime = runtime().with_clock(Arc::new(clock.clone()));
+    clock.set(Duration::from_millis(1500));
+
+    let path = env::temp_dir().join(format!("elea-test-journal-clock-{}.jsonl", std::process::id()));
+    let _ = fs::remove_file(&path);
+    let entry = Journal::open(&path).unwrap().fire(&mut runtime, "alice", "dim").unwrap();
+    assert_eq!(entry.timestamp, 1500);
+    fs::remove_file(&path).unwrap();
+
+    // a journal that cannot be written leaves the runtime where it was
+    if let Ok(mut full) = Journal::open(std::path::Path::new("/dev/full")) {
+        assert!(matches!(full.fire(&mut runtime, "alice", "off"), Err(JournalError::Io { .. })));
+        assert_eq!(runtime.current(), &state_id("default/on"));
+        assert_eq!(runtime.history().len(), 1);
+    }
+}
+
+#[test]
+fn test_tick_and_replay_timed_arrows() {
+    // in `default/strobe` of the guarded lightswitch, on a virtual clock
+    let strobing = |clock: &VirtualClock| {
+        return Runtime::new(Arc::new(common::load_space("unite/guarded-lightswitch")), "default/strobe").unwrap()
+            .with_clock(Arc::new(clock.clone()))
+            .with_context(Context::from_iter([("battery_level", Value::Int(80)), ("power_saving", Value::Bool(false))]));
+    };
+    let path = env::temp_dir().join(format!("elea-test-journal-tick-{}.jsonl", std::process::id()));
+    let _ = fs::remove_file(&path);
+
+    let clock = VirtualClock::new();
+    let mut runtime = strobing(&clock);
+    let mut journal = Journal::open(&path).unwrap();
+    clock.set(Duration::from_secs(300));
+    let entries = journal.tick(&mut runtime, "timer").unwrap();
+    let stamped: Vec<(&str, u64)> = entries.iter().map(|entry| (entry.arrow.0.as_str(), entry.timestamp)).collect();
+    assert_eq!(stamped, vec![("calm", 10_000), ("sleep", 70_000)]);
+    journal.fire(&mut runtime, "alice", "on").unwrap();
+    journal.fire(&mut runtime, "alice", "strobe").unwrap();
+    drop(journal);
+
+    // timers hold as they did, and the runtime keeps its time in state
+    let entries = Journal::read(&path).unwrap();
+    let replay_clock = VirtualClock::new();
+    replay_clock.set(Duration::from_secs(305));
+    let replay = journal::replay(strobing(&replay_clock), &entries);
+    assert!(replay.invalid.is_empty());
+    assert_eq!(replay.runtime.current(), &state_id("default/strobe"));
+    assert_eq!(replay.runtime.clock().now(), Duration::from_secs(305));
+    assert_eq!(replay.runtime.next_deadline(), Some(Duration::from_secs(310)));
+
+    // sleeping 20s after the calm is too early
+    let mut early = entries.clone();
+    early[1].timestamp = 30_000;
+    let replay = journal::replay(strobing(&VirtualClock::new()), &early);
+    assert_eq!(replay.invalid[0].index, 1);
+    assert_eq!(replay.invalid[0].problem, ReplayProblem::Runtime(RuntimeError::NotDue {
+        state: state_id("default/on"),
+        arrow: arrow_id("sleep"),
+        remaining: Duration::from_secs(40),
+    }));
+    fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn test_replay_invalid_entries() {
+    let entries = Journal::read(&common::asset_path("unite/journals/variable-lightswitch.jsonl")).unwrap();
+    assert_eq!(entries.len(), 6);
+
+    let replay = journal::replay(runtime(), &entries);
+    let invalid: Vec<(usize, ReplayProblem)> = replay.invalid.iter().map(|invalid| (invalid.index, invalid.problem.clone())).collect();
+    assert_eq!(invalid, vec![
+        (2, ReplayProblem::Runtime(RuntimeError::UnknownArrow {
+            state: state_id("default/off"),
+            arrow: arrow_id("dim"),
+        })),
+        (3, ReplayProblem::ChangedArrow { found: state_id("default/on") }),
+        (4, ReplayProblem::Runtime(RuntimeError::UnknownArrow {
+            state: state_id("default/off"),
+            arrow: arrow_id("dim"),
+        })),
+        (5, ReplayProblem::Discontinuous { current: state_id("default/off") }),
+    ]);
+    assert_eq!(replay.invalid[1].to_string(), "entry 3 (default/off -on-> default/strobe): arrow now leads to [default/on]");
+    assert_eq!(replay.runtime.current(), &state_id("default/off"));
+    assert_eq!(replay.runtime.context().get("brightness"), Some(&Value::Int(0)));
+    assert_eq!(replay.runtime.history().len(), 2);
+}
+
+#[test]
+fn test_read_invalid_journal() {
+    let path = env::temp_dir().join(format!("elea-test-journal-invalid-{}.jsonl", std::process::id()));
+    fs::write(&path, "{\"timestamp\":1,\"agent\":\"a\",\"arrow\":\"dim\",\"from\":\"default/on\",\"to\":\"default/on\"}\n{\"timestamp\":2,\"ag").unwrap();
+    assert!(matches!(Journal::read(&path), Err(JournalError::Parse { line: 2, .. })));
+    fs::remove_file(&path).unwrap();
+}
@@ -0,0 +1,6 @@
+mod journal;
+mod observer;
+mod runtime;
+mod session;
+mod snapshot;
+mod timer;
@@ -0,0 +1,111 @@
+//!
+//! Test | Observer
+//!
+
+use std::sync::{Arc, Mutex};
+
+use crate::common::{self, arrow_id, state_id};
+use elea::define::experience::language::context::{Context, Value};
+use elea::define::experience::representation::rust_minimal::{MachineId, StateId};
+use elea::unite::observer::{Observer, Scope, Veto};
+use elea::unite::runtime::{Runtime, RuntimeError, Transition};
+
+
+/// Records its hooks as `name:hook:id`, vetoing arrows into `veto_enter`
+struct Recorder {
+    name: &'static str,
+    log: Arc<Mutex<Vec<String>>>,
+    veto_enter: Option<&'static str>,
+}
+
+impl Recorder {
+
+    fn new(name: &'static str, log: &Arc<Mutex<Vec<String>>>) -> Self {
+        return Self { name, log: Arc::clone(log), veto_enter: None };
+    }
+}
+
+impl Observer for Recorder {
+
+    fn allow(&mut self, transition: &Transition, _before: &Context, _after: &Context) -> Result<(), Veto> {
+        self.log.lock().unwrap().push(format!("{}:allow:{}", self.name, transition.arrow.0));
+        if self.veto_enter == Some(transition.to.0.as_str()) {
+            return Err(Veto::new("maintenance"));
+        }
+        return Ok(());
+    }
+
+    fn on_exit(&mut self, state: &StateId, _context: &Context) {
+        self.log.lock().unwrap().push(format!("{}:exit:{}", self.name, state.0));
+    }
+
+    fn on_arrow(&mut self, transition: &Transition, context: &Context) {
+        let brightness = context.get("brightness").map(|value| value.to_string()).unwrap_or_default();
+        self.log.lock().unwrap().push(format!("{}:arrow:{}:{}", self.name, transition.arrow.0, brightness));
+    }
+
+    fn on_enter(&mut self, state: &StateId, _context: &Context) {
+        self.log.lock().unwrap().push(format!("{}:enter:{}", self.name, state.0));
+    }
+}
+
+fn runtime() -> Runtime {
+    let space = common::load_space("unite/variable-lightswitch");
+    return Runtime::new(Arc::new(space), "default/on").unwrap();
+}
+
+#[test]
+fn test_hook_order() {
+    let log = Arc::new(Mutex::new(Vec::new()));
+    let mut runtime = runtime();
+    runtime.observe(Scope::State(state_id("default/off")), Recorder::new("off", &log));
+    runtime.observe(Scope::Machine(MachineId("default".to_string())), Recorder::new("machine", &log));
+    runtime.observe(Scope::Global, Recorder::new("global", &log));
+    runtime.observe(Scope::State(state_id("default/on")), Recorder::new("on", &log));
+
+    runtime.fire("off").unwrap();
+    assert_eq!(*log.lock().unwrap(), vec![
+        "global:allow:off",
+        "machine:allow:off",
+        "off:allow:off",
+        "on:allow:off",
+        "global:exit:default/on",
+        "machine:exit:default/on",
+        "on:exit:default/on",
+        "global:arrow:off:0",
+        "machine:arrow:off:0",
+        "off:arrow:off:0",
+        "on:arrow:off:0",
+        "global:enter:default/off",
+        "machine:enter:default/off",
+        "off:enter:default/off",
+    ]);
+}
+
+#[test]
+fn test_veto() {
+    let log = Arc::new(Mutex::new(Vec::new()));
+    let mut runtime = runtime();
+    let mut recorder = Recorder::new("guard", &log);
+    recorder.veto_enter = Some("default/off");
+    let id = runtime.observe(Scope::Global, recorder);
+    runtime.observe(Scope::Global, Recorder::new("late", &log));
+
+    assert_eq!(runtime.fire("off"), Err(RuntimeError::Vetoed {
+        state: state_id("default/on"),
+        arrow: arrow_id("off"),
+        reason: "maintenance".to_string(),
+    }));
+    // the veto stops the asking before any observer is told, the runtime
+    // and its context stay
+    assert_eq!(*log.lock().unwrap(), vec!["guard:allow:off"]);
+    assert_eq!(runtime.current(), &state_id("default/on"));
+    assert_eq!(runtime.context().get("brightness"), Some(&Value::Int(100)));
+    assert!(runtime.history().is_empty());
+
+    assert!(runtime.unobserve(id));
+    assert!(!runtime.unobserve(id));
+    runtime.fire("off").unwrap();
+    assert_eq!(runtime.current(), &state_id("default/off"));
+    assert_eq!(log.lock().unwrap().last().map(|entry| entry.as_str()), Some("late:enter:default/off"));
+}
@@ -0,0 +1,98 @@
+//!
+//! Test | Runtime
+//!
+
+use std::sync::Arc;
+
+use crate::common::{self, state_id};
+use elea::define::experience::language::context::{Context, Type, Value};
+use elea::define::experience::language::guard::GuardError;
+use elea::define::experience::representation::rust_minimal::{ArrowId, MachineId};
+use elea::define::experience::representation::rust_pragmatic::{
+    Machine, Space, SpaceError, State, Variable
+};
+use elea::unite::runtime::{Runtime, RuntimeError, Transition};
+
+
+fn guarded_lightswitch() -> Arc<Space> {
+    return Arc::new(common::load_space("unite/guarded-lightswitch"));
+}
+
+#[test]
+fn test_fire_unguarded_arrows() {
+    let mut runtime = Runtime::new(guarded_lightswitch(), "default/on").unwrap();
+    let transition = runtime.fire("off").unwrap();
+    assert_eq!(transition, Transition { from: state_id("default/on"), arrow: ArrowId("off".to_string()), to: state_id("default/off") });
+    runtime.fire("on").unwrap();
+    assert_eq!(runtime.current(), &state_id("default/on"));
+    assert_eq!(runtime.history().len(), 2);
+    assert_eq!(runtime.history()[1].to_string(), "default/off -on-> default/on");
+
+    assert_eq!(runtime.fire("unplug"), Err(RuntimeError::UnknownArrow {
+        state: state_id("default/on"),
+        arrow: ArrowId("unplug".to_string()),
+    }));
+    assert!(matches!(Runtime::new(guarded_lightswitch(), "default/missing"), Err(RuntimeError::UnknownState(_))));
+}
+
+#[test]
+fn test_guards_allow_and_deny() {
+    let context = Context::from_iter([("battery_level", 10)]);
+    let mut runtime = Runtime::new(guarded_lightswitch(), "default/on").unwrap().with_context(context);
+    runtime.context_mut().set("power_saving", false);
+
+    assert_eq!(runtime.fire("strobe"), Err(RuntimeError::Denied {
+        state: state_id("default/on"),
+        arrow: ArrowId("strobe".to_string()),
+        guard: "battery_level >= 20 && !power_saving".to_string(),
+    }));
+    assert_eq!(runtime.current(), &state_id("default/on"));
+    assert!(runtime.history().is_empty());
+
+    runtime.context_mut().set("battery_level", 80);
+    runtime.fire("strobe").unwrap();
+    assert_eq!(runtime.current(), &state_id("default/strobe"));
+}
+
+#[test]
+fn test_ill_typed_guards() {
+    let context = Context::from_iter([("battery_level", 80)]);
+    let mut runtime = Runtime::new(guarded_lightswitch(), "default/on").unwrap().with_context(context);
+    assert_eq!(runtime.fire("strobe"), Err(RuntimeError::Guard {
+        state: state_id("default/on"),
+        arrow: ArrowId("strobe".to_string()),
+        error: GuardError::UnknownVariable("power_saving".to_string()),
+    }));
+
+    runtime.context_mut().set("brightness", 40);
+    runtime.context_mut().set("power_saving", true);
+    assert!(matches!(
+        runtime.enabled_arrows(),
+        Err(RuntimeError::Guard { error: GuardError::TypeMismatch { .. }, .. })
+    ));
+    runtime.context_mut().set("brightness", "max");
+    let enabled: Vec<&str> = runtime.enabled_arrows().unwrap().iter().map(|arrow| arrow.id.0.as_str()).collect();
+    assert_eq!(enabled, vec!["dim", "off"]);
+    assert_eq!(runtime.context().get("brightness").map(|value| value.type_of()), Some(Type::Text));
+}
+
+#[test]
+fn test_conflicting_variables_are_rejected() {
+    let mut counter = Machine::new("counter", vec![State::new("idle", Vec::new())]);
+    counter.variables.insert("level".to_string(), Variable { type_: Type::Int, default: Value::Int(0) });
+    let mut switch = Machine::new("switch", vec![State::new("off", Vec::new())]);
+    switch.variables.insert("level".to_string(), Variable { type_: Type::Bool, default: Value::Bool(false) });
+    let space = Arc::new(Space::new(vec![counter, switch]));
+
+    let conflict = SpaceError::ConflictingVariable {
+        name: "level".to_string(),
+        first: MachineId("counter".to_string()),
+        second: MachineId("switch".to_string()),
+    };
+    assert_eq!(space.validate(), vec![conflict]);
+    assert_eq!(Runtime::new(Arc::clone(&space), "counter/idle").err(), Some(RuntimeError::ConflictingVariable {
+        name: "level".to_string(),
+        first: MachineId("counter".to_string()),
+        second: MachineId("switch".to_string()),
+    }));
+}
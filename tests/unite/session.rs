@@ -0,0 +1,81 @@
+//!
+//! Test | Session
+//!
+
+use std::sync::Arc;
+use std::thread;
+
+use crate::common::{self, state_id};
+use elea::define::experience::language::context::Value;
+use elea::define::experience::representation::rust_pragmatic::Space;
+use elea::unite::runtime::{Runtime, RuntimeError};
+use elea::unite::session::{SessionError, SessionManager};
+
+
+fn space() -> Arc<Space> {
+    return Arc::new(common::load_space("unite/variable-lightswitch"));
+}
+
+#[test]
+fn test_concurrent_instances() {
+    let manager = SessionManager::new(space());
+    for index in 0..1000 {
+        manager.create(&format!("switch-{:04}", index), "default/on").unwrap();
+    }
+    assert_eq!(manager.len(), 1000);
+
+    // each thread dims its own instances, then turns every other one off
+    thread::scope(|scope| {
+        for worker in 0..8 {
+            let manager = &manager;
+            scope.spawn(move || {
+                for index in (worker..1000).step_by(8) {
+                    let id = format!("switch-{:04}", index);
+                    manager.fire(&id, "dim").unwrap();
+                    if index % 2 == 0 {
+                        manager.fire(&id, "off").unwrap();
+                    }
+                }
+            });
+        }
+    });
+
+    assert_eq!(manager.in_state("default/off").unwrap().len(), 500);
+    assert_eq!(manager.in_state("default").unwrap().len(), 1000);
+    assert_eq!(manager.in_state("default/on").unwrap()[..2], ["switch-0001".to_string(), "switch-0003".to_string()]);
+    let dimmed = manager.filter(|runtime| runtime.context().get("brightness") == Some(&Value::Int(50)));
+    assert_eq!(dimmed.len(), 500);
+    assert_eq!(manager.with("switch-0002", |runtime| runtime.history().len()), Ok(2));
+}
+
+#[test]
+fn test_sessions() {
+    let space = space();
+    let manager = SessionManager::new(Arc::clone(&space));
+    manager.create("hall", "default/on").unwrap();
+    manager.create("kitchen", "default/off").unwrap();
+
+    assert!(matches!(manager.create("hall", "default/off"), Err(SessionError::Exists(_))));
+    assert!(matches!(manager.create("porch", "default/dim"), Err(SessionError::Runtime(RuntimeError::UnknownState(_)))));
+    assert!(matches!(manager.fire("porch", "on"), Err(SessionError::UnknownSession(_))));
+    assert!(matches!(manager.in_state("default//on"), Err(SessionError::InvalidState(_))));
+    assert!(matches!(manager.fire("kitchen", "dim"), Err(SessionError::Runtime(RuntimeError::UnknownArrow { .. }))));
+
+    // runtimes of another Space are refused, even an equal one
+    let foreign = Runtime::new(self::space(), "default/on").unwrap();
+    assert_eq!(manager.insert("porch", foreign).err(), Some(SessionError::ForeignSpace("porch".to_string())));
+    manager.insert("porch", Runtime::new(space, "default/on").unwrap()).unwrap();
+    assert_eq!(manager.ids(), vec!["hall", "kitchen", "porch"]);
+
+    // an evicted session lives on apart from the manager
+    let kitchen = manager.evict("kitchen").unwrap();
+    assert!(manager.evict("kitchen").is_none());
+    kitchen.lock().unwrap().fire("on").unwrap();
+    assert_eq!(manager.in_state("default/on").unwrap(), vec!["hall", "porch"]);
+
+    manager.fire("porch", "off").unwrap();
+    let evicted = manager.evict_where(|runtime| runtime.current() == &state_id("default/off"));
+    assert_eq!(evicted, vec!["porch"]);
+    assert_eq!(manager.ids(), vec!["hall"]);
+    assert!(!manager.is_empty());
+}
@@ -0,0 +1,125 @@
+//!
+//! Test | Snapshot
+//!
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::common::{self, state_id};
+use elea::define::experience::language::context::{Type, Value};
+use elea::define::experience::representation::filesystem;
+use elea::define::experience::representation::rust_minimal::ArrowId;
+use elea::define::experience::representation::rust_pragmatic::{
+    Arrow, Machine, Space, State, Variable
+};
+use elea::unite::runtime::Runtime;
+use elea::unite::snapshot::{Snapshot, SnapshotError, SnapshotProblem};
+use elea::unite::timer::VirtualClock;
+
+
+fn machines() -> Vec<Machine> {
+    let machines_path = common::asset_path("unite/variable-lightswitch");
+    return filesystem::to_machines(&machines_path, filesystem::Format::NestedMachines).unwrap();
+}
+
+/// A runtime that dimmed, turned off and on, and dimmed again, then stayed
+/// 5s, on a virtual clock
+fn used_runtime() -> Runtime {
+    let clock = VirtualClock::new();
+    let mut runtime = Runtime::new(Arc::new(Space::new(machines())), "default/on").unwrap()
+        .with_clock(Arc::new(clock.clone()));
+    for arrow in ["dim", "off", "on", "dim"] {
+        clock.advance(Duration::from_secs(1));
+        runtime.fire(arrow).unwrap();
+    }
+    clock.advance(Duration::from_secs(5));
+    return runtime;
+}
+
+#[test]
+fn test_round_trip() {
+    let runtime = used_runtime();
+    let snapshot = runtime.snapshot();
+    assert_eq!(snapshot.history.len(), 4);
+    assert_eq!(snapshot.elapsed, 5000);
+    assert_eq!(snapshot.context.get("temperature"), Some(&Value::Float(21.0)));
+
+    for restored in [
+        Snapshot::from_json(&snapshot.to_json().unwrap()).unwrap(),
+        Snapshot::from_yaml(&snapshot.to_yaml().unwrap()).unwrap(),
+    ] {
+        assert_eq!(restored, snapshot);
+        let mut runtime = Runtime::restore(Arc::new(Space::new(machines())), restored).unwrap();
+        // on the system clock, time went on since
+        let elapsed = runtime.snapshot().elapsed;
+        assert!((5000..6000).contains(&elapsed), "{}", elapsed);
+        assert_eq!(Snapshot { elapsed: snapshot.elapsed, ..runtime.snapshot() }, snapshot);
+        runtime.fire("dim").unwrap();
+        assert_eq!(runtime.context().get("brightness"), Some(&Value::Int(25)));
+        assert_eq!(runtime.history().len(), 5);
+    }
+
+    assert!(matches!(Snapshot::from_json("{\"current\": 3}"), Err(SnapshotError::Format(_))));
+    // snapshots without the time in state are just entered
+    let json = "{\"current\": \"default/on\", \"context\": {}, \"history\": []}";
+    assert_eq!(Snapshot::from_json(json).unwrap().elapsed, 0);
+}
+
+#[test]
+fn test_restore_in_changed_space() {
+    let snapshot = used_runtime().snapshot();
+
+    // `off` now leads to `on`, `brightness` became text, and a variable was
+    // added
+    let mut machines = machines();
+    let mut states = machines[0].states.clone();
+    let on = states.iter_mut().find(|state| state.id.0 == "on").unwrap();
+    *on = State::new("on", vec![Arrow::new("dim", "default/on"), Arrow::new("off", "default/on")]);
+    let mut machine = Machine::new("default", states);
+    machine.variables = machines[0].variables.clone();
+    machine.variables.insert("brightness".to_string(), Variable { type_: Type::Text, default: Value::from("full") });
+    machine.variables.insert("color".to_string(), Variable { type_: Type::Text, default: Value::from("white") });
+    machines[0] = machine;
+
+    let space = Space::new(machines);
+    assert_eq!(snapshot.validate(&space), vec![
+        SnapshotProblem::ChangedArrow {
+            index: 1,
+            arrow: ArrowId("off".to_string()),
+            recorded: state_id("default/off"),
+            found: state_id("default/on"),
+        },
+        SnapshotProblem::VariableType { variable: "brightness".to_string(), expected: Type::Text, found: Type::Int },
+    ]);
+    let error = Runtime::restore(Arc::new(space), snapshot.clone()).err().unwrap();
+    assert_eq!(
+        error.to_string(),
+        "snapshot does not match the space: transition 1: arrow [off] leads to [default/on], not [default/off], \
+        variable [brightness] is int, not text"
+    );
+
+    // new variables take their defaults
+    let mut machines = self::machines();
+    machines[0].variables.insert("color".to_string(), Variable { type_: Type::Text, default: Value::from("white") });
+    let runtime = Runtime::restore(Arc::new(Space::new(machines)), snapshot).unwrap();
+    assert_eq!(runtime.context().get("color"), Some(&Value::from("white")));
+    assert_eq!(runtime.current(), &state_id("default/on"));
+}
+
+#[test]
+fn test_restore_unknown_states() {
+    let mut snapshot = used_runtime().snapshot();
+    snapshot.current = state_id("default/broken");
+    snapshot.history[3].arrow = ArrowId("flicker".to_string());
+    snapshot.history[0].from = state_id("default/nowhere");
+    snapshot.history[2].from = state_id("default/on");
+
+    let problems = snapshot.validate(&Space::new(machines()));
+    assert_eq!(problems, vec![
+        SnapshotProblem::UnknownState { index: 0, state: state_id("default/nowhere") },
+        SnapshotProblem::Discontinuous { index: 2 },
+        SnapshotProblem::UnknownArrow { index: 2, state: state_id("default/on"), arrow: ArrowId("on".to_string()) },
+        SnapshotProblem::UnknownArrow { index: 3, state: state_id("default/on"), arrow: ArrowId("flicker".to_string()) },
+        SnapshotProblem::UnknownCurrent(state_id("default/broken")),
+    ]);
+}
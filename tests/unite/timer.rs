@@ -0,0 +1,105 @@
+//!
+//! Test | Timer
+//!
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::common::{self, state_id};
+use elea::define::experience::language::context::Context;
+use elea::define::experience::lint::{self, LintConfig};
+use elea::define::experience::representation::rust_minimal::ArrowId;
+use elea::define::experience::representation::rust_pragmatic::{
+    Arrow, Machine, Space, State
+};
+use elea::unite::runtime::{Runtime, RuntimeError};
+use elea::unite::timer::{Clock, VirtualClock};
+
+
+/// A runtime in `default/strobe` of the guarded lightswitch, on a virtual
+/// clock
+fn strobing() -> (Runtime, VirtualClock) {
+    let space = common::load_space("unite/guarded-lightswitch");
+    let clock = VirtualClock::new();
+    let runtime = Runtime::new(Arc::new(space), "default/strobe").unwrap()
+        .with_clock(Arc::new(clock.clone()))
+        .with_context(Context::from_iter([("battery_level", 80)]));
+    return (runtime, clock);
+}
+
+#[test]
+fn test_timed_arrows() {
+    let (mut runtime, clock) = strobing();
+    assert_eq!(runtime.next_deadline(), Some(Duration::from_secs(10)));
+    assert!(runtime.tick().unwrap().is_empty());
+    assert!(runtime.enabled_arrows().unwrap().is_empty());
+
+    clock.advance(Duration::from_secs(4));
+    assert_eq!(runtime.fire("calm"), Err(RuntimeError::NotDue {
+        state: state_id("default/strobe"),
+        arrow: ArrowId("calm".to_string()),
+        remaining: Duration::from_secs(6),
+    }));
+
+    clock.advance(Duration::from_secs(6));
+    let transitions = runtime.tick().unwrap();
+    assert_eq!(transitions.len(), 1);
+    assert_eq!(transitions[0].to_string(), "default/strobe -calm-> default/on");
+    assert_eq!(runtime.entered_at(), Duration::from_secs(10));
+    assert_eq!(runtime.next_deadline(), Some(Duration::from_secs(70)));
+}
+
+#[test]
+fn test_tick_takes_arrows_on_time() {
+    // calm at 10s, sleep at 1m10s, then off has no timed arrows
+    let (mut runtime, clock) = strobing();
+    clock.set(Duration::from_secs(300));
+    let arrows: Vec<String> = runtime.tick().unwrap().iter().map(|transition| transition.arrow.0.clone()).collect();
+    assert_eq!(arrows, vec!["calm", "sleep"]);
+    assert_eq!(runtime.current(), &state_id("default/off"));
+    assert_eq!(runtime.entered_at(), Duration::from_secs(70));
+    assert_eq!(runtime.next_deadline(), None);
+    assert_eq!(clock.now(), Duration::from_secs(300));
+
+    // taking an arrow by hand restarts the state's time
+    runtime.fire("on").unwrap();
+    assert_eq!(runtime.entered_at(), Duration::from_secs(300));
+    clock.advance(Duration::from_secs(59));
+    assert!(runtime.tick().unwrap().is_empty());
+}
+
+#[test]
+fn test_guarded_timed_arrows() {
+    let mut sleep = Arrow::new("sleep", "default/off");
+    sleep.after = Some("1m".to_string());
+    sleep.guard = Some("idle".to_string());
+    let mut standby = Arrow::new("standby", "default/standby");
+    standby.after = Some("1m".to_string());
+    let space = Arc::new(Space::new(vec![Machine::new("default", vec![
+        State::new("on", vec![sleep, standby]),
+        State::new("off", Vec::new()),
+        State::new("standby", Vec::new()),
+    ])]));
+
+    // both come due at once, `sleep` is first unless its guard is false
+    for (idle, taken) in [(true, "sleep"), (false, "standby")] {
+        let clock = VirtualClock::new();
+        let mut runtime = Runtime::new(Arc::clone(&space), "default/on").unwrap()
+            .with_clock(Arc::new(clock.clone()))
+            .with_context(Context::from_iter([("idle", idle)]));
+        clock.advance(Duration::from_secs(60));
+        let transitions = runtime.tick().unwrap();
+        assert_eq!(transitions.len(), 1);
+        assert_eq!(transitions[0].arrow, ArrowId(taken.to_string()));
+    }
+}
+
+#[test]
+fn test_lint_invalid_timers() {
+    let mut arrow = Arrow::new("calm", "default/on");
+    arrow.after = Some("10sec".to_string());
+    let space = Space::new(vec![Machine::new("default", vec![State::new("on", vec![arrow])])]);
+    let findings = lint::lint(&space, &LintConfig::default());
+    let finding = findings.iter().find(|finding| finding.rule == "invalid_timer").unwrap();
+    assert_eq!(finding.message, "duration of arrow [calm] is invalid: at 2: unknown unit [sec]");
+}